@@ -1,149 +1,3847 @@
-use syn::{File, ItemFn, Expr, ExprUnsafe, ExprMethodCall, Pat, Local, visit::{self, Visit}, Lit, ExprLit};
+use syn::{File, ItemFn, ItemConst, ItemStruct, ItemMod, ItemImpl, ItemTrait, ImplItemFn, TraitItemFn, ExprClosure, Expr, ExprAssign, ExprCall, ExprLoop, ExprUnary, UnOp, ExprUnsafe, ExprMethodCall, ExprIf, ExprWhile, ExprMatch, ExprForLoop, Block, Stmt, Pat, Local, Type, FnArg, visit::{self, Visit}, Lit, ExprLit, BinOp, spanned::Spanned};
+use quote::ToTokens;
 use std::collections::HashMap;
+use std::ops::Range;
+use crate::interval::{self, FunctionRanges, Interval};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct OverflowCandidate {
     pub location: String,
     pub buffer_name: String,
+    /// Raw pointer the access goes through, when the operation is pointer arithmetic.
+    pub pointer_name: Option<String>,
     pub operation: String,
     pub line: usize,
     pub column: usize,
     pub buffer_size: Option<usize>,
+    /// Source text of the buffer length when it could not be evaluated to a constant.
+    pub buffer_size_expr: Option<String>,
     pub offset: Option<usize>,
+    /// Source text of the offset when it is not a literal, e.g. `offset` or `i + 1`.
+    pub offset_expr: Option<String>,
+    /// Number of elements accessed from `offset` on, when the access covers a range,
+    /// e.g. `n` for `slice::from_raw_parts(ptr, n)` or `b - (a)` for `get_unchecked(a..b)`.
+    pub count_expr: Option<String>,
+    /// Method or function performing the access, e.g. `sub`, `byte_offset` or
+    /// `get_unchecked_mut`; `add` when unset.
+    pub pointer_method: Option<String>,
+    /// The other buffer of a two-pointer operation: the origin of `offset_from`, or the
+    /// other side of a copy. For `double_reclaim`: the buffer already owning the memory.
+    pub origin_buffer: Option<String>,
+    /// For a copy: the offset of the other side's pointer into `origin_buffer`, when it is
+    /// counted in the buffer's own elements.
+    pub origin_offset: Option<String>,
+    /// For `dealloc_layout`: the alignment of the layout passed in, and of the one the
+    /// memory was allocated with, e.g. `8` and `1`.
+    pub layout_align: Option<(String, String)>,
+    /// Element type of the buffer, e.g. `u8` for `vec![0u8; 10]`.
+    pub buffer_element_type: Option<String>,
+    /// Type the pointer addresses when it was cast away from the buffer's element type,
+    /// e.g. `u32` for `buf.as_mut_ptr() as *mut u32`. The offset counts these elements.
+    pub element_type: Option<String>,
+    /// For `use_after_free`: the operation after which the pointer dangles. For
+    /// `double_reclaim`: the operation that gave the memory its current owner.
+    pub invalidation: Option<Invalidation>,
+    /// Conditions (Rust boolean expressions) that hold whenever the access executes:
+    /// enclosing `if`/`while`/`match` guards, earlier early returns and `assert!`s.
+    pub path_conditions: Vec<String>,
+    /// Facts about the variables in scope, e.g. `i >= 0` for a `usize` parameter or
+    /// `idx == n + 1` for `let idx = n + 1`.
+    pub assumptions: Vec<String>,
+    pub span: SourceSpan,
+    /// For an access in a callee checked against a buffer passed in by a caller, which is
+    /// reported at the call passing the buffer: the access, and the function containing it.
+    pub callee_span: Option<SourceSpan>,
+    pub callee: Option<String>,
+    /// For a call-site candidate: the callee's locals its offset or count mentions, as
+    /// renamed in `offset_expr`, e.g. `write_at_j`. They cannot be named at the call.
+    pub callee_locals: Vec<String>,
+    /// Values the offset takes where the access executes, from the interval analysis of
+    /// the enclosing function, e.g. `[0, 10]` for `p.add(i)` in `for i in 0..=10`.
+    pub offset_range: Option<Interval>,
+}
+
+/// An operation that frees or moves a buffer, leaving the pointers into it dangling.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Invalidation {
+    /// `drop`, `end of scope`, or the reallocating method, e.g. `push`; `allocation` or
+    /// e.g. `Box::from_raw` for an owner.
+    pub operation: String,
+    pub line: usize,
+    /// Whether the buffer is gone on every path, rather than only when it has to grow or a
+    /// branch is taken.
+    pub certain: bool,
+    /// Path conditions under which the operation ran, e.g. `flag` inside `if flag`.
+    pub conditions: Vec<String>,
+}
+
+/// Position of a candidate in the analyzed source file.
+///
+/// Lines are 1-based and columns 0-based, as reported by `proc_macro2`; `byte_range`
+/// indexes into the file contents.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceSpan {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub byte_range: Range<usize>,
+}
+
+impl SourceSpan {
+    pub fn from_span(span: proc_macro2::Span) -> Self {
+        let start = span.start();
+        let end = span.end();
+        SourceSpan {
+            start_line: start.line,
+            start_column: start.column,
+            end_line: end.line,
+            end_column: end.column,
+            byte_range: span.byte_range(),
+        }
+    }
+
+    /// Human readable position, e.g. `Line 5, Column 10` (column counted from 1).
+    pub fn location(&self) -> String {
+        format!("Line {}, Column {}", self.start_line, self.start_column + 1)
+    }
+
+    /// Returns the source text covered by the span, if it lies within `source`.
+    pub fn snippet<'s>(&self, source: &'s str) -> Option<&'s str> {
+        if self.byte_range.is_empty() {
+            return None;
+        }
+        source.get(self.byte_range.clone())
+    }
+}
+
+/// Length of a `vec!` allocation as far as it can be determined statically.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BufferLen {
+    Known(usize),
+    Symbolic(String),
+}
+
+impl std::fmt::Display for BufferLen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BufferLen::Known(len) => write!(f, "{}", len),
+            BufferLen::Symbolic(expr) => write!(f, "{}", expr),
+        }
+    }
+}
+
+/// Size of a `std::alloc::Layout` as a number of elements of a type: `n` of `T` for
+/// `Layout::array::<T>(n)`, one `T` for `Layout::new::<T>()`, and bytes for
+/// `Layout::from_size_align(size, align)`.
+#[derive(Debug, Clone, PartialEq)]
+struct HeapLayout {
+    len: BufferLen,
+    element_type: String,
+    /// Alignment in bytes, e.g. `align` or `std::mem::align_of::<T>()`.
+    align: BufferLen,
+}
+
+/// Whether a `Vec` method moved the buffer to a new allocation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Reallocation {
+    None,
+    Possible,
+    Certain,
+}
+
+/// Capacity of a `Vec` relative to its length.
+#[derive(Debug, Clone, PartialEq)]
+enum Capacity {
+    /// Exactly its length, as allocated by `vec!`.
+    Len,
+    /// Tracked on its own, e.g. `n` after `Vec::with_capacity(n)`.
+    Known(BufferLen),
+    /// Reallocated by a growing operation to at least the given length.
+    AtLeast(Option<BufferLen>),
+}
+
+/// Who frees an allocation, for the ownership transfers of `Box::into_raw`/`from_raw` and
+/// `Vec::into_raw_parts`/`from_raw_parts`.
+#[derive(Debug, Clone, PartialEq)]
+enum Ownership {
+    /// Freed when `owner` is dropped, or at the end of the statement when the value was
+    /// not bound. `operation` at `line` made it the owner, e.g. `Box::from_raw`, under the
+    /// path conditions `conditions`.
+    Owned { owner: Option<String>, operation: String, line: usize, conditions: Vec<String> },
+    /// Released to a raw pointer, e.g. by `Box::into_raw` or `mem::forget`, so that
+    /// nothing frees it until it is reclaimed.
+    Raw,
 }
 
 #[derive(Debug, Clone)]
 struct PointerInfo {
     buffer_name: String,
+    /// Length of the buffer, i.e. its initialized elements.
     buffer_size: Option<usize>,
+    size_expr: Option<String>,
+    capacity: Capacity,
+    buffer_element_type: Option<String>,
+    /// Pointee type after a cast; `None` while it is still the buffer's element type.
+    element_type: Option<String>,
+    /// Number of enclosing branches and loops where the buffer or pointer was defined.
+    depth: usize,
+    /// Set once the buffer was freed or moved after the pointer was taken.
+    invalidation: Option<Invalidation>,
+    /// Identifies the allocation, so that pointers into it are found even when its name
+    /// is shadowed; 0 for buffers that are not tracked.
+    allocation: usize,
+    /// Offset of the pointer from the start of the allocation, in elements, for pointers
+    /// derived with `add`/`sub`/`offset`.
+    base_offset: Option<BufferLen>,
+    /// Alignment of the `Layout` the memory was allocated with by `alloc` or `realloc`.
+    align: Option<BufferLen>,
+}
+
+impl PointerInfo {
+    fn from_len(buffer_name: String, len: Option<BufferLen>, buffer_element_type: Option<String>) -> Self {
+        let mut info = PointerInfo {
+            buffer_name,
+            buffer_size: None,
+            size_expr: None,
+            capacity: Capacity::Len,
+            buffer_element_type,
+            element_type: None,
+            depth: 0,
+            invalidation: None,
+            allocation: 0,
+            base_offset: None,
+            align: None,
+        };
+        info.set_len(len);
+        info
+    }
+
+    fn cast_to(mut self, element_type: String) -> Option<Self> {
+        let accessed = self.element_type.as_ref().or(self.buffer_element_type.as_ref());
+        // An offset counted in elements of another type cannot be carried over.
+        if self.base_offset.is_some() && accessed != Some(&element_type) {
+            return None;
+        }
+        self.element_type = (self.buffer_element_type.as_ref() != Some(&element_type)).then_some(element_type);
+        Some(self)
+    }
+
+    /// Whether nothing is known about the length but its name, as for a slice parameter.
+    fn len_is_unknown(&self) -> bool {
+        match self.len() {
+            None => true,
+            Some(BufferLen::Symbolic(len)) => len == format!("{}.len()", self.buffer_name),
+            Some(BufferLen::Known(_)) => false,
+        }
+    }
+
+    /// Offset from the start of the allocation, 0 for a pointer to its start.
+    fn base(&self) -> BufferLen {
+        self.base_offset.clone().unwrap_or(BufferLen::Known(0))
+    }
+
+    fn same_allocation(&self, other: &PointerInfo) -> bool {
+        match (self.allocation, other.allocation) {
+            (0, _) | (_, 0) => self.buffer_name == other.buffer_name,
+            (allocation, other) => allocation == other,
+        }
+    }
+
+    /// The pointer moved by `method(arg)`; `None` for the `byte_` methods, whose offsets
+    /// are not counted in elements.
+    fn moved(mut self, method: &str, arg: &Expr) -> Option<Self> {
+        if method.contains("byte_") {
+            return None;
+        }
+        self.base_offset = Some(moved_offset(&self.base(), method, arg));
+        Some(self)
+    }
+
+    fn len(&self) -> Option<BufferLen> {
+        match (self.buffer_size, &self.size_expr) {
+            (Some(len), _) => Some(BufferLen::Known(len)),
+            (None, Some(expr)) => Some(BufferLen::Symbolic(expr.clone())),
+            (None, None) => None,
+        }
+    }
+
+    fn set_len(&mut self, len: Option<BufferLen>) {
+        (self.buffer_size, self.size_expr) = match len {
+            Some(BufferLen::Known(n)) => (Some(n), None),
+            Some(BufferLen::Symbolic(expr)) => (None, Some(expr)),
+            None => (None, None),
+        };
+    }
+
+    /// Size of the allocation in elements, with a fact bounding it when it is only known
+    /// to be at least some length.
+    fn allocation(&self) -> (Option<BufferLen>, Option<String>) {
+        match &self.capacity {
+            Capacity::Len => (self.len(), None),
+            Capacity::Known(capacity) => (Some(capacity.clone()), None),
+            Capacity::AtLeast(None) => (None, None),
+            Capacity::AtLeast(Some(len)) => {
+                let capacity = format!("{}.capacity()", self.buffer_name);
+                let fact = format!("{} >= {}", capacity, len);
+                (Some(BufferLen::Symbolic(capacity)), Some(fact))
+            }
+        }
+    }
+
+    /// Applies a length-changing `Vec` method such as `push`, `truncate` or `reserve`.
+    /// Returns whether it moved the buffer, or `None` for methods that are not modelled.
+    fn apply(&mut self, method: &str, arg: Option<BufferLen>, conditional: bool) -> Option<Reallocation> {
+        let len = self.len();
+        // `needed` is the number of elements the allocation has to hold afterwards.
+        let (new_len, needed) = match method {
+            "push" | "insert" => {
+                let new_len = add_len(len.clone(), BufferLen::Known(1));
+                (new_len.clone(), Some(new_len))
+            }
+            "extend_from_slice" => {
+                let new_len = arg.and_then(|extra| add_len(len.clone(), extra));
+                (new_len.clone(), Some(new_len))
+            }
+            "resize" => (arg.clone(), Some(arg)),
+            // Reserving room for `additional` more elements keeps the length.
+            "reserve" | "reserve_exact" => (len.clone(), Some(arg.and_then(|extra| add_len(len.clone(), extra)))),
+            "truncate" => (match (&len, arg) {
+                (Some(BufferLen::Known(len)), Some(BufferLen::Known(n))) => Some(BufferLen::Known(n.min(*len))),
+                _ => None,
+            }, None),
+            "clear" => (Some(BufferLen::Known(0)), None),
+            "set_len" => (arg, None),
+            "pop" => (match &len {
+                Some(BufferLen::Known(len)) => Some(BufferLen::Known(len.saturating_sub(1))),
+                _ => None,
+            }, None),
+            "shrink_to_fit" => (len.clone(), None),
+            _ => return None,
+        };
+
+        // Shrinking keeps the allocation, which may now be larger than the length.
+        if self.capacity == Capacity::Len {
+            self.capacity = match &len {
+                Some(len) => Capacity::Known(len.clone()),
+                None => Capacity::AtLeast(None),
+            };
+        }
+        let mut reallocation = Reallocation::None;
+        if let Some(needed) = needed {
+            match self.fits(needed.as_ref()) {
+                Some(true) => {}
+                fits => {
+                    reallocation = if fits == Some(false) && !conditional { Reallocation::Certain } else { Reallocation::Possible };
+                    self.capacity = if conditional { Capacity::AtLeast(None) } else { Capacity::AtLeast(needed) };
+                }
+            }
+        }
+        if method == "shrink_to_fit"
+            && !matches!((&self.capacity, &len), (Capacity::Known(capacity), Some(len)) if capacity == len)
+        {
+            // The spare capacity is released by moving the elements.
+            reallocation = Reallocation::Possible;
+            self.capacity = match len.clone() {
+                Some(len) if !conditional => Capacity::Known(len),
+                len => Capacity::AtLeast(len),
+            };
+        }
+        // A method that may not run leaves the length unknown.
+        self.set_len(if conditional && new_len != len { None } else { new_len });
+        Some(reallocation)
+    }
+
+    /// Applies a `Vec` method an unknown number of times, as in a loop: the length is no
+    /// longer known, and a method that may grow the buffer may also move it to an
+    /// allocation of unknown capacity.
+    fn apply_repeatedly(&mut self, method: &str) -> Option<Reallocation> {
+        let reallocation = match method {
+            "push" | "insert" | "extend_from_slice" | "resize" | "reserve" | "reserve_exact" | "shrink_to_fit" => Reallocation::Possible,
+            "truncate" | "clear" | "set_len" | "pop" => Reallocation::None,
+            _ => return None,
+        };
+        self.capacity = match (reallocation, &self.capacity) {
+            (Reallocation::Possible, _) => Capacity::AtLeast(None),
+            // Shrinking keeps the allocation.
+            (_, Capacity::Len) => self.len().map_or(Capacity::AtLeast(None), Capacity::Known),
+            (_, capacity) => capacity.clone(),
+        };
+        self.set_len(None);
+        Some(reallocation)
+    }
+
+    /// Whether `len` elements fit within the current capacity without reallocating, when
+    /// that can be told.
+    fn fits(&self, len: Option<&BufferLen>) -> Option<bool> {
+        match (&self.capacity, len?) {
+            (Capacity::Known(BufferLen::Known(capacity)), BufferLen::Known(len)) => Some(len <= capacity),
+            (Capacity::AtLeast(Some(BufferLen::Known(capacity))), BufferLen::Known(len)) => (len <= capacity).then_some(true),
+            (Capacity::Known(capacity) | Capacity::AtLeast(Some(capacity)), len) => (capacity == len).then_some(true),
+            _ => None,
+        }
+    }
+}
+
+/// `base` moved by `method(arg)`: forwards for `add` and `offset`, backwards for `sub`
+/// and for `offset` by a negated step, e.g. `offset(-n)`. Constant offsets are folded; a
+/// known offset moved back past the start of the buffer becomes the negative offset.
+fn moved_offset(base: &BufferLen, method: &str, arg: &Expr) -> BufferLen {
+    let (negated, arg) = match arg {
+        Expr::Unary(ExprUnary { op: UnOp::Neg(_), expr, .. }) if method.ends_with("offset") => (true, &**expr),
+        arg => (false, arg),
+    };
+    let backwards = negated || method.ends_with("sub");
+    let text = expr_text(arg);
+    let step = match extract_offset(arg) {
+        Some(step) => BufferLen::Known(step),
+        None if text.contains(' ') => BufferLen::Symbolic(format!("({})", text)),
+        None => BufferLen::Symbolic(text),
+    };
+    match (base, step, backwards) {
+        (BufferLen::Known(0), step, false) => step,
+        (BufferLen::Known(base), BufferLen::Known(step), false) if base.checked_add(step).is_some() => BufferLen::Known(base + step),
+        (BufferLen::Known(base), BufferLen::Known(step), true) if *base >= step => BufferLen::Known(base - step),
+        (BufferLen::Known(base), BufferLen::Known(step), true) => BufferLen::Symbolic(format!("-{}", step - base)),
+        (base, step, false) => BufferLen::Symbolic(format!("{} + {}", base, step)),
+        (base, step, true) => BufferLen::Symbolic(format!("{} - {}", base, step)),
+    }
+}
+
+/// Sets the offset of an access that starts `offset` elements into its buffer.
+fn set_offset(candidate: &mut OverflowCandidate, offset: BufferLen) {
+    match offset {
+        BufferLen::Known(offset) => candidate.offset = Some(offset),
+        BufferLen::Symbolic(expr) => {
+            candidate.offset = None;
+            candidate.offset_expr = Some(expr);
+        }
+    }
+}
+
+/// Number of elements between two offsets into the same buffer, when both are known or
+/// they are the same.
+fn distance(a: &BufferLen, b: &BufferLen) -> Option<usize> {
+    match (a, b) {
+        (BufferLen::Known(a), BufferLen::Known(b)) => Some(a.abs_diff(*b)),
+        (a, b) if a == b => Some(0),
+        _ => None,
+    }
+}
+
+/// The expression a block evaluates to, such as `p.add(1)` in `unsafe { p.add(1) }`.
+fn block_value(block: &Block) -> Option<&Expr> {
+    match block.stmts.last()? {
+        Stmt::Expr(expr, None) => Some(expr),
+        _ => None,
+    }
+}
+
+/// Buffers and pointers in scope, one list of bindings per enclosing block, innermost
+/// last. Shadowed bindings are kept until their block ends, and a binding that is not a
+/// buffer or pointer still shadows outer ones, as `None`.
+#[derive(Debug, Default)]
+struct Scopes {
+    frames: Vec<Vec<(String, Option<PointerInfo>)>>,
+}
+
+impl Scopes {
+    fn get(&self, name: &str) -> Option<&PointerInfo> {
+        self.binding(name)?.as_ref()
+    }
+
+    fn binding(&self, name: &str) -> Option<&Option<PointerInfo>> {
+        self.frames.iter().flatten().rev().find(|(bound, _)| bound == name).map(|(_, info)| info)
+    }
+
+    /// The buffer `name` itself, as opposed to a pointer into it.
+    fn buffer(&self, name: &str) -> Option<&PointerInfo> {
+        self.get(name).filter(|info| info.buffer_name == name)
+    }
+
+    /// The live buffer of the allocation a pointer points into, even when its name has
+    /// since been shadowed.
+    fn allocation_of(&self, pointer: &PointerInfo) -> Option<&PointerInfo> {
+        if pointer.allocation == 0 {
+            return self.buffer(&pointer.buffer_name);
+        }
+        self.frames.iter().flatten()
+            .filter_map(|(name, info)| info.as_ref().filter(|info| info.buffer_name == *name))
+            .find(|info| info.allocation == pointer.allocation)
+    }
+
+    /// A new binding in the innermost block.
+    fn declare(&mut self, name: String, info: Option<PointerInfo>) {
+        if self.frames.is_empty() {
+            self.frames.push(Vec::new());
+        }
+        if let Some(frame) = self.frames.last_mut() {
+            frame.push((name, info));
+        }
+    }
+
+    /// Replaces the value of the innermost binding of `name`, wherever it was declared.
+    fn assign(&mut self, name: &str, info: Option<PointerInfo>) {
+        if let Some((_, binding)) = self.frames.iter_mut().flatten().rev().find(|(bound, _)| bound == name) {
+            *binding = info;
+        }
+    }
+
+    fn push(&mut self) {
+        self.frames.push(Vec::new());
+    }
+
+    /// Ends the innermost block, returning the buffers declared in it.
+    fn pop(&mut self) -> Vec<PointerInfo> {
+        self.frames.pop().unwrap_or_default().into_iter()
+            .filter_map(|(name, info)| info.filter(|info| info.buffer_name == name))
+            .collect()
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut PointerInfo)> {
+        self.frames.iter_mut()
+            .flatten()
+            .filter_map(|(name, info)| Some((&*name, info.as_mut()?)))
+    }
+}
+
+/// `len + extra`, folded when both are known.
+fn add_len(len: Option<BufferLen>, extra: BufferLen) -> Option<BufferLen> {
+    Some(match (len?, extra) {
+        (BufferLen::Known(len), BufferLen::Known(extra)) => BufferLen::Known(len.checked_add(extra)?),
+        (len, extra) => BufferLen::Symbolic(format!("{} + {}", len, extra)),
+    })
 }
 
+/// Accesses a function makes to the buffers of its parameters, to be checked again at each
+/// call site against the buffers the caller passes.
+#[derive(Debug, Clone)]
+struct Summary {
+    parameters: Vec<String>,
+    /// Index of the accessed parameter, and the access in the callee's terms.
+    accesses: Vec<(usize, OverflowCandidate)>,
+}
+
+/// Operations whose outcome depends on the size of the accessed buffer.
+const SUMMARY_OPERATIONS: [&str; 9] = [
+    "pointer_offset", "unchecked_index", "unchecked_range", "from_raw_parts",
+    "copy_src", "copy_dst", "write_bytes", "set_len", "uninitialized_read",
+];
+
 pub fn find_buffer_overflows(ast: &File, mir_candidates: Vec<OverflowCandidate>) -> Vec<OverflowCandidate> {
     let mut visitor = OverflowVisitor {
         candidates: Vec::new(),
-        pointers: HashMap::new(),
+        pointers: Scopes::default(),
+        allocations: 0,
+        ownership: HashMap::new(),
         current_function: String::new(),
+        consts: collect_consts(ast),
+        arrays: interval::const_arrays(ast),
+        layouts: HashMap::new(),
+        fields: collect_fields(ast),
+        self_type: None,
+        conditions: Vec::new(),
+        assumptions: Vec::new(),
+        branch_depth: 0,
+        loop_depths: Vec::new(),
+        block_values: Vec::new(),
+        writes: Vec::new(),
+        reads: Vec::new(),
+        item_path: Vec::new(),
+        parameters: Vec::new(),
+        summaries: HashMap::new(),
+        propagated: Vec::new(),
     };
-    
-    visitor.visit_file(ast);
-    visitor.candidates.extend(mir_candidates);
-    visitor.candidates
+
+    // Callees are visited before their callers so that their summaries are ready at the
+    // call sites; the candidates are still reported in source order.
+    let mut found = vec![Vec::new(); ast.items.len()];
+    for index in call_order(ast) {
+        visitor.visit_item(&ast.items[index]);
+        found[index] = std::mem::take(&mut visitor.candidates);
+    }
+    let mut candidates: Vec<OverflowCandidate> = found.into_iter().flatten().collect();
+    candidates.extend(mir_candidates);
+    candidates
+}
+
+/// Indices of the file's items with every function after the functions and modules it
+/// calls into; calls within a cycle are ignored.
+fn call_order(ast: &File) -> Vec<usize> {
+    struct CallCollector {
+        callees: Vec<String>,
+    }
+
+    impl<'ast> Visit<'ast> for CallCollector {
+        fn visit_expr_call(&mut self, call: &'ast ExprCall) {
+            // `io::read(..)` depends on the whole module `io`.
+            if let Expr::Path(func) = &*call.func
+                && let Some(first) = func.path.segments.first()
+            {
+                self.callees.push(first.ident.to_string());
+            }
+            visit::visit_expr_call(self, call);
+        }
+    }
+
+    let functions: HashMap<String, usize> = ast.items.iter().enumerate()
+        .filter_map(|(index, item)| match item {
+            syn::Item::Fn(func) => Some((func.sig.ident.to_string(), index)),
+            syn::Item::Mod(module) => Some((module.ident.to_string(), index)),
+            _ => None,
+        })
+        .collect();
+
+    fn visit(index: usize, ast: &File, functions: &HashMap<String, usize>, visited: &mut Vec<bool>, order: &mut Vec<usize>) {
+        if visited[index] {
+            return;
+        }
+        visited[index] = true;
+        if let syn::Item::Fn(_) | syn::Item::Mod(_) = &ast.items[index] {
+            let mut collector = CallCollector { callees: Vec::new() };
+            collector.visit_item(&ast.items[index]);
+            for callee in collector.callees {
+                if let Some(&callee) = functions.get(&callee) {
+                    visit(callee, ast, functions, visited, order);
+                }
+            }
+        }
+        order.push(index);
+    }
+
+    let mut visited = vec![false; ast.items.len()];
+    let mut order = Vec::new();
+    for index in 0..ast.items.len() {
+        visit(index, ast, &functions, &mut visited, &mut order);
+    }
+    order
 }
 
 struct OverflowVisitor {
     candidates: Vec<OverflowCandidate>,
-    pointers: HashMap<String, PointerInfo>,
+    pointers: Scopes,
+    /// Number of allocations seen so far, for their ids.
+    allocations: usize,
+    /// Who frees each allocation, by id; allocations without an entry are freed by their
+    /// buffer as usual.
+    ownership: HashMap<usize, Ownership>,
     current_function: String,
+    consts: HashMap<String, usize>,
+    /// Elements of the file's constant arrays, for the interval analysis.
+    arrays: HashMap<String, Vec<i128>>,
+    /// `Layout`s bound by `let` in the current function.
+    layouts: HashMap<String, HeapLayout>,
+    /// Field types of the file's structs, for buffers such as `self.buf`.
+    fields: HashMap<String, HashMap<String, Type>>,
+    /// Type of `self` in the `impl` being visited.
+    self_type: Option<String>,
+    /// Path conditions of the code currently being visited, innermost last; `None` once a
+    /// variable the condition mentions was changed.
+    conditions: Vec<Option<String>>,
+    /// Facts about the variables in scope, innermost last; `None` once a variable the fact
+    /// mentions was changed or bound again.
+    assumptions: Vec<Option<String>>,
+    /// Number of branches and loops enclosing the code currently being visited.
+    branch_depth: usize,
+    /// Branch depths of the bodies of the loops enclosing the code being visited,
+    /// innermost last.
+    loop_depths: Vec<usize>,
+    /// Pointer expressions dereferenced without reading the pointee, e.g. `p.add(i)` in
+    /// `*p.add(i) = x` or `&mut *p.add(i)`.
+    writes: Vec<SourceSpan>,
+    /// Pointer expressions the pointee is read through, e.g. `p.add(i)` in `x = *p.add(i)`,
+    /// `p.add(i).read()` or the source of `ptr::copy`.
+    reads: Vec<SourceSpan>,
+    /// Pointers that blocks evaluate to into buffers the blocks themselves dropped, e.g.
+    /// `{ let v = vec![0; 4]; v.as_ptr() }`, by the span of the block.
+    block_values: Vec<(SourceSpan, PointerInfo)>,
+    /// Modules, types, traits and functions enclosing the code being visited.
+    item_path: Vec<String>,
+    /// Parameter names of the current function.
+    parameters: Vec<String>,
+    /// Summaries of the functions visited so far.
+    summaries: HashMap<String, Summary>,
+    /// Accesses of callees into the current function's parameters, through calls that
+    /// pass a parameter on.
+    propagated: Vec<(usize, OverflowCandidate)>,
 }
 
-impl<'ast> Visit<'ast> for OverflowVisitor {
-    fn visit_item_fn(&mut self, func: &'ast ItemFn) {
-        self.current_function = func.sig.ident.to_string();
-        visit::visit_item_fn(self, func);
+impl OverflowVisitor {
+    /// The buffer a pointer expression points into, following `as_ptr`/`as_mut_ptr`,
+    /// pointer variables, `as *mut T` casts, `.cast::<T>()` and pointers derived with
+    /// `add`/`sub`/`offset`, whose offsets accumulate.
+    fn pointer_of(&self, expr: &Expr) -> Option<PointerInfo> {
+        match expr {
+            Expr::Path(path) => self.pointers.get(&path.path.get_ident()?.to_string()).cloned(),
+            Expr::Paren(paren) => self.pointer_of(&paren.expr),
+            Expr::Group(group) => self.pointer_of(&group.expr),
+            Expr::Unsafe(unsafe_expr) => self.block_pointer(&unsafe_expr.block),
+            Expr::Block(block) => self.block_pointer(&block.block),
+            Expr::Cast(cast) => match &*cast.ty {
+                Type::Ptr(ptr) => self.pointer_of(&cast.expr)?.cast_to(type_text(&ptr.elem)),
+                _ => None,
+            },
+            Expr::MethodCall(call) if call.method == "as_mut_ptr" || call.method == "as_ptr" => {
+                Some(PointerInfo { element_type: None, ..self.buffer_of(&call.receiver)? })
+            }
+            Expr::Call(_) => match release_call(expr)? {
+                (operation, value) if operation.ends_with("into_raw") => {
+                    Some(PointerInfo { element_type: None, ..self.tracked_buffer(value)? })
+                }
+                _ => None,
+            },
+            Expr::MethodCall(call) if call.method == "cast" && call.args.is_empty() => {
+                let target = match call.turbofish.as_ref().and_then(|turbofish| turbofish.args.first()) {
+                    Some(syn::GenericArgument::Type(ty)) => type_text(ty),
+                    _ => "_".to_string(),
+                };
+                self.pointer_of(&call.receiver)?.cast_to(target)
+            }
+            Expr::MethodCall(call) if POINTER_METHODS.contains(&call.method.to_string().as_str()) => {
+                self.pointer_of(&call.receiver)?.moved(&call.method.to_string(), call.args.first()?)
+            }
+            _ => None,
+        }
     }
-    
-    fn visit_local(&mut self, local: &'ast Local) {
-        if let Pat::Ident(pat_ident) = &local.pat {
-            let var_name = pat_ident.ident.to_string();
-            
-            if let Some(init) = &local.init {
-                if let Expr::Macro(expr_macro) = &*init.expr {
-                    if expr_macro.mac.path.is_ident("vec") {
-                        let size = extract_vec_size(&expr_macro.mac);
-                        println!("Found vec! for {} with size {:?}", var_name, size);
-                        self.pointers.insert(var_name.clone(), PointerInfo {
-                            buffer_name: var_name.clone(),
-                            buffer_size: size,
-                        });
-                    }
-                }
-                
-                if let Expr::MethodCall(method_call) = &*init.expr {
-                    let method_name = method_call.method.to_string();
-                    
-                    if method_name == "as_mut_ptr" || method_name == "as_ptr" {
-                        if let Expr::Path(path) = &*method_call.receiver {
-                            if let Some(ident) = path.path.get_ident() {
-                                let buffer_name = ident.to_string();
-                                let buffer_size = self.pointers.get(&buffer_name)
-                                    .and_then(|info| info.buffer_size);
-                                
-                                println!("Found pointer {} from buffer {} (size: {:?})", 
-                                       var_name, buffer_name, buffer_size);
-                                
-                                self.pointers.insert(var_name.clone(), PointerInfo {
-                                    buffer_name,
-                                    buffer_size,
-                                });
-                            }
-                        }
+
+    /// The pointer a block evaluates to, dangling when the block dropped its buffer.
+    fn block_pointer(&self, block: &Block) -> Option<PointerInfo> {
+        self.dropped_value(block).or_else(|| self.pointer_of(block_value(block)?))
+    }
+
+    /// The pointer a visited block evaluates to into a buffer the block dropped.
+    fn dropped_value(&self, block: &Block) -> Option<PointerInfo> {
+        let span = SourceSpan::from_span(block.span());
+        self.block_values.iter().find(|(value, _)| *value == span).map(|(_, info)| info.clone())
+    }
+
+    fn with_condition(&mut self, condition: Option<String>, visit: impl FnOnce(&mut Self)) {
+        let depth = self.conditions.len();
+        self.conditions.push(condition);
+        self.branch_depth += 1;
+        visit(self);
+        self.branch_depth -= 1;
+        self.conditions.truncate(depth);
+    }
+
+    /// Visits a loop body, which may run any number of times.
+    fn in_loop(&mut self, condition: Option<String>, visit: impl FnOnce(&mut Self)) {
+        self.loop_depths.push(self.branch_depth + 1);
+        self.with_condition(condition, visit);
+        self.loop_depths.pop();
+    }
+
+    /// Whether code being visited may run repeatedly for the same buffer or pointer
+    /// `info`, i.e. it is in a loop that `info` was defined outside of.
+    fn repeats(&self, info: &PointerInfo) -> bool {
+        self.loop_depths.last().is_some_and(|depth| *depth > info.depth)
+    }
+
+    /// The path conditions that still hold, outermost first.
+    fn path_conditions(&self) -> Vec<String> {
+        self.conditions.iter().flatten().cloned().collect()
+    }
+
+    /// Drops the path conditions and the first `facts` facts mentioning a variable `stmt`
+    /// may change, which no longer hold after it, e.g. `i < buf.len()` after `i += 10` or
+    /// `idx == i + 1` after `let i = 0`. Facts `stmt` added itself are about the new values.
+    fn forget_changed(&mut self, stmt: &Stmt, facts: usize) {
+        let changed = changed_variables(stmt);
+        if changed.is_empty() {
+            return;
+        }
+        for entry in self.conditions.iter_mut().chain(self.assumptions[..facts].iter_mut()) {
+            if entry.as_deref().is_some_and(|text| mentions(text, &changed)) {
+                *entry = None;
+            }
+        }
+    }
+
+    /// The buffer a slice or `Vec` expression refers to; its size is unknown unless it is
+    /// tracked.
+    fn buffer_of(&self, expr: &Expr) -> Option<PointerInfo> {
+        if let Some(info) = self.tracked_buffer(expr) {
+            return Some(info);
+        }
+        match expr {
+            Expr::Path(path) => Some(PointerInfo::from_len(path.path.get_ident()?.to_string(), None, None)),
+            Expr::Paren(paren) => self.buffer_of(&paren.expr),
+            Expr::Group(group) => self.buffer_of(&group.expr),
+            _ => None,
+        }
+    }
+
+    /// The tracked buffer a place expression names: a local or parameter, a field of
+    /// `self` such as `self.buf` whose type is a buffer, or the buffer behind
+    /// `s.as_mut_vec()`, `s.as_bytes()`, `ManuallyDrop::new(v)` and the like.
+    fn tracked_buffer(&self, expr: &Expr) -> Option<PointerInfo> {
+        match expr {
+            Expr::Path(path) => self.pointers.buffer(&path.path.get_ident()?.to_string()).cloned(),
+            Expr::Paren(paren) => self.tracked_buffer(&paren.expr),
+            Expr::Group(group) => self.tracked_buffer(&group.expr),
+            Expr::Reference(reference) => self.tracked_buffer(&reference.expr),
+            Expr::Unsafe(unsafe_expr) => self.tracked_buffer(block_value(&unsafe_expr.block)?),
+            Expr::Field(field) => {
+                let Expr::Path(base) = &*field.base else {
+                    return None;
+                };
+                let syn::Member::Named(member) = &field.member else {
+                    return None;
+                };
+                if !base.path.is_ident("self") {
+                    return None;
+                }
+                let ty = self.fields.get(self.self_type.as_ref()?)?.get(&member.to_string())?;
+                typed_buffer(&expr_text(expr), ty, &self.consts)
+            }
+            Expr::MethodCall(call) if call.args.is_empty() && BUFFER_VIEWS.contains(&call.method.to_string().as_str()) => {
+                self.tracked_buffer(&call.receiver)
+            }
+            // The value is kept, only no longer dropped.
+            Expr::Call(_) => match release_call(expr)? {
+                (operation, value) if operation.ends_with("ManuallyDrop::new") || operation.ends_with("leak") => {
+                    self.tracked_buffer(value)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// A candidate for an access of `operation` into the buffer of `info` at `span`, under
+    /// the current path conditions.
+    ///
+    /// Accesses through a slice are bounded by the buffer's length, raw pointer accesses by
+    /// its allocation, which for a `Vec` is its capacity.
+    fn candidate(&self, info: &PointerInfo, operation: &str, span: proc_macro2::Span) -> OverflowCandidate {
+        let span = SourceSpan::from_span(span);
+        let (bound, fact) = match operation {
+            "unchecked_index" | "unchecked_range" | "uninitialized_read" => (info.len(), None),
+            _ => info.allocation(),
+        };
+        let mut assumptions: Vec<String> = self.assumptions.iter().flatten().cloned().collect();
+        assumptions.extend(fact);
+        OverflowCandidate {
+            location: self.current_function.clone(),
+            buffer_name: info.buffer_name.clone(),
+            operation: operation.to_string(),
+            line: span.start_line,
+            column: span.start_column,
+            buffer_size: match &bound {
+                Some(BufferLen::Known(len)) => Some(*len),
+                _ => None,
+            },
+            buffer_size_expr: match bound {
+                Some(BufferLen::Symbolic(expr)) => Some(expr),
+                _ => None,
+            },
+            buffer_element_type: info.buffer_element_type.clone(),
+            element_type: info.element_type.clone(),
+            path_conditions: self.path_conditions(),
+            assumptions,
+            span,
+            ..Default::default()
+        }
+    }
+
+    /// The buffer a `let` initializer allocates: `vec!`, `Vec::new`/`with_capacity`, an
+    /// array, `Box::new(..)`, `into_boxed_slice()`, or a `String` from a literal, also
+    /// wrapped in `ManuallyDrop::new`. The box of `Box::into_raw(Box::new(..))` is named
+    /// `*name`.
+    fn new_allocation(&self, name: &str, init: &Expr) -> Option<PointerInfo> {
+        let string = |len: usize| PointerInfo::from_len(name.to_string(), Some(BufferLen::Known(len)), Some("u8".to_string()));
+        match init {
+            Expr::Macro(expr_macro) if expr_macro.mac.path.is_ident("vec") => {
+                let size = extract_vec_size(&expr_macro.mac, &self.consts);
+                Some(PointerInfo::from_len(name.to_string(), size, vec_element_type(&expr_macro.mac)))
+            }
+            Expr::Repeat(repeat) => {
+                Some(PointerInfo::from_len(name.to_string(), Some(self.len_of(&repeat.len)), literal_type(&repeat.expr)))
+            }
+            Expr::Array(array) => {
+                let element_type = array.elems.first().and_then(literal_type);
+                Some(PointerInfo::from_len(name.to_string(), Some(BufferLen::Known(array.elems.len())), element_type))
+            }
+            Expr::Paren(paren) => self.new_allocation(name, &paren.expr),
+            Expr::Group(group) => self.new_allocation(name, &group.expr),
+            Expr::Unsafe(unsafe_expr) => self.new_allocation(name, block_value(&unsafe_expr.block)?),
+            init if let Some(info) = self.heap_allocation(name, init) => Some(info),
+            Expr::Call(call) => {
+                let Expr::Path(func) = &*call.func else {
+                    return None;
+                };
+                let segments: Vec<String> = func.path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+                let args: Vec<&Expr> = call.args.iter().collect();
+                match (segments.as_slice(), args.as_slice()) {
+                    // A boxed value that is not a buffer is a buffer of one element.
+                    ([.., ty, constructor], [inner]) if ty == "Box" && constructor == "new" => self.new_allocation(name, inner)
+                        .or_else(|| Some(PointerInfo::from_len(name.to_string(), Some(BufferLen::Known(1)), literal_type(inner)))),
+                    // The box is only reachable through the pointer, so it is named after it.
+                    ([.., ty, function], [inner]) if ty == "Box" && function == "into_raw" => self.new_allocation(&format!("*{}", name), inner),
+                    ([.., ty, constructor], [inner]) if ty == "ManuallyDrop" && constructor == "new" => self.new_allocation(name, inner),
+                    ([.., ty, constructor], [text]) if ty == "String" && constructor == "from" => Some(string(string_literal_len(text)?)),
+                    _ => {
+                        let (capacity, element_type) = vec_constructor(init, &self.consts)?;
+                        let mut info = PointerInfo::from_len(name.to_string(), Some(BufferLen::Known(0)), element_type);
+                        info.capacity = Capacity::Known(capacity);
+                        Some(info)
                     }
                 }
             }
+            Expr::MethodCall(call) if call.args.is_empty() => match call.method.to_string().as_str() {
+                "to_string" | "to_owned" | "into" | "to_vec" if let Some(len) = string_literal_len(&call.receiver) => Some(string(len)),
+                // A copy, or the same elements without spare capacity.
+                "into_boxed_slice" | "into_boxed_str" | "into_bytes" | "to_vec" | "to_owned" | "clone" => {
+                    let source = self.new_allocation(name, &call.receiver).or_else(|| self.tracked_buffer(&call.receiver))?;
+                    Some(PointerInfo::from_len(name.to_string(), source.len(), source.buffer_element_type))
+                }
+                _ => None,
+            },
+            _ => None,
         }
-        
-        visit::visit_local(self, local);
     }
-    
-    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
-        let method_name = expr.method.to_string();
-        
-        if method_name == "add" {
-            if let Expr::Path(path) = &*expr.receiver {
-                if let Some(ident) = path.path.get_ident() {
-                    let ptr_name = ident.to_string();
-                    
-                    if let Some(ptr_info) = self.pointers.get(&ptr_name) {
-                        let offset = extract_offset(&expr.args);
-                        
-                        println!("Found add for pointer {} with offset {:?}", ptr_name, offset);
-                        
-                        let line = 0;
-                        let column = 0;
-                        
-                        self.candidates.push(OverflowCandidate {
-                            location: self.current_function.clone(),
-                            buffer_name: ptr_info.buffer_name.clone(),
-                            operation: "pointer_offset".to_string(),
-                            line,
-                            column,
-                            buffer_size: ptr_info.buffer_size,
-                            offset,
-                        });
+
+    /// The memory `std::alloc::alloc`, `alloc_zeroed` or `realloc` returns, with the size
+    /// of its layout or the new size, as the `*mut u8` or the pointer type it is cast to.
+    fn heap_allocation(&self, name: &str, expr: &Expr) -> Option<PointerInfo> {
+        match expr {
+            Expr::Paren(paren) => self.heap_allocation(name, &paren.expr),
+            Expr::Group(group) => self.heap_allocation(name, &group.expr),
+            Expr::Unsafe(unsafe_expr) => self.heap_allocation(name, block_value(&unsafe_expr.block)?),
+            Expr::Cast(cast) => match &*cast.ty {
+                Type::Ptr(ptr) => self.heap_allocation(name, &cast.expr)?.cast_to(type_text(&ptr.elem)),
+                _ => None,
+            },
+            Expr::MethodCall(call) if call.method == "cast" && call.args.is_empty() => {
+                let target = match call.turbofish.as_ref().and_then(|turbofish| turbofish.args.first()) {
+                    Some(syn::GenericArgument::Type(ty)) => type_text(ty),
+                    _ => "_".to_string(),
+                };
+                self.heap_allocation(name, &call.receiver)?.cast_to(target)
+            }
+            expr => {
+                let (function, args) = allocator_call(expr)?;
+                let info = match (function.as_str(), args.as_slice()) {
+                    ("alloc" | "alloc_zeroed", [layout]) => {
+                        let layout = self.layout_of(layout)?;
+                        let info = PointerInfo::from_len(name.to_string(), Some(layout.len), Some(layout.element_type));
+                        PointerInfo { align: Some(layout.align), ..info }
                     }
+                    // The memory keeps the alignment of the old layout.
+                    ("realloc", [_, layout, new_size]) => {
+                        let layout = self.layout_of(layout)?;
+                        let info = PointerInfo::from_len(name.to_string(), Some(self.len_of(new_size)), Some("u8".to_string()));
+                        PointerInfo { align: Some(layout.align), ..info }
+                    }
+                    _ => return None,
+                };
+                info.cast_to("u8".to_string())
+            }
+        }
+    }
+
+    /// The layout an expression evaluates to: a `Layout` variable, or `Layout::array`,
+    /// `Layout::new` or `Layout::from_size_align`, possibly followed by `?` or `unwrap()`.
+    fn layout_of(&self, expr: &Expr) -> Option<HeapLayout> {
+        match expr {
+            Expr::Path(path) => self.layouts.get(&path.path.get_ident()?.to_string()).cloned(),
+            Expr::Paren(paren) => self.layout_of(&paren.expr),
+            Expr::Reference(reference) => self.layout_of(&reference.expr),
+            Expr::Try(try_expr) => self.layout_of(&try_expr.expr),
+            Expr::Unsafe(unsafe_expr) => self.layout_of(block_value(&unsafe_expr.block)?),
+            Expr::MethodCall(call) if matches!(call.method.to_string().as_str(), "unwrap" | "expect" | "unwrap_unchecked" | "clone") => {
+                self.layout_of(&call.receiver)
+            }
+            Expr::Call(call) => {
+                let Expr::Path(func) = &*call.func else {
+                    return None;
+                };
+                let segments: Vec<_> = func.path.segments.iter().collect();
+                let [.., layout, constructor] = segments.as_slice() else {
+                    return None;
+                };
+                if layout.ident != "Layout" {
+                    return None;
                 }
+                let element_type = match &constructor.arguments {
+                    syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+                        Some(syn::GenericArgument::Type(ty)) => Some(type_text(ty)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                let args: Vec<&Expr> = call.args.iter().collect();
+                let (len, element_type, align) = match (constructor.ident.to_string().as_str(), args.as_slice()) {
+                    ("array", [len]) => (self.len_of(len), element_type?, None),
+                    ("new", []) => (BufferLen::Known(1), element_type?, None),
+                    ("from_size_align" | "from_size_align_unchecked", [size, align]) => {
+                        (self.len_of(size), "u8".to_string(), Some(self.len_of(align)))
+                    }
+                    _ => return None,
+                };
+                let align = align.unwrap_or_else(|| BufferLen::Symbolic(format!("std::mem::align_of::<{}>()", element_type)));
+                Some(HeapLayout { len, element_type, align })
             }
+            _ => None,
         }
-        
-        visit::visit_expr_method_call(self, expr);
     }
-    
-    fn visit_expr_unsafe(&mut self, expr: &'ast ExprUnsafe) {
-        println!("Found unsafe block");
-        visit::visit_expr_unsafe(self, expr);
+
+    /// Checks that `dealloc(ptr, layout)` or `realloc(ptr, layout, _)` is given the layout
+    /// `ptr` was allocated with, then marks `ptr` and the pointers into its allocation as
+    /// dangling.
+    fn deallocate(&mut self, function: &str, pointer: &Expr, layout: &Expr, span: proc_macro2::Span) {
+        let Some(info) = self.pointer_of(pointer) else {
+            return;
+        };
+        if self.dangling(&info, pointer, span) {
+            return;
+        }
+        if let Some(layout) = self.layout_of(layout) {
+            let mut candidate = self.candidate(&info, "dealloc_layout", span);
+            candidate.pointer_name = Some(expr_text(pointer));
+            candidate.pointer_method = Some(function.to_string());
+            candidate.offset = Some(0);
+            candidate.count_expr = Some(layout.len.to_string());
+            candidate.element_type = (info.buffer_element_type.as_ref() != Some(&layout.element_type)).then_some(layout.element_type);
+            candidate.layout_align = info.align.as_ref().map(|align| (layout.align.to_string(), align.to_string()));
+            self.candidates.push(candidate);
+        }
+
+        // `realloc` may keep the memory in place.
+        let line = span.start().line;
+        let certain = function == "dealloc";
+        self.invalidate(&info, function, line, certain);
+        if let Some(name) = pointer_variable(pointer)
+            && let Some(mut own) = self.pointers.get(&name).cloned()
+            && own.invalidation.is_none()
+        {
+            own.invalidation = Some(Invalidation {
+                operation: function.to_string(),
+                line,
+                certain: certain && self.branch_depth <= own.depth,
+                conditions: self.path_conditions(),
+            });
+            self.pointers.assign(&name, Some(own));
+        }
     }
-    
-    fn visit_expr(&mut self, expr: &'ast Expr) {
-        visit::visit_expr(self, expr);
+
+    /// Marks the allocation of `value` as released to a raw pointer, e.g. by
+    /// `Box::into_raw`, so that its buffer no longer frees it.
+    fn release(&mut self, value: &Expr) {
+        let Some(info) = self.tracked_buffer(value) else {
+            return;
+        };
+        if info.allocation == 0 {
+            return;
+        }
+        if self.branch_depth > info.depth {
+            // Released in a branch, the buffer may still free it.
+            self.ownership.remove(&info.allocation);
+        } else {
+            self.ownership.insert(info.allocation, Ownership::Raw);
+        }
     }
-}
 
-fn extract_vec_size(mac: &syn::Macro) -> Option<usize> {
-    if let syn::MacroDelimiter::Bracket(_) = mac.delimiter {
-        if let Ok(tokens) = syn::parse2::<syn::Expr>(mac.tokens.clone()) {
-            if let syn::Expr::Array(array) = tokens {
-                return Some(array.elems.len());
+    /// Checks `Box::from_raw(ptr)` or `Vec::from_raw_parts(ptr, len, cap)` taking back the
+    /// allocation `ptr` points into: it must not have an owner already, `len` must fit in
+    /// its capacity, and `cap` must be that capacity. The allocation is owned by the result
+    /// afterwards.
+    fn reclaim(&mut self, function: &str, pointer: &Expr, parts: Option<(&Expr, &Expr)>, span: proc_macro2::Span) {
+        let Some(info) = self.pointer_of(pointer) else {
+            return;
+        };
+        if self.dangling(&info, pointer, span) {
+            return;
+        }
+        if let Some(Ownership::Owned { owner, operation, line, conditions }) = self.ownership.get(&info.allocation).cloned()
+            // Taken on the other branch of an `if`, the two never both run.
+            && !exclusive(&conditions, &self.path_conditions())
+        {
+            let mut candidate = self.candidate(&info, "double_reclaim", span);
+            candidate.pointer_name = Some(expr_text(pointer));
+            candidate.pointer_method = Some(function.to_string());
+            candidate.origin_buffer = owner;
+            set_offset(&mut candidate, info.base());
+            candidate.invalidation = Some(Invalidation {
+                operation,
+                line,
+                // Owned on every path that reaches the call.
+                certain: self.path_conditions().starts_with(&conditions),
+                conditions,
+            });
+            self.candidates.push(candidate);
+        }
+        if let Some((len, capacity)) = parts {
+            for (operation, part) in [("reclaim_len", len), ("reclaim_cap", capacity)] {
+                let mut candidate = self.candidate(&info, operation, span);
+                candidate.pointer_name = Some(expr_text(pointer));
+                candidate.pointer_method = Some(function.to_string());
+                set_offset(&mut candidate, info.base());
+                candidate.count_expr = Some(expr_text(part));
+                self.candidates.push(candidate);
             }
         }
+        if info.allocation != 0 {
+            self.ownership.insert(info.allocation, Ownership::Owned {
+                owner: None,
+                operation: function.to_string(),
+                line: span.start().line,
+                conditions: self.path_conditions(),
+            });
+        }
     }
-    None
-}
 
-fn extract_offset(args: &syn::punctuated::Punctuated<syn::Expr, syn::token::Comma>) -> Option<usize> {
-    if let Some(arg) = args.first() {
-        if let syn::Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) = arg {
-            return lit.base10_parse().ok();
+    /// The buffer `name` bound to `Box::from_raw(ptr)` or `Vec::from_raw_parts(ptr, len, cap)`:
+    /// the allocation `ptr` points into, with the given length and capacity.
+    fn reclaimed_buffer(&self, name: &str, init: &Expr) -> Option<PointerInfo> {
+        let (_, args) = reclaim_call(init)?;
+        let mut info = PointerInfo {
+            buffer_name: name.to_string(),
+            element_type: None,
+            invalidation: None,
+            base_offset: None,
+            ..self.pointer_of(args.first()?)?
+        };
+        if let [_, len, capacity] = args.as_slice() {
+            info.set_len(Some(self.len_of(len)));
+            info.capacity = Capacity::Known(self.len_of(capacity));
         }
+        Some(info)
     }
-    None
-}
\ No newline at end of file
+
+    /// Binds `let (ptr, len, cap) = v.into_raw_parts();`: `ptr` points to the start of the
+    /// allocation of `v`, and `len` and `cap` are its length and capacity.
+    fn bind_raw_parts(&mut self, tuple: &syn::PatTuple, init: &Expr) {
+        let Some((_, value)) = release_call(init).filter(|(operation, _)| operation.ends_with("into_raw_parts")) else {
+            return;
+        };
+        let Some(buffer) = self.tracked_buffer(value) else {
+            return;
+        };
+        let names: Vec<Option<String>> = tuple.elems.iter()
+            .map(|pat| match pat {
+                Pat::Ident(ident) => Some(ident.ident.to_string()),
+                _ => None,
+            })
+            .collect();
+        let [pointer, len, capacity] = names.as_slice() else {
+            return;
+        };
+        let (allocation, fact) = buffer.allocation();
+        if let Some(len) = len {
+            self.assumptions.extend(buffer.len().map(|buffer_len| Some(format!("{} == {}", len, buffer_len))));
+            self.pointers.declare(len.clone(), None);
+        }
+        if let Some(capacity) = capacity {
+            self.assumptions.extend(allocation.map(|allocation| Some(format!("{} == {}", capacity, allocation))));
+            self.assumptions.push(fact);
+            self.pointers.declare(capacity.clone(), None);
+        }
+        if let Some(pointer) = pointer {
+            let info = PointerInfo { element_type: None, depth: self.branch_depth, ..buffer };
+            self.pointers.declare(pointer.clone(), Some(info));
+        }
+    }
+
+    /// Whether dropping `buffer` frees its allocation, rather than leaving it to a raw
+    /// pointer or to another owner.
+    fn frees(&self, buffer: &PointerInfo) -> bool {
+        match self.ownership.get(&buffer.allocation) {
+            Some(Ownership::Raw) => false,
+            Some(Ownership::Owned { owner, .. }) => owner.as_ref() == Some(&buffer.buffer_name),
+            None => true,
+        }
+    }
+
+    /// Tracks a length-changing method call on the `Vec` `name`; `set_len` is also checked
+    /// against the capacity.
+    fn vec_method(&mut self, name: &str, method: &str, args: &[&Expr], span: proc_macro2::Span) {
+        let Some(mut info) = self.pointers.buffer(name).cloned() else {
+            return;
+        };
+        let arg = match (method, args) {
+            ("extend_from_slice" | "push_str", [slice]) => self.slice_len(slice),
+            ("resize" | "truncate" | "set_len" | "reserve" | "reserve_exact", [len, ..]) => Some(self.len_of(len)),
+            _ => None,
+        };
+        if method == "set_len" && let [len] = args {
+            let mut candidate = self.candidate(&info, "set_len", span);
+            candidate.offset = Some(0);
+            candidate.count_expr = Some(expr_text(len));
+            self.candidates.push(candidate);
+        }
+        let conditional = self.branch_depth > info.depth;
+        // `String::push_str` appends the bytes like `extend_from_slice`.
+        let applied = if method == "push_str" { "extend_from_slice" } else { method };
+        let reallocation = if self.repeats(&info) {
+            info.apply_repeatedly(applied)
+        } else {
+            info.apply(applied, arg, conditional)
+        };
+        if let Some(reallocation) = reallocation {
+            if reallocation != Reallocation::None {
+                self.invalidate(&info, method, span.start().line, reallocation == Reallocation::Certain);
+            }
+            self.pointers.assign(name, Some(info));
+        }
+    }
+
+    /// Marks the pointers taken from `buffer` as dangling after `operation` at `line`.
+    fn invalidate(&mut self, buffer: &PointerInfo, operation: &str, line: usize, certain: bool) {
+        let conditions = self.path_conditions();
+        for (name, info) in self.pointers.iter_mut() {
+            let same = match buffer.allocation {
+                0 => info.buffer_name == buffer.buffer_name,
+                allocation => info.allocation == allocation,
+            };
+            if same && *name != info.buffer_name && info.invalidation.is_none() {
+                info.invalidation = Some(Invalidation {
+                    operation: operation.to_string(),
+                    line,
+                    // Freed in a branch, the buffer may still be alive where the pointer is used.
+                    certain: certain && self.branch_depth <= info.depth,
+                    conditions: conditions.clone(),
+                });
+            }
+        }
+    }
+
+    /// Records a `use_after_free` when the pointer `info` dangles; any other check of the
+    /// access would be meaningless then.
+    fn dangling(&mut self, info: &PointerInfo, pointer: &Expr, span: proc_macro2::Span) -> bool {
+        let Some(invalidation) = &info.invalidation else {
+            return false;
+        };
+        let mut candidate = self.candidate(info, "use_after_free", span);
+        candidate.pointer_name = Some(expr_text(pointer));
+        set_offset(&mut candidate, info.base());
+        candidate.invalidation = Some(invalidation.clone());
+        self.candidates.push(candidate);
+        true
+    }
+
+    /// Length given by an expression, folded when it is constant.
+    fn len_of(&self, expr: &Expr) -> BufferLen {
+        match eval_const_expr(expr, &self.consts) {
+            Some(len) => BufferLen::Known(len),
+            None => BufferLen::Symbolic(expr_text(expr)),
+        }
+    }
+
+    /// Number of elements of a slice argument such as `&[1, 2, 3]`, `&other` or `"abc"`.
+    fn slice_len(&self, expr: &Expr) -> Option<BufferLen> {
+        if let Some(len) = string_literal_len(expr) {
+            return Some(BufferLen::Known(len));
+        }
+        match expr {
+            Expr::Reference(reference) => self.slice_len(&reference.expr),
+            Expr::Array(array) => Some(BufferLen::Known(array.elems.len())),
+            Expr::Path(path) => {
+                let name = path.path.get_ident()?.to_string();
+                match self.pointers.buffer(&name) {
+                    Some(info) => info.len(),
+                    None => Some(BufferLen::Symbolic(format!("{}.len()", name))),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Records `get_unchecked(i)`, `get_unchecked_mut(a..b)` and the like on `buffer`.
+    fn unchecked_access(&mut self, buffer: PointerInfo, method: &str, index: &Expr, span: proc_macro2::Span) {
+        let mut candidate = self.candidate(&buffer, "unchecked_index", span);
+        candidate.pointer_method = Some(method.to_string());
+        match index {
+            Expr::Range(range) => {
+                let start = range.start.as_deref().map(expr_text).unwrap_or_else(|| "0".to_string());
+                let end = match (&range.end, &range.limits) {
+                    (Some(end), syn::RangeLimits::HalfOpen(_)) => expr_text(end),
+                    (Some(end), syn::RangeLimits::Closed(_)) => format!("{} + 1", expr_text(end)),
+                    (None, _) => format!("{}.len()", buffer.buffer_name),
+                };
+                candidate.operation = "unchecked_range".to_string();
+                candidate.offset = start.parse().ok();
+                candidate.offset_expr = candidate.offset.is_none().then(|| start.clone());
+                candidate.count_expr = Some(if candidate.offset == Some(0) {
+                    end
+                } else if start.contains(' ') {
+                    format!("{} - ({})", end, start)
+                } else {
+                    format!("{} - {}", end, start)
+                });
+            }
+            _ => {
+                candidate.offset = extract_offset(index);
+                candidate.offset_expr = candidate.offset.is_none().then(|| expr_text(index));
+            }
+        }
+        self.candidates.push(candidate);
+    }
+
+    /// Records `slice::from_raw_parts(ptr, len)`, whose elements must lie within the
+    /// allocation `ptr` points into.
+    fn raw_parts(&mut self, ptr_info: PointerInfo, function: &str, pointer: &Expr, len: &Expr, span: proc_macro2::Span) {
+        if self.dangling(&ptr_info, pointer, span) {
+            return;
+        }
+        let mut candidate = self.candidate(&ptr_info, "from_raw_parts", span);
+        candidate.pointer_name = Some(expr_text(pointer));
+        candidate.pointer_method = Some(function.to_string());
+        set_offset(&mut candidate, ptr_info.base());
+        candidate.count_expr = Some(expr_text(len));
+        self.candidates.push(candidate);
+    }
+
+    /// Records `ptr::copy`-like copies of `count` elements: each side whose allocation is
+    /// known becomes a candidate of its own, and a nonoverlapping copy within one buffer
+    /// is also checked for overlap.
+    fn bulk_copy(&mut self, function: &str, src: &Expr, dst: &Expr, count: &Expr, span: proc_macro2::Span) {
+        self.mark_read(src);
+        self.mark_write(dst);
+        let src_info = self.pointer_of(src);
+        let dst_info = self.pointer_of(dst);
+        let sides = [("copy_src", src, &src_info, &dst_info), ("copy_dst", dst, &dst_info, &src_info)];
+        for (operation, pointer, info, other) in sides {
+            let Some(info) = info else {
+                continue;
+            };
+            if self.dangling(info, pointer, span) {
+                continue;
+            }
+            let mut candidate = self.candidate(info, operation, span);
+            candidate.pointer_name = Some(expr_text(pointer));
+            candidate.pointer_method = Some(function.to_string());
+            set_offset(&mut candidate, info.base());
+            candidate.count_expr = Some(expr_text(count));
+            candidate.origin_buffer = other.as_ref().map(|other| other.buffer_name.clone());
+            candidate.origin_offset = other.as_ref()
+                .filter(|other| other.element_type.is_none())
+                .map(|other| other.base().to_string());
+            self.candidates.push(candidate);
+        }
+
+        if function.ends_with("nonoverlapping")
+            && let (Some(src_info), Some(dst_info)) = (&src_info, &dst_info)
+            && src_info.same_allocation(dst_info)
+            && let Some(distance) = distance(&src_info.base(), &dst_info.base())
+        {
+            // The distance between the two starts is the offset.
+            let mut candidate = self.candidate(dst_info, "copy_overlap", span);
+            candidate.pointer_name = Some(expr_text(dst));
+            candidate.pointer_method = Some(function.to_string());
+            candidate.offset = Some(distance);
+            candidate.count_expr = Some(expr_text(count));
+            candidate.origin_buffer = Some(src_info.buffer_name.clone());
+            self.candidates.push(candidate);
+        }
+    }
+
+    /// Records `ptr::write_bytes(dst, value, count)`, which writes `count` elements.
+    fn write_bytes(&mut self, function: &str, dst: &Expr, count: &Expr, span: proc_macro2::Span) {
+        self.mark_write(dst);
+        let Some(info) = self.pointer_of(dst) else {
+            return;
+        };
+        if self.dangling(&info, dst, span) {
+            return;
+        }
+        let mut candidate = self.candidate(&info, "write_bytes", span);
+        candidate.pointer_name = Some(expr_text(dst));
+        candidate.pointer_method = Some(function.to_string());
+        set_offset(&mut candidate, info.base());
+        candidate.count_expr = Some(expr_text(count));
+        self.candidates.push(candidate);
+    }
+
+    /// Records an access through the pointer `pointer`, which for a derived pointer must lie
+    /// within its allocation at the pointer's offset.
+    fn based_access(&mut self, info: &PointerInfo, pointer: &Expr, span: proc_macro2::Span) {
+        if self.dangling(info, pointer, span) {
+            return;
+        }
+        let Some(base) = &info.base_offset else {
+            return;
+        };
+        let mut candidate = self.candidate(info, "pointer_offset", span);
+        candidate.pointer_name = Some(expr_text(pointer));
+        set_offset(&mut candidate, base.clone());
+        self.candidates.push(candidate);
+    }
+
+    /// Records a candidate for pointer arithmetic `method` applied to the pointer `receiver`.
+    fn pointer_method(&mut self, ptr_info: PointerInfo, receiver: &Expr, method: &str, arg: Option<&Expr>, span: proc_macro2::Span) {
+        if POINTER_ACCESSES.contains(&method) {
+            self.based_access(&ptr_info, receiver, span);
+            return;
+        }
+        if method != "offset_from" && !POINTER_METHODS.contains(&method) {
+            return;
+        }
+        // `wrapping_*` may leave the allocation; only an access through the result has to
+        // stay within it.
+        let accessed = SourceSpan::from_span(span);
+        if method.starts_with("wrapping_") && !self.reads.contains(&accessed) && !self.writes.contains(&accessed) {
+            return;
+        }
+        if self.dangling(&ptr_info, receiver, span) {
+            return;
+        }
+        let ptr_name = expr_text(receiver);
+        let mut candidate = self.candidate(&ptr_info, "pointer_offset", span);
+        candidate.pointer_name = Some(ptr_name.clone());
+
+        if method == "offset_from" {
+            // Only pointers into the same allocation may be subtracted.
+            let Some(origin) = arg.and_then(|arg| self.pointer_of(arg)) else {
+                return;
+            };
+            if origin.same_allocation(&ptr_info) {
+                return;
+            }
+            candidate.operation = "offset_from".to_string();
+            candidate.origin_buffer = Some(origin.buffer_name);
+            self.candidates.push(candidate);
+            return;
+        }
+
+        let Some(arg) = arg else {
+            return;
+        };
+        match &ptr_info.base_offset {
+            // A derived pointer is checked at its offset from the start of the allocation.
+            Some(base) => {
+                if method.contains("byte_") {
+                    return;
+                }
+                set_offset(&mut candidate, moved_offset(base, method, arg));
+                // Moved back before the start of the buffer, checked as a signed offset.
+                if candidate.offset_expr.as_ref().is_some_and(|expr| expr.starts_with('-')) {
+                    candidate.pointer_method = Some("offset".to_string());
+                }
+            }
+            None => {
+                candidate.offset = extract_offset(arg);
+                candidate.offset_expr = candidate.offset.is_none().then(|| expr_text(arg));
+                candidate.pointer_method = (method != "add").then(|| method.to_string());
+            }
+        }
+        println!("Found {} for pointer {} with offset {:?}", method, ptr_name, candidate.offset);
+
+        // Elements past the length of a `Vec` with spare capacity are uninitialized.
+        if self.reads.contains(&candidate.span)
+            && let Some(buffer) = self.pointers.allocation_of(&ptr_info)
+            && buffer.capacity != Capacity::Len
+        {
+            let mut read = self.candidate(buffer, "uninitialized_read", span);
+            read.pointer_name = candidate.pointer_name.clone();
+            read.offset = candidate.offset;
+            read.offset_expr = candidate.offset_expr.clone();
+            read.pointer_method = candidate.pointer_method.clone();
+            read.element_type = ptr_info.element_type.clone();
+            self.candidates.push(read);
+        }
+        self.candidates.push(candidate);
+    }
+
+    /// Runs `visit` over a function or method body with its own parameters, buffers and
+    /// conditions, bounds the offsets of its accesses with the interval analysis of `body`,
+    /// then records its summary under its item path.
+    fn visit_function(&mut self, sig: &syn::Signature, body: Option<&Block>, visit: impl FnOnce(&mut Self)) {
+        self.item_path.push(sig.ident.to_string());
+        let path = self.item_path.join("::");
+        let outer_function = std::mem::replace(&mut self.current_function, path.clone());
+        let outer = std::mem::take(&mut self.conditions);
+        let outer_assumptions = std::mem::replace(&mut self.assumptions, parameter_assumptions(sig).into_iter().map(Some).collect());
+        let outer_parameters = std::mem::replace(&mut self.parameters, parameter_names(sig));
+        let outer_pointers = std::mem::replace(&mut self.pointers, parameter_pointers(sig, &self.consts));
+        for (_, info) in self.pointers.iter_mut() {
+            self.allocations += 1;
+            info.allocation = self.allocations;
+        }
+        let outer_propagated = std::mem::take(&mut self.propagated);
+        let outer_layouts = std::mem::take(&mut self.layouts);
+        let outer_depth = std::mem::take(&mut self.branch_depth);
+        let outer_loops = std::mem::take(&mut self.loop_depths);
+        let start = self.candidates.len();
+        visit(self);
+
+        if let Some(body) = body {
+            let ranges = FunctionRanges::analyze(sig, body, &self.consts, &self.arrays);
+            for candidate in &mut self.candidates[start..] {
+                if candidate.callee_span.is_none() && candidate.location == path {
+                    bound_offset(candidate, &ranges, &self.consts);
+                }
+            }
+        }
+
+        // Accesses into parameters are summarized for the callers.
+        let mut accesses = std::mem::replace(&mut self.propagated, outer_propagated);
+        for candidate in &self.candidates[start..] {
+            if candidate.callee_span.is_none()
+                && candidate.location == path
+                && SUMMARY_OPERATIONS.contains(&candidate.operation.as_str())
+                && let Some(parameter) = self.parameters.iter().position(|name| *name == candidate.buffer_name)
+            {
+                accesses.push((parameter, candidate.clone()));
+            }
+        }
+        if !accesses.is_empty() {
+            let parameters = self.parameters.clone();
+            self.summaries.insert(path, Summary { parameters, accesses });
+        }
+
+        self.current_function = outer_function;
+        self.conditions = outer;
+        self.assumptions = outer_assumptions;
+        self.parameters = outer_parameters;
+        self.pointers = outer_pointers;
+        self.layouts = outer_layouts;
+        self.branch_depth = outer_depth;
+        self.loop_depths = outer_loops;
+        self.item_path.pop();
+    }
+
+    /// The summary a call refers to, looking the called path up from the innermost
+    /// enclosing item outwards, e.g. `helper` inside `mod io` as `io::helper`.
+    fn summary_of(&self, func: &syn::ExprPath) -> Option<(String, Summary)> {
+        if func.qself.is_some() {
+            return None;
+        }
+        let called = func.path.segments.iter().map(|segment| segment.ident.to_string()).collect::<Vec<_>>().join("::");
+        (0..=self.item_path.len()).rev().find_map(|depth| {
+            let mut path = self.item_path[..depth].to_vec();
+            path.push(called.clone());
+            let path = path.join("::");
+            self.summaries.get(&path).map(|summary| (path, summary.clone()))
+        })
+    }
+
+    /// Checks the accesses of `callee`'s summary against the buffers passed at this call.
+    /// Accesses into the caller's own parameters are passed on to its summary instead.
+    fn instantiate(&mut self, callee: &str, summary: &Summary, args: &[&Expr], span: proc_macro2::Span) {
+        let buffers: Vec<Option<PointerInfo>> = args.iter().map(|arg| self.argument_buffer(arg)).collect();
+        let bindings: HashMap<String, String> = summary.parameters.iter().zip(args.iter().zip(&buffers))
+            .map(|(parameter, (arg, buffer))| {
+                let value = match buffer {
+                    Some(info) => info.buffer_name.clone(),
+                    None if matches!(arg, Expr::Lit(_) | Expr::Path(_)) => expr_text(arg),
+                    None => format!("({})", expr_text(arg)),
+                };
+                (parameter.clone(), value)
+            })
+            .collect();
+        let prefix = callee.rsplit("::").next().unwrap_or(callee);
+        let substituted = |text: &String| substitute(text, &bindings, prefix, &mut Vec::new());
+
+        for (parameter, access) in &summary.accesses {
+            let Some(Some(info)) = buffers.get(*parameter) else {
+                continue;
+            };
+            if info.invalidation.is_some() {
+                continue;
+            }
+            let mut candidate = self.candidate(info, &access.operation, span);
+            // Accesses passed on from a callee's own calls keep the innermost access.
+            candidate.callee_span = Some(access.callee_span.clone().unwrap_or_else(|| access.span.clone()));
+            candidate.callee = Some(access.callee.clone().unwrap_or_else(|| access.location.clone()));
+            candidate.pointer_name = access.pointer_name.clone();
+            candidate.pointer_method = access.pointer_method.clone();
+            candidate.offset = access.offset;
+            let mut renamed = Vec::new();
+            candidate.offset_expr = access.offset_expr.as_ref().map(|text| substitute(text, &bindings, prefix, &mut renamed));
+            candidate.count_expr = access.count_expr.as_ref().map(|text| substitute(text, &bindings, prefix, &mut renamed));
+            candidate.callee_locals = renamed;
+            candidate.origin_buffer = access.origin_buffer.as_ref().map(substituted);
+            candidate.origin_offset = access.origin_offset.as_ref().map(substituted);
+            // The callee may see the elements under another type than the caller.
+            let accessed = access.element_type.clone().or(access.buffer_element_type.clone());
+            candidate.element_type = accessed.filter(|ty| Some(ty) != info.buffer_element_type.as_ref());
+            candidate.path_conditions.extend(access.path_conditions.iter().map(substituted));
+            candidate.assumptions.extend(access.assumptions.iter().map(substituted));
+
+            if let Some(parameter) = self.parameters.iter().position(|name| *name == info.buffer_name)
+                && info.len_is_unknown()
+            {
+                self.propagated.push((parameter, candidate));
+                continue;
+            }
+            self.candidates.push(candidate);
+        }
+    }
+
+    /// The buffer an argument passes: `&mut buf`, `buf`, or a pointer into it.
+    fn argument_buffer(&self, arg: &Expr) -> Option<PointerInfo> {
+        match arg {
+            Expr::Reference(reference) => self.argument_buffer(&reference.expr),
+            Expr::Paren(paren) => self.argument_buffer(&paren.expr),
+            Expr::Path(path) => {
+                let name = path.path.get_ident()?.to_string();
+                match self.pointers.get(&name) {
+                    Some(info) => Some(info.clone()),
+                    None => self.parameters.contains(&name).then(|| PointerInfo::from_len(name, None, None)),
+                }
+            }
+            arg => self.pointer_of(arg).or_else(|| self.tracked_buffer(arg)),
+        }
+    }
+
+    /// Marks the pointer expression written through by `*target = ...`, `target.write(x)`
+    /// or `ptr::write(target, x)`, or borrowed as `&mut *target`.
+    fn mark_write(&mut self, target: &Expr) {
+        self.writes.push(pointer_span(target));
+    }
+
+    /// Marks the pointer expression read through by `*source`, `source.read()`,
+    /// `ptr::read(source)` or a copy from `source`.
+    fn mark_read(&mut self, source: &Expr) {
+        self.reads.push(pointer_span(source));
+    }
+}
+
+/// Span of a pointer expression, seeing through parentheses.
+fn pointer_span(pointer: &Expr) -> SourceSpan {
+    match pointer {
+        Expr::Paren(paren) => pointer_span(&paren.expr),
+        Expr::Group(group) => pointer_span(&group.expr),
+        pointer => SourceSpan::from_span(pointer.span()),
+    }
+}
+
+/// Pointer methods that read or write the pointee in place.
+const POINTER_ACCESSES: [&str; 8] = [
+    "read", "write", "read_unaligned", "write_unaligned", "read_volatile", "write_volatile", "replace", "swap",
+];
+
+/// Functions of `std::alloc` and methods of `GlobalAlloc`.
+const ALLOCATOR_FUNCTIONS: [&str; 4] = ["alloc", "alloc_zeroed", "realloc", "dealloc"];
+
+/// Methods that give another view of the same buffer, e.g. `String::as_mut_vec`.
+const BUFFER_VIEWS: [&str; 8] = [
+    "as_mut_vec", "as_bytes", "as_bytes_mut", "as_slice", "as_mut_slice", "as_str", "as_mut_str", "leak",
+];
+
+/// `Vec` methods that change its length or capacity.
+const VEC_METHODS: [&str; 12] = [
+    "push", "insert", "pop", "extend_from_slice", "push_str", "resize", "truncate", "clear", "reserve", "reserve_exact",
+    "set_len", "shrink_to_fit",
+];
+
+/// Pointer methods that move a pointer by an offset; the `byte_` forms count bytes.
+pub(crate) const POINTER_METHODS: [&str; 12] = [
+    "add", "sub", "offset",
+    "wrapping_add", "wrapping_sub", "wrapping_offset",
+    "byte_add", "byte_sub", "byte_offset",
+    "wrapping_byte_add", "wrapping_byte_sub", "wrapping_byte_offset",
+];
+
+
+impl<'ast> Visit<'ast> for OverflowVisitor {
+    fn visit_item_fn(&mut self, func: &'ast ItemFn) {
+        self.visit_function(&func.sig, Some(&func.block), |v| visit::visit_item_fn(v, func));
+    }
+
+    fn visit_impl_item_fn(&mut self, method: &'ast ImplItemFn) {
+        self.visit_function(&method.sig, Some(&method.block), |v| visit::visit_impl_item_fn(v, method));
+    }
+
+    fn visit_trait_item_fn(&mut self, method: &'ast TraitItemFn) {
+        self.visit_function(&method.sig, method.default.as_ref(), |v| visit::visit_trait_item_fn(v, method));
+    }
+
+    fn visit_item_mod(&mut self, module: &'ast ItemMod) {
+        self.item_path.push(module.ident.to_string());
+        visit::visit_item_mod(self, module);
+        self.item_path.pop();
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast ItemImpl) {
+        self.item_path.push(self_type_name(&item.self_ty));
+        let outer = self.self_type.replace(self_type_name(&item.self_ty));
+        visit::visit_item_impl(self, item);
+        self.self_type = outer;
+        self.item_path.pop();
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast ItemTrait) {
+        self.item_path.push(item.ident.to_string());
+        visit::visit_item_trait(self, item);
+        self.item_path.pop();
+    }
+
+    fn visit_expr_closure(&mut self, closure: &'ast ExprClosure) {
+        // A closure may run any number of times, or not at all.
+        self.with_condition(None, |v| visit::visit_expr_closure(v, closure));
+    }
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        let depth = self.conditions.len();
+        let assumptions = self.assumptions.len();
+        self.pointers.push();
+        for stmt in &block.stmts {
+            let facts = self.assumptions.len();
+            self.visit_stmt(stmt);
+            self.forget_changed(stmt, facts);
+            // Whatever follows an early return or an assertion only runs if it did not fire.
+            self.conditions.extend(condition_after(stmt).map(Some));
+            if let Stmt::Local(local) = stmt {
+                self.assumptions.extend(local_assumptions(local).into_iter().map(Some));
+            }
+        }
+        self.conditions.truncate(depth);
+        self.assumptions.truncate(assumptions);
+
+        // Buffers declared in the block are dropped at its end, also under a pointer the
+        // block evaluates to.
+        let line = block.brace_token.span.close().start().line;
+        let mut value = block_value(block).and_then(|value| self.pointer_of(value));
+        for buffer in self.pointers.pop() {
+            if self.frees(&buffer) {
+                self.invalidate(&buffer, "end of scope", line, true);
+                if let Some(value) = &mut value
+                    && value.same_allocation(&buffer)
+                    && value.invalidation.is_none()
+                {
+                    value.invalidation = Some(Invalidation {
+                        operation: "end of scope".to_string(),
+                        line,
+                        certain: true,
+                        conditions: self.path_conditions(),
+                    });
+                    self.block_values.push((SourceSpan::from_span(block.span()), value.clone()));
+                }
+            }
+        }
+    }
+
+    fn visit_expr_for_loop(&mut self, expr: &'ast ExprForLoop) {
+        self.visit_expr(&expr.expr);
+        let bounds = match (&*expr.pat, &*expr.expr) {
+            (Pat::Ident(var), Expr::Range(range)) => range_condition(&var.ident.to_string(), range),
+            _ => None,
+        };
+        self.in_loop(bounds, |v| v.visit_block(&expr.body));
+    }
+
+    fn visit_expr_if(&mut self, expr: &'ast ExprIf) {
+        self.visit_expr(&expr.cond);
+        let cond = condition_text(&expr.cond);
+        self.with_condition(cond.clone(), |v| v.visit_block(&expr.then_branch));
+        if let Some((_, else_branch)) = &expr.else_branch {
+            self.with_condition(cond.map(|c| negate(&c)), |v| v.visit_expr(else_branch));
+        }
+    }
+
+    fn visit_expr_while(&mut self, expr: &'ast ExprWhile) {
+        self.visit_expr(&expr.cond);
+        self.in_loop(condition_text(&expr.cond), |v| v.visit_block(&expr.body));
+    }
+
+    fn visit_expr_loop(&mut self, expr: &'ast ExprLoop) {
+        self.in_loop(None, |v| v.visit_block(&expr.body));
+    }
+
+    fn visit_expr_unary(&mut self, expr: &'ast ExprUnary) {
+        // Assignments and borrows have marked the dereferences that do not read.
+        if let UnOp::Deref(_) = expr.op
+            && !self.writes.contains(&pointer_span(&expr.expr))
+        {
+            self.mark_read(&expr.expr);
+        }
+        if let UnOp::Deref(_) = expr.op
+            && let Expr::Path(_) = &*expr.expr
+            && let Some(info) = self.pointer_of(&expr.expr)
+        {
+            self.based_access(&info, &expr.expr, expr.span());
+        }
+        visit::visit_expr_unary(self, expr);
+    }
+
+    fn visit_expr_reference(&mut self, expr: &'ast syn::ExprReference) {
+        if let Expr::Unary(ExprUnary { op: UnOp::Deref(_), expr: target, .. }) = &*expr.expr {
+            self.mark_write(target);
+        }
+        visit::visit_expr_reference(self, expr);
+    }
+
+    fn visit_expr_raw_addr(&mut self, expr: &'ast syn::ExprRawAddr) {
+        if let Expr::Unary(ExprUnary { op: UnOp::Deref(_), expr: target, .. }) = &*expr.expr {
+            self.mark_write(target);
+        }
+        visit::visit_expr_raw_addr(self, expr);
+    }
+
+    fn visit_expr_assign(&mut self, expr: &'ast ExprAssign) {
+        if let Expr::Unary(ExprUnary { op: UnOp::Deref(_), expr: target, .. }) = &*expr.left {
+            self.mark_write(target);
+        }
+        // The right-hand side runs first, e.g. the `realloc` in `p = realloc(p, layout, n)`.
+        visit::visit_expr_assign(self, expr);
+        // A pointer assigned to a variable declared earlier, possibly in an outer block. Assigned
+        // in a branch or loop, the variable may still hold its old value afterwards, so it
+        // is no longer tracked.
+        if let Expr::Path(path) = &*expr.left
+            && let Some(name) = path.path.get_ident().map(|ident| ident.to_string())
+            && let Some(binding) = self.pointers.binding(&name)
+        {
+            let depth = binding.as_ref().map_or(0, |info| info.depth);
+            let info = match self.heap_allocation(&name, &expr.right) {
+                Some(info) => {
+                    self.allocations += 1;
+                    Some(PointerInfo { allocation: self.allocations, ..info })
+                }
+                None => self.pointer_of(&expr.right),
+            };
+            let info = info.filter(|_| self.branch_depth <= depth);
+            self.pointers.assign(&name, info.map(|info| PointerInfo { depth, ..info }));
+        }
+    }
+
+    fn visit_expr_match(&mut self, expr: &'ast ExprMatch) {
+        self.visit_expr(&expr.expr);
+        let scrutinee = expr_text(&expr.expr);
+        // Arms are tried in order, so each one also implies that no earlier unguarded arm matched.
+        let mut excluded = Vec::new();
+        for arm in &expr.arms {
+            let pattern = pattern_condition(&scrutinee, &arm.pat);
+            let guard = arm.guard.as_ref().and_then(|(_, guard)| condition_text(guard));
+
+            let depth = self.conditions.len();
+            self.conditions.extend(excluded.iter().cloned().map(Some));
+            self.conditions.extend(pattern.clone().map(Some));
+            if let Some((_, guard)) = &arm.guard {
+                self.visit_expr(guard);
+            }
+            self.conditions.extend(guard.clone().map(Some));
+            self.branch_depth += 1;
+            self.visit_expr(&arm.body);
+            self.branch_depth -= 1;
+            self.conditions.truncate(depth);
+
+            if arm.guard.is_none()
+                && let Some(pattern) = pattern
+            {
+                excluded.push(negate(&pattern));
+            }
+        }
+    }
+    
+    fn visit_local(&mut self, local: &'ast Local) {
+        let (pat, annotation) = match &local.pat {
+            Pat::Type(typed) => (&*typed.pat, Some(&*typed.ty)),
+            pat => (pat, None),
+        };
+        let Pat::Ident(pat_ident) = pat else {
+            visit::visit_local(self, local);
+            if let Pat::Tuple(tuple) = pat
+                && let Some(init) = &local.init
+            {
+                self.bind_raw_parts(tuple, &init.expr);
+            }
+            return;
+        };
+        let var_name = pat_ident.ident.to_string();
+        let init = local.init.as_ref().map(|init| &*init.expr);
+        match init.and_then(|init| self.layout_of(init)) {
+            Some(layout) => {
+                self.layouts.insert(var_name.clone(), layout);
+            }
+            None => {
+                self.layouts.remove(&var_name);
+            }
+        }
+        let annotated = annotation.and_then(|ty| typed_buffer(&var_name, ty, &self.consts));
+        let mut reclaimed = false;
+        let info = if let Some(mut info) = init.and_then(|init| self.new_allocation(&var_name, init)) {
+            println!("Found buffer {} with length {:?} and capacity {:?}", var_name, info.len(), info.capacity);
+            info.buffer_element_type = annotated.and_then(|annotated| annotated.buffer_element_type).or(info.buffer_element_type);
+            self.allocations += 1;
+            info.allocation = self.allocations;
+            // Memory from `std::alloc` has no owner to free it.
+            if init.and_then(|init| self.heap_allocation(&var_name, init)).is_none() {
+                // e.g. `Box::into_raw(Box::new(..))` or `ManuallyDrop::new(vec![..])`.
+                let ownership = if info.buffer_name != var_name || init.and_then(release_call).is_some() {
+                    Ownership::Raw
+                } else {
+                    Ownership::Owned {
+                        owner: Some(var_name.clone()),
+                        operation: "allocation".to_string(),
+                        line: local.let_token.span.start().line,
+                        conditions: self.path_conditions(),
+                    }
+                };
+                self.ownership.insert(info.allocation, ownership);
+            }
+            Some(info)
+        } else if let Some(info) = init.and_then(|init| self.reclaimed_buffer(&var_name, init)) {
+            reclaimed = true;
+            Some(info)
+        } else if let Some(info) = init.and_then(|init| self.tracked_buffer(init)) {
+            // Another name for the same buffer, e.g. `let v = unsafe { s.as_mut_vec() };`.
+            // Moving the buffer moves the duty to free it.
+            if let Some(Ownership::Owned { owner, .. }) = self.ownership.get_mut(&info.allocation)
+                && init.is_some_and(|init| matches!(init, Expr::Path(_)))
+                && *owner == Some(info.buffer_name.clone())
+            {
+                *owner = Some(var_name.clone());
+            }
+            Some(PointerInfo { buffer_name: var_name.clone(), ..info })
+        } else if let Some(info) = init.and_then(|init| self.pointer_of(init)) {
+            println!("Found pointer {} from buffer {} (size: {:?}, offset: {:?})",
+                   var_name, info.buffer_name, info.buffer_size, info.base_offset);
+            Some(info)
+        } else if let Some(mut info) = annotated {
+            // A buffer from elsewhere, whose length is known from its type at most.
+            self.allocations += 1;
+            info.allocation = self.allocations;
+            Some(info)
+        } else {
+            None
+        };
+        let info = info.map(|info| PointerInfo { depth: self.branch_depth, ..info });
+
+        // The initializer still sees the bindings the new one shadows.
+        visit::visit_local(self, local);
+        // A block initializer may evaluate to a pointer into a buffer it dropped, which is
+        // only known once it was visited.
+        let dropped = match init {
+            Some(Expr::Block(syn::ExprBlock { block, .. }) | Expr::Unsafe(ExprUnsafe { block, .. })) => {
+                self.dropped_value(block)
+            }
+            _ => None,
+        };
+        let info = dropped.map(|info| PointerInfo { depth: self.branch_depth, ..info }).or(info);
+        if reclaimed
+            && let Some(info) = &info
+            && let Some(Ownership::Owned { owner, .. }) = self.ownership.get_mut(&info.allocation)
+        {
+            *owner = Some(var_name.clone());
+        }
+        self.pointers.declare(var_name, info);
+    }
+    
+    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
+        let method_name = expr.method.to_string();
+        
+        let args: Vec<&Expr> = expr.args.iter().collect();
+        match method_name.as_str() {
+            "write" | "write_unaligned" | "write_volatile" => self.mark_write(&expr.receiver),
+            "read" | "read_unaligned" | "read_volatile" | "replace" | "swap" => self.mark_read(&expr.receiver),
+            _ => {}
+        }
+        if (method_name == "get_unchecked" || method_name == "get_unchecked_mut")
+            && let Some(index) = expr.args.first()
+            && let Some(buffer) = self.buffer_of(&expr.receiver)
+        {
+            self.unchecked_access(buffer, &method_name, index, expr.span());
+        } else if let ("copy_to" | "copy_to_nonoverlapping", [dst, count]) = (method_name.as_str(), args.as_slice()) {
+            self.bulk_copy(&method_name, &expr.receiver, dst, count, expr.span());
+        } else if let ("copy_from" | "copy_from_nonoverlapping", [src, count]) = (method_name.as_str(), args.as_slice()) {
+            self.bulk_copy(&method_name, src, &expr.receiver, count, expr.span());
+        } else if let ("write_bytes", [_, count]) = (method_name.as_str(), args.as_slice()) {
+            self.write_bytes(&method_name, &expr.receiver, count, expr.span());
+        } else if let Expr::Path(path) = &*expr.receiver
+            && let Some(name) = path.path.get_ident()
+            && VEC_METHODS.contains(&method_name.as_str())
+        {
+            self.vec_method(&name.to_string(), &method_name, &args, expr.span());
+        } else if let Some(ptr_info) = self.pointer_of(&expr.receiver) {
+            self.pointer_method(ptr_info, &expr.receiver, &method_name, expr.args.first(), expr.span());
+        }
+        
+        visit::visit_expr_method_call(self, expr);
+    }
+
+    /// Function-call forms such as `<*mut u8>::add(ptr, 3)`, `slice::from_raw_parts` and
+    /// `ptr::copy_nonoverlapping`.
+    fn visit_expr_call(&mut self, expr: &'ast ExprCall) {
+        let args: Vec<&Expr> = expr.args.iter().collect();
+        if let Expr::Path(func) = &*expr.func
+            && func.path.segments.len() > 1
+            && func.path.segments.iter().rev().nth(1).is_some_and(|segment| segment.ident == "ptr")
+            && let Some(function) = func.path.segments.last().map(|segment| segment.ident.to_string())
+        {
+            match (function.as_str(), args.as_slice()) {
+                ("copy" | "copy_nonoverlapping", [src, dst, count]) => self.bulk_copy(&function, src, dst, count, expr.span()),
+                ("write_bytes", [dst, _, count]) => self.write_bytes(&function, dst, count, expr.span()),
+                ("write" | "write_unaligned" | "write_volatile", [dst, _]) => self.mark_write(dst),
+                ("read" | "read_unaligned" | "read_volatile", [src]) => self.mark_read(src),
+                ("replace", [dst, _]) => self.mark_read(dst),
+                ("swap", [a, b]) => {
+                    self.mark_read(a);
+                    self.mark_read(b);
+                }
+                _ => {}
+            }
+        }
+        if let Expr::Path(func) = &*expr.func
+            && func.path.segments.last().is_some_and(|segment| segment.ident == "drop")
+            && let [Expr::Path(buffer)] = args.as_slice()
+            && let Some(name) = buffer.path.get_ident().map(|ident| ident.to_string())
+            && let Some(info) = self.pointers.buffer(&name).cloned()
+            && self.frees(&info)
+        {
+            self.invalidate(&info, "drop", expr.span().start().line, true);
+        }
+        if let Expr::Path(func) = &*expr.func
+            && (func.qself.is_some() || func.path.segments.len() > 1)
+            && let Some(method) = func.path.segments.last()
+            && let Some(receiver) = expr.args.first()
+            && let Some(ptr_info) = self.pointer_of(receiver)
+        {
+            let method = method.ident.to_string();
+            let module = func.path.segments.iter().rev().nth(1).map(|segment| segment.ident.to_string());
+            if (method == "from_raw_parts" || method == "from_raw_parts_mut") && module.as_deref() == Some("slice") {
+                if let Some(len) = expr.args.iter().nth(1) {
+                    self.raw_parts(ptr_info, &method, receiver, len, expr.span());
+                }
+            } else {
+                self.pointer_method(ptr_info, receiver, &method, expr.args.iter().nth(1), expr.span());
+            }
+        }
+
+        if let Expr::Path(func) = &*expr.func
+            && let Some((callee, summary)) = self.summary_of(func)
+        {
+            self.instantiate(&callee, &summary, &args, expr.span());
+        }
+
+        visit::visit_expr_call(self, expr);
+    }
+    
+    fn visit_expr_unsafe(&mut self, expr: &'ast ExprUnsafe) {
+        println!("Found unsafe block");
+        visit::visit_expr_unsafe(self, expr);
+    }
+    
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        if let Some((function, args)) = allocator_call(expr)
+            && let ("dealloc" | "realloc", [pointer, layout, ..]) = (function.as_str(), args.as_slice())
+        {
+            self.deallocate(&function, pointer, layout, expr.span());
+        }
+        if let Expr::Call(_) = expr
+            && let Some((function, args)) = reclaim_call(expr)
+        {
+            match args.as_slice() {
+                [pointer] => self.reclaim(&function, pointer, None, expr.span()),
+                [pointer, len, capacity] => self.reclaim(&function, pointer, Some((len, capacity)), expr.span()),
+                _ => {}
+            }
+        }
+        if let Some((_, value)) = release_call(expr) {
+            self.release(value);
+        }
+        visit::visit_expr(self, expr);
+    }
+}
+
+/// The variable a pointer expression reads, seeing through casts, e.g. `p` in
+/// `p as *mut u8`.
+fn pointer_variable(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) => Some(path.path.get_ident()?.to_string()),
+        Expr::Paren(paren) => pointer_variable(&paren.expr),
+        Expr::Cast(cast) => pointer_variable(&cast.expr),
+        Expr::MethodCall(call) if call.method == "cast" && call.args.is_empty() => pointer_variable(&call.receiver),
+        _ => None,
+    }
+}
+
+/// The `std::alloc` function an expression calls and its arguments, for the functions
+/// `alloc::alloc(layout)` and the like and the `GlobalAlloc` methods such as
+/// `System.alloc(layout)`.
+fn allocator_call(expr: &Expr) -> Option<(String, Vec<&Expr>)> {
+    let (function, args) = match expr {
+        Expr::Call(call) => {
+            let Expr::Path(func) = &*call.func else {
+                return None;
+            };
+            let segments: Vec<_> = func.path.segments.iter().collect();
+            // A bare `alloc(..)` was imported from `std::alloc`.
+            if segments.len() > 1 && segments[segments.len() - 2].ident != "alloc" {
+                return None;
+            }
+            (segments.last()?.ident.to_string(), call.args.iter().collect())
+        }
+        Expr::MethodCall(call) => (call.method.to_string(), call.args.iter().collect()),
+        _ => return None,
+    };
+    ALLOCATOR_FUNCTIONS.contains(&function.as_str()).then_some((function, args))
+}
+
+/// The call of an expression that takes back ownership from a raw pointer, as the last
+/// two segments of its path, and its arguments: `Box::from_raw(ptr)`,
+/// `Vec::from_raw_parts(ptr, len, cap)` or `String::from_raw_parts(ptr, len, cap)`.
+fn reclaim_call(expr: &Expr) -> Option<(String, Vec<&Expr>)> {
+    match expr {
+        Expr::Paren(paren) => reclaim_call(&paren.expr),
+        Expr::Group(group) => reclaim_call(&group.expr),
+        Expr::Unsafe(unsafe_expr) => reclaim_call(block_value(&unsafe_expr.block)?),
+        Expr::Call(call) => {
+            let Expr::Path(func) = &*call.func else {
+                return None;
+            };
+            let segments: Vec<String> = func.path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+            let [.., ty, function] = segments.as_slice() else {
+                return None;
+            };
+            let args: Vec<&Expr> = call.args.iter().collect();
+            match (ty.as_str(), function.as_str(), args.len()) {
+                ("Box", "from_raw", 1) | ("Vec" | "String", "from_raw_parts", 3) => Some((format!("{}::{}", ty, function), args)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The operation of an expression that releases a value to a raw pointer, and the value:
+/// `Box::into_raw(b)`, `Box::leak(b)`, `mem::forget(v)`, `ManuallyDrop::new(v)`,
+/// `Vec::into_raw_parts(v)` or the methods `v.into_raw_parts()` and `v.leak()`.
+fn release_call(expr: &Expr) -> Option<(String, &Expr)> {
+    match expr {
+        Expr::Call(call) => {
+            let Expr::Path(func) = &*call.func else {
+                return None;
+            };
+            let segments: Vec<String> = func.path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+            let [value] = call.args.iter().collect::<Vec<_>>()[..] else {
+                return None;
+            };
+            let released = match segments.as_slice() {
+                [.., function] if function == "forget" => true,
+                [.., ty, function] => matches!(
+                    (ty.as_str(), function.as_str()),
+                    ("Box", "into_raw" | "leak") | ("ManuallyDrop", "new") | ("Vec" | "String", "into_raw_parts" | "leak")
+                ),
+                _ => false,
+            };
+            released.then(|| (segments.join("::"), value))
+        }
+        Expr::MethodCall(call) if call.args.is_empty() && (call.method == "into_raw_parts" || call.method == "leak") => {
+            Some((call.method.to_string(), &*call.receiver))
+        }
+        _ => None,
+    }
+}
+
+/// Whether two sets of path conditions exclude each other, as on the two branches of an
+/// `if`.
+fn exclusive(a: &[String], b: &[String]) -> bool {
+    a.iter().any(|condition| b.contains(&negate(condition))) || b.iter().any(|condition| a.contains(&negate(condition)))
+}
+
+/// Determines the length of a `vec!` invocation.
+///
+/// Handles both the list form `vec![a, b, c]` and the repeat form `vec![elem; len]`.
+/// A repeat length that is not a constant expression is returned as `BufferLen::Symbolic`.
+pub(crate) fn extract_vec_size(mac: &syn::Macro, consts: &HashMap<String, usize>) -> Option<BufferLen> {
+    if let syn::MacroDelimiter::Bracket(_) = mac.delimiter {
+        if mac.tokens.is_empty() {
+            return Some(BufferLen::Known(0));
+        }
+        // `[..]` gives syn the same grammar the macro accepts: an array or a repeat expression.
+        let tokens = mac.tokens.clone();
+        if let Ok(expr) = syn::parse2::<syn::Expr>(quote::quote!([#tokens])) {
+            match expr {
+                syn::Expr::Array(array) => return Some(BufferLen::Known(array.elems.len())),
+                syn::Expr::Repeat(repeat) => {
+                    return Some(match eval_const_expr(&repeat.len, consts) {
+                        Some(len) => BufferLen::Known(len),
+                        None => BufferLen::Symbolic(repeat.len.to_token_stream().to_string()),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// Capacity and element type of an empty `Vec` from `Vec::new()` or
+/// `Vec::with_capacity(n)`, e.g. `Vec::<u8>::with_capacity(16)`, or of an empty `String`.
+fn vec_constructor(expr: &Expr, consts: &HashMap<String, usize>) -> Option<(BufferLen, Option<String>)> {
+    let Expr::Call(call) = expr else {
+        return None;
+    };
+    let Expr::Path(func) = &*call.func else {
+        return None;
+    };
+    let segments: Vec<_> = func.path.segments.iter().collect();
+    let [.., vec, constructor] = segments.as_slice() else {
+        return None;
+    };
+    let element_type = match &vec.arguments {
+        _ if vec.ident == "String" => Some("u8".to_string()),
+        _ if vec.ident != "Vec" => return None,
+        syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(syn::GenericArgument::Type(ty)) => Some(type_text(ty)),
+            _ => None,
+        },
+        _ => None,
+    };
+    let args: Vec<&Expr> = call.args.iter().collect();
+    let capacity = match (constructor.ident.to_string().as_str(), args.as_slice()) {
+        ("new", []) => BufferLen::Known(0),
+        ("with_capacity", [capacity]) => match eval_const_expr(capacity, consts) {
+            Some(capacity) => BufferLen::Known(capacity),
+            None => BufferLen::Symbolic(expr_text(capacity)),
+        },
+        _ => return None,
+    };
+    Some((capacity, element_type))
+}
+
+/// Element type of a `vec!` as written in its elements, e.g. `u8` for `vec![0u8; 10]`.
+///
+/// Unsuffixed integer and float literals are left to type inference and give `None`.
+fn vec_element_type(mac: &syn::Macro) -> Option<String> {
+    let tokens = mac.tokens.clone();
+    let elem = match syn::parse2::<syn::Expr>(quote::quote!([#tokens])).ok()? {
+        syn::Expr::Array(array) => array.elems.into_iter().next()?,
+        syn::Expr::Repeat(repeat) => *repeat.expr,
+        _ => return None,
+    };
+    literal_type(&elem)
+}
+
+/// Length in bytes of a string literal such as `"abc"` or `b"abc"`.
+fn string_literal_len(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Str(text), .. }) => Some(text.value().len()),
+        Expr::Lit(ExprLit { lit: Lit::ByteStr(bytes), .. }) => Some(bytes.value().len()),
+        Expr::Reference(reference) => string_literal_len(&reference.expr),
+        _ => None,
+    }
+}
+
+fn literal_type(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(ExprLit { lit, .. }) => match lit {
+            Lit::Int(int) if !int.suffix().is_empty() => Some(int.suffix().to_string()),
+            Lit::Float(float) if !float.suffix().is_empty() => Some(float.suffix().to_string()),
+            Lit::Bool(_) => Some("bool".to_string()),
+            Lit::Char(_) => Some("char".to_string()),
+            Lit::Byte(_) => Some("u8".to_string()),
+            _ => None,
+        },
+        Expr::Cast(cast) => Some(type_text(&cast.ty)),
+        Expr::Paren(paren) => literal_type(&paren.expr),
+        Expr::Unary(unary) => literal_type(&unary.expr),
+        _ => None,
+    }
+}
+
+/// The buffer a value of type `ty` named `name` owns or borrows: an array has its declared
+/// length, while a slice, `Vec`, boxed slice or string has the symbolic length `name.len()`.
+fn typed_buffer(name: &str, ty: &Type, consts: &HashMap<String, usize>) -> Option<PointerInfo> {
+    let symbolic = || Some(BufferLen::Symbolic(format!("{}.len()", name)));
+    match ty {
+        Type::Reference(reference) => typed_buffer(name, &reference.elem, consts),
+        Type::Paren(paren) => typed_buffer(name, &paren.elem, consts),
+        Type::Group(group) => typed_buffer(name, &group.elem, consts),
+        Type::Array(array) => {
+            let len = match eval_const_expr(&array.len, consts) {
+                Some(len) => BufferLen::Known(len),
+                None => BufferLen::Symbolic(expr_text(&array.len)),
+            };
+            Some(PointerInfo::from_len(name.to_string(), Some(len), Some(type_text(&array.elem))))
+        }
+        Type::Slice(slice) => Some(PointerInfo::from_len(name.to_string(), symbolic(), Some(type_text(&slice.elem)))),
+        Type::Path(path) => {
+            let segment = path.path.segments.last()?;
+            let argument = match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+                    Some(syn::GenericArgument::Type(ty)) => Some(ty),
+                    _ => None,
+                },
+                _ => None,
+            };
+            match segment.ident.to_string().as_str() {
+                "Vec" => {
+                    let element_type = argument.filter(|ty| !matches!(ty, Type::Infer(_))).map(type_text);
+                    Some(PointerInfo::from_len(name.to_string(), symbolic(), element_type))
+                }
+                "Box" => typed_buffer(name, argument?, consts),
+                "String" | "str" => Some(PointerInfo::from_len(name.to_string(), symbolic(), Some("u8".to_string()))),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Source text of a type, e.g. `u32` or `[u8; 4]`.
+pub(crate) fn type_text(ty: &Type) -> String {
+    expr_text(&Expr::Verbatim(ty.to_token_stream())).replace(" ;", ";")
+}
+
+/// Collects every `const` item of the file whose value evaluates to an integer.
+///
+/// Constants may refer to each other in any order, so evaluation is repeated until no new
+/// value can be resolved.
+fn collect_consts(ast: &File) -> HashMap<String, usize> {
+    struct ConstCollector<'ast> {
+        items: Vec<&'ast ItemConst>,
+    }
+
+    impl<'ast> Visit<'ast> for ConstCollector<'ast> {
+        fn visit_item_const(&mut self, item: &'ast ItemConst) {
+            self.items.push(item);
+            visit::visit_item_const(self, item);
+        }
+    }
+
+    let mut collector = ConstCollector { items: Vec::new() };
+    collector.visit_file(ast);
+
+    let mut consts = HashMap::new();
+    loop {
+        let mut changed = false;
+        for item in &collector.items {
+            let name = item.ident.to_string();
+            if consts.contains_key(&name) {
+                continue;
+            }
+            if let Some(value) = eval_const_expr(&item.expr, &consts) {
+                consts.insert(name, value);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    consts
+}
+
+/// Named fields of every struct of the file, by struct name.
+fn collect_fields(ast: &File) -> HashMap<String, HashMap<String, Type>> {
+    struct FieldCollector {
+        structs: HashMap<String, HashMap<String, Type>>,
+    }
+
+    impl<'ast> Visit<'ast> for FieldCollector {
+        fn visit_item_struct(&mut self, item: &'ast ItemStruct) {
+            let fields = item.fields.iter()
+                .filter_map(|field| Some((field.ident.as_ref()?.to_string(), field.ty.clone())))
+                .collect();
+            self.structs.insert(item.ident.to_string(), fields);
+            visit::visit_item_struct(self, item);
+        }
+    }
+
+    let mut collector = FieldCollector { structs: HashMap::new() };
+    collector.visit_file(ast);
+    collector.structs
+}
+
+/// Evaluates integer literals, known constants and arithmetic over them.
+///
+/// Returns `None` for anything that depends on runtime values or overflows `usize`.
+pub fn eval_const_expr(expr: &Expr, consts: &HashMap<String, usize>) -> Option<usize> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) => lit.base10_parse().ok(),
+        Expr::Paren(paren) => eval_const_expr(&paren.expr, consts),
+        Expr::Group(group) => eval_const_expr(&group.expr, consts),
+        Expr::Cast(cast) => eval_const_expr(&cast.expr, consts),
+        Expr::Block(block) if block.block.stmts.len() == 1 => match &block.block.stmts[0] {
+            syn::Stmt::Expr(inner, None) => eval_const_expr(inner, consts),
+            _ => None,
+        },
+        Expr::Path(path) => {
+            let name = path.path.segments.last()?.ident.to_string();
+            consts.get(&name).copied()
+        }
+        Expr::Binary(binary) => {
+            let left = eval_const_expr(&binary.left, consts)?;
+            let right = eval_const_expr(&binary.right, consts)?;
+            match binary.op {
+                BinOp::Add(_) => left.checked_add(right),
+                BinOp::Sub(_) => left.checked_sub(right),
+                BinOp::Mul(_) => left.checked_mul(right),
+                BinOp::Div(_) => left.checked_div(right),
+                BinOp::Rem(_) => left.checked_rem(right),
+                BinOp::Shl(_) => u32::try_from(right).ok().and_then(|r| left.checked_shl(r)),
+                BinOp::Shr(_) => u32::try_from(right).ok().and_then(|r| left.checked_shr(r)),
+                BinOp::BitAnd(_) => Some(left & right),
+                BinOp::BitOr(_) => Some(left | right),
+                BinOp::BitXor(_) => Some(left ^ right),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Source text of an expression with the token spacing of `to_token_stream` tidied up,
+/// e.g. `buffer.len() - 1` rather than `buffer . len () - 1`.
+pub(crate) fn expr_text(expr: &Expr) -> String {
+    let text = expr.to_token_stream().to_string();
+    let tidy = [
+        (r"\s*(\.|::)\s*", "$1"),
+        (r"([\w)>]) ([(\[])", "$1$2"),
+        (r"([(\[]) ", "$1"),
+        (r" ([)\],])", "$1"),
+        (r"(^|[(\[]|, )- ", "$1-"),
+    ];
+    tidy.iter().fold(text, |text, (pattern, replacement)| {
+        regex::Regex::new(pattern).unwrap().replace_all(&text, *replacement).to_string()
+    })
+}
+
+const UNSIGNED_TYPES: [&str; 6] = ["usize", "u8", "u16", "u32", "u64", "u128"];
+
+/// Sets the offset range of an access from the variable ranges where it executes, and
+/// adds the non-negative ranges of the variables its offset and length use to its
+/// assumptions, e.g. `i <= 10` in `for i in 0..=10`.
+fn bound_offset(candidate: &mut OverflowCandidate, ranges: &FunctionRanges, consts: &HashMap<String, usize>) {
+    let Some(env) = ranges.at(&candidate.span) else {
+        return;
+    };
+    candidate.offset_range = match (candidate.offset, &candidate.offset_expr) {
+        (Some(offset), _) => Some(Interval::constant(offset as i128)),
+        (None, Some(expr)) => interval::eval_text(expr, env, consts).filter(|range| *range != Interval::TOP),
+        (None, None) => None,
+    };
+    let texts = candidate.offset_expr.iter().chain(&candidate.count_expr);
+    let mut names: Vec<String> = texts
+        .filter_map(|text| syn::parse_str::<Expr>(text).ok())
+        .flat_map(|expr| variables(&expr))
+        .collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let Some(range) = env.get(&name) else {
+            continue;
+        };
+        match (range.lo, range.hi) {
+            (Some(lo), hi) if lo >= 0 => {
+                if lo > 0 {
+                    candidate.assumptions.push(format!("{} >= {}", name, lo));
+                }
+                if let Some(hi) = hi {
+                    candidate.assumptions.push(format!("{} <= {}", name, hi));
+                }
+                // A loop over an array of known values takes no values in between.
+                if let Some(values) = ranges.values_at(&candidate.span, &name)
+                    && !values.is_empty()
+                {
+                    let choices: Vec<String> = values.iter().map(|value| format!("{} == {}", name, value)).collect();
+                    candidate.assumptions.push(choices.join(" || "));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Names of the plain variables an expression reads, e.g. `i` and `n` in `i + n * 2`.
+fn variables(expr: &Expr) -> Vec<String> {
+    struct Variables(Vec<String>);
+    impl<'ast> Visit<'ast> for Variables {
+        fn visit_expr_path(&mut self, path: &'ast syn::ExprPath) {
+            if let Some(ident) = path.path.get_ident() {
+                self.0.push(ident.to_string());
+            }
+        }
+    }
+    let mut variables = Variables(Vec::new());
+    variables.visit_expr(expr);
+    variables.0
+}
+
+fn is_unsigned(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.path.get_ident().is_some_and(|ident| UNSIGNED_TYPES.contains(&ident.to_string().as_str())),
+        Type::Reference(reference) => is_unsigned(&reference.elem),
+        Type::Paren(paren) => is_unsigned(&paren.elem),
+        _ => false,
+    }
+}
+
+/// Variables a statement may change: assigned to, compound-assigned, bound again by `let`
+/// or borrowed mutably, e.g. `i` in `i += 1` or `read(&mut i)`. A field or element
+/// counts as its variable, e.g. `s` in `s.len = 0`.
+fn changed_variables(stmt: &Stmt) -> Vec<String> {
+    struct Changed(Vec<String>);
+    impl Changed {
+        fn place(&mut self, expr: &Expr) {
+            match expr {
+                Expr::Path(path) => self.0.extend(path.path.get_ident().map(|ident| ident.to_string())),
+                Expr::Field(field) => self.place(&field.base),
+                Expr::Index(index) => self.place(&index.expr),
+                Expr::Paren(paren) => self.place(&paren.expr),
+                _ => {}
+            }
+        }
+    }
+    impl<'ast> Visit<'ast> for Changed {
+        fn visit_local(&mut self, local: &'ast Local) {
+            struct Bound<'a>(&'a mut Vec<String>);
+            impl<'ast> Visit<'ast> for Bound<'_> {
+                fn visit_pat_ident(&mut self, ident: &'ast syn::PatIdent) {
+                    self.0.push(ident.ident.to_string());
+                    visit::visit_pat_ident(self, ident);
+                }
+            }
+            Bound(&mut self.0).visit_pat(&local.pat);
+            visit::visit_local(self, local);
+        }
+        fn visit_expr_assign(&mut self, expr: &'ast ExprAssign) {
+            self.place(&expr.left);
+            visit::visit_expr_assign(self, expr);
+        }
+        fn visit_expr_binary(&mut self, expr: &'ast syn::ExprBinary) {
+            if matches!(expr.op,
+                BinOp::AddAssign(_) | BinOp::SubAssign(_) | BinOp::MulAssign(_) | BinOp::DivAssign(_) | BinOp::RemAssign(_)
+                | BinOp::BitXorAssign(_) | BinOp::BitAndAssign(_) | BinOp::BitOrAssign(_) | BinOp::ShlAssign(_) | BinOp::ShrAssign(_))
+            {
+                self.place(&expr.left);
+            }
+            visit::visit_expr_binary(self, expr);
+        }
+        fn visit_expr_reference(&mut self, expr: &'ast syn::ExprReference) {
+            if expr.mutability.is_some() {
+                self.place(&expr.expr);
+            }
+            visit::visit_expr_reference(self, expr);
+        }
+        fn visit_expr_raw_addr(&mut self, expr: &'ast syn::ExprRawAddr) {
+            if let syn::PointerMutability::Mut(_) = expr.mutability {
+                self.place(&expr.expr);
+            }
+            visit::visit_expr_raw_addr(self, expr);
+        }
+        // Nested items do not run as part of the statement.
+        fn visit_item(&mut self, _: &'ast syn::Item) {}
+    }
+    let mut changed = Changed(Vec::new());
+    changed.visit_stmt(stmt);
+    changed.0
+}
+
+/// Whether a condition or fact reads any of `names`; one that cannot be parsed is assumed
+/// to.
+fn mentions(text: &str, names: &[String]) -> bool {
+    match syn::parse_str::<Expr>(text) {
+        Ok(expr) => variables(&expr).iter().any(|name| names.contains(name)),
+        Err(_) => true,
+    }
+}
+
+/// `x >= 0` for every parameter of an unsigned integer type.
+fn parameter_assumptions(sig: &syn::Signature) -> Vec<String> {
+    sig.inputs.iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(arg) if is_unsigned(&arg.ty) => match &*arg.pat {
+                Pat::Ident(ident) => Some(format!("{} >= 0", ident.ident)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Name of an `impl` block's type as it appears in item paths, e.g. `Buffer` for
+/// `impl<T> Buffer<T>`.
+pub(crate) fn self_type_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.to_string()).unwrap_or_default(),
+        ty => type_text(ty),
+    }
+}
+
+fn parameter_names(sig: &syn::Signature) -> Vec<String> {
+    sig.inputs.iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(arg) => match &*arg.pat {
+                Pat::Ident(ident) => Some(ident.ident.to_string()),
+                _ => Some("_".to_string()),
+            },
+            FnArg::Receiver(_) => None,
+        })
+        .collect()
+}
+
+/// Bindings of the parameters, where raw pointers each stand for an allocation of unknown
+/// size and arrays, slices, `Vec`s and strings are buffers of their own.
+fn parameter_pointers(sig: &syn::Signature, consts: &HashMap<String, usize>) -> Scopes {
+    let parameters = sig.inputs.iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(arg) => match (&*arg.pat, &*arg.ty) {
+                (Pat::Ident(ident), Type::Ptr(ptr)) => {
+                    let name = ident.ident.to_string();
+                    Some((name.clone(), Some(PointerInfo::from_len(name, None, Some(type_text(&ptr.elem))))))
+                }
+                (Pat::Ident(ident), ty) => {
+                    let name = ident.ident.to_string();
+                    Some((name.clone(), typed_buffer(&name, ty, consts)))
+                }
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+    Scopes { frames: vec![parameters] }
+}
+
+/// Rewrites an expression from a callee's summary into the caller's terms: parameters
+/// become the arguments bound to them, and the callee's locals are prefixed with its name
+/// to keep them apart from the caller's. The renamed locals are added to `renamed`.
+fn substitute(text: &str, bindings: &HashMap<String, String>, callee: &str, renamed: &mut Vec<String>) -> String {
+    fn rewrite(
+        tokens: proc_macro2::TokenStream,
+        bindings: &HashMap<String, String>,
+        callee: &str,
+        renamed: &mut Vec<String>,
+    ) -> proc_macro2::TokenStream {
+        use proc_macro2::{Group, TokenTree};
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        let punct = |token: Option<&TokenTree>, ch: char| matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == ch);
+        let mut rewritten = Vec::new();
+        for (i, token) in tokens.iter().enumerate() {
+            let previous = i.checked_sub(1).and_then(|i| tokens.get(i));
+            match token {
+                TokenTree::Group(group) => {
+                    let mut inner = Group::new(group.delimiter(), rewrite(group.stream(), bindings, callee, renamed));
+                    inner.set_span(group.span());
+                    rewritten.push(TokenTree::Group(inner));
+                }
+                // Method and field names, path segments, types after `as` and called
+                // functions stay as they are.
+                TokenTree::Ident(ident)
+                    if !punct(previous, '.')
+                        && !punct(previous, ':')
+                        && !punct(tokens.get(i + 1), ':')
+                        && !matches!(previous, Some(TokenTree::Ident(previous)) if previous == "as")
+                        && !matches!(tokens.get(i + 1), Some(TokenTree::Group(group)) if group.delimiter() == proc_macro2::Delimiter::Parenthesis)
+                        && !matches!(ident.to_string().as_str(), "as" | "true" | "false" | "self")
+                        && !ident.to_string().starts_with(|c: char| c.is_ascii_uppercase()) =>
+                {
+                    let name = ident.to_string();
+                    let replacement = match bindings.get(&name) {
+                        Some(bound) => bound.clone(),
+                        None => {
+                            let local = format!("{}_{}", callee, name);
+                            if !renamed.contains(&local) {
+                                renamed.push(local.clone());
+                            }
+                            local
+                        }
+                    };
+                    match replacement.parse::<proc_macro2::TokenStream>() {
+                        Ok(stream) => rewritten.extend(stream),
+                        Err(_) => rewritten.push(token.clone()),
+                    }
+                }
+                token => rewritten.push(token.clone()),
+            }
+        }
+        rewritten.into_iter().collect()
+    }
+
+    let Ok(tokens) = text.parse::<proc_macro2::TokenStream>() else {
+        return text.to_string();
+    };
+    let rewritten = rewrite(tokens, bindings, callee, renamed);
+    match syn::parse2::<Expr>(rewritten.clone()) {
+        Ok(expr) => expr_text(&expr),
+        Err(_) => rewritten.to_string(),
+    }
+}
+
+/// Facts introduced by a `let`: the sign of an unsigned annotation and, for immutable
+/// bindings of arithmetic expressions, the defining equation. A binding that shadows a
+/// name its initializer reads, as in `let len = len + 1`, has no equation, since the two
+/// `len`s are different values.
+fn local_assumptions(local: &Local) -> Vec<String> {
+    let (ident, ty) = match &local.pat {
+        Pat::Ident(ident) => (ident, None),
+        Pat::Type(typed) => match &*typed.pat {
+            Pat::Ident(ident) => (ident, Some(&*typed.ty)),
+            _ => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+    let name = ident.ident.to_string();
+
+    let mut facts = Vec::new();
+    if ty.is_some_and(is_unsigned) {
+        facts.push(format!("{} >= 0", name));
+    }
+    if ident.mutability.is_none()
+        && let Some(init) = &local.init
+        && init.diverge.is_none()
+        && is_arithmetic(&init.expr)
+        && !variables(&init.expr).contains(&name)
+    {
+        facts.push(format!("{} == {}", name, expr_text(&init.expr)));
+    }
+    facts
+}
+
+/// Whether an expression only combines literals, variables and `len()` calls arithmetically.
+fn is_arithmetic(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(_), .. }) => true,
+        Expr::Path(path) => path.path.get_ident().is_some(),
+        Expr::Paren(paren) => is_arithmetic(&paren.expr),
+        Expr::Group(group) => is_arithmetic(&group.expr),
+        Expr::Cast(cast) => is_arithmetic(&cast.expr),
+        Expr::MethodCall(call) => call.method == "len" && call.args.is_empty() && matches!(&*call.receiver, Expr::Path(_)),
+        Expr::Binary(binary) => {
+            matches!(binary.op, BinOp::Add(_) | BinOp::Sub(_) | BinOp::Mul(_) | BinOp::Div(_) | BinOp::Rem(_))
+                && is_arithmetic(&binary.left)
+                && is_arithmetic(&binary.right)
+        }
+        _ => false,
+    }
+}
+
+/// Bounds of a loop variable iterating over `start..end` or `start..=end`.
+fn range_condition(var: &str, range: &syn::ExprRange) -> Option<String> {
+    let start = range.start.as_ref()?;
+    let mut bounds = vec![format!("{} >= {}", var, expr_text(start))];
+    if let Some(end) = &range.end {
+        let op = match range.limits {
+            syn::RangeLimits::Closed(_) => "<=",
+            syn::RangeLimits::HalfOpen(_) => "<",
+        };
+        bounds.push(format!("{} {} {}", var, op, expr_text(end)));
+    }
+    Some(bounds.join(" && "))
+}
+
+fn negate(condition: &str) -> String {
+    format!("!({})", condition)
+}
+
+/// Text of a branch condition, or `None` for `if let`/`while let` patterns.
+fn condition_text(cond: &Expr) -> Option<String> {
+    match cond {
+        Expr::Let(_) => None,
+        Expr::Binary(binary) if matches!(binary.op, BinOp::And(_)) && contains_let(cond) => None,
+        _ => Some(expr_text(cond)),
+    }
+}
+
+fn contains_let(expr: &Expr) -> bool {
+    match expr {
+        Expr::Let(_) => true,
+        Expr::Binary(binary) => contains_let(&binary.left) || contains_let(&binary.right),
+        Expr::Paren(paren) => contains_let(&paren.expr),
+        _ => false,
+    }
+}
+
+/// Condition under which `pattern` matches `scrutinee`, for literal and range patterns.
+fn pattern_condition(scrutinee: &str, pattern: &Pat) -> Option<String> {
+    match pattern {
+        Pat::Lit(lit) => Some(format!("{} == {}", scrutinee, expr_text(&Expr::Lit(lit.clone())))),
+        Pat::Range(range) => {
+            let mut bounds = Vec::new();
+            if let Some(start) = &range.start {
+                bounds.push(format!("{} >= {}", scrutinee, expr_text(start)));
+            }
+            if let Some(end) = &range.end {
+                let op = match range.limits {
+                    syn::RangeLimits::Closed(_) => "<=",
+                    syn::RangeLimits::HalfOpen(_) => "<",
+                };
+                bounds.push(format!("{} {} {}", scrutinee, op, expr_text(end)));
+            }
+            (!bounds.is_empty()).then(|| bounds.join(" && "))
+        }
+        Pat::Or(or) => {
+            let cases = or.cases.iter()
+                .map(|case| pattern_condition(scrutinee, case).map(|c| format!("({})", c)))
+                .collect::<Option<Vec<_>>>()?;
+            Some(cases.join(" || "))
+        }
+        Pat::Paren(paren) => pattern_condition(scrutinee, &paren.pat),
+        Pat::Ident(ident) => ident.subpat.as_ref().and_then(|(_, sub)| pattern_condition(scrutinee, sub)),
+        _ => None,
+    }
+}
+
+/// Condition that holds after `stmt` has completed: the negation of an `if` that always
+/// leaves the block, or the condition of an `assert!`, `assert_eq!` or `assert_ne!`.
+fn condition_after(stmt: &Stmt) -> Option<String> {
+    match stmt {
+        Stmt::Expr(Expr::If(expr_if), _) if expr_if.else_branch.is_none() && diverges(&expr_if.then_branch) => {
+            condition_text(&expr_if.cond).map(|cond| negate(&cond))
+        }
+        Stmt::Macro(stmt_macro) => assertion_condition(&stmt_macro.mac),
+        Stmt::Expr(Expr::Macro(expr_macro), _) => assertion_condition(&expr_macro.mac),
+        _ => None,
+    }
+}
+
+fn diverges(block: &Block) -> bool {
+    match block.stmts.last() {
+        Some(Stmt::Expr(Expr::Return(_) | Expr::Break(_) | Expr::Continue(_), _)) => true,
+        Some(Stmt::Expr(Expr::Macro(expr_macro), _)) => is_panic_macro(&expr_macro.mac),
+        Some(Stmt::Macro(stmt_macro)) => is_panic_macro(&stmt_macro.mac),
+        _ => false,
+    }
+}
+
+fn is_panic_macro(mac: &syn::Macro) -> bool {
+    ["panic", "unreachable", "unimplemented", "todo"].iter().any(|name| mac.path.is_ident(name))
+}
+
+fn assertion_condition(mac: &syn::Macro) -> Option<String> {
+    let name = mac.path.get_ident()?.to_string();
+    let args = mac.parse_body_with(syn::punctuated::Punctuated::<Expr, syn::Token![,]>::parse_terminated).ok()?;
+    let mut args = args.iter();
+    match name.as_str() {
+        "assert" | "debug_assert" => condition_text(args.next()?),
+        "assert_eq" | "debug_assert_eq" => Some(format!("{} == {}", expr_text(args.next()?), expr_text(args.next()?))),
+        "assert_ne" | "debug_assert_ne" => Some(format!("{} != {}", expr_text(args.next()?), expr_text(args.next()?))),
+        _ => None,
+    }
+}
+
+fn extract_offset(arg: &Expr) -> Option<usize> {
+    if let syn::Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) = arg {
+        return lit.base10_parse().ok();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{check_source, PointerWidth, Verdict};
+    use std::collections::BTreeMap;
+
+    fn candidates(code: &str) -> Vec<OverflowCandidate> {
+        let ast = syn::parse_file(code).unwrap();
+        find_buffer_overflows(&ast, Vec::new())
+    }
+
+    #[test]
+    fn test_vec_repeat_literal_size() {
+        let found = candidates(r#"
+            fn main() {
+                let mut buffer = vec![0u8; 10];
+                let ptr = buffer.as_mut_ptr();
+                unsafe { *ptr.add(15) = 42; }
+            }
+        "#);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].buffer_size, Some(10));
+        assert_eq!(found[0].offset, Some(15));
+    }
+
+    #[test]
+    fn test_vec_repeat_const_size() {
+        let found = candidates(r#"
+            fn main() {
+                let mut buffer = vec![0u8; BUF_LEN];
+                let ptr = buffer.as_mut_ptr();
+                unsafe { *ptr.add(1) = 42; }
+            }
+            const BUF_LEN: usize = PAGE * 4;
+            const PAGE: usize = 4 * 1024;
+        "#);
+        assert_eq!(found[0].buffer_size, Some(16384));
+    }
+
+    #[test]
+    fn test_vec_repeat_symbolic_size() {
+        let found = candidates(r#"
+            fn fill(n: usize) {
+                let mut buffer = vec![0u8; n + 1];
+                let ptr = buffer.as_mut_ptr();
+                unsafe { *ptr.add(3) = 42; }
+            }
+        "#);
+        assert_eq!(found[0].buffer_size, None);
+        assert_eq!(found[0].buffer_size_expr.as_deref(), Some("n + 1"));
+    }
+
+    #[test]
+    fn test_vec_list_size() {
+        let found = candidates(r#"
+            fn main() {
+                let mut v = vec![1, 2, 3];
+                let p = v.as_mut_ptr();
+                unsafe { *p.add(10) = 42; }
+            }
+        "#);
+        assert_eq!(found[0].buffer_size, Some(3));
+    }
+
+    #[test]
+    fn test_candidate_span() {
+        let code = "fn main() {\n    let mut buffer = vec![0u8; 10];\n    let ptr = buffer.as_mut_ptr();\n    unsafe { *ptr.add(15) = 42; }\n}\n";
+        let found = candidates(code);
+        let span = &found[0].span;
+        assert_eq!((found[0].line, found[0].column), (4, 14));
+        assert_eq!((span.start_line, span.start_column), (4, 14));
+        assert_eq!((span.end_line, span.end_column), (4, 25));
+        assert_eq!(span.snippet(code), Some("ptr.add(15)"));
+        assert_eq!(span.location(), "Line 4, Column 15");
+    }
+
+    #[test]
+    fn test_path_conditions() {
+        let found = candidates(r#"
+            fn write(buffer: &mut Vec<u8>, offset: usize) {
+                let ptr = buffer.as_mut_ptr();
+                if offset == 0 {
+                    return;
+                }
+                unsafe {
+                    if offset < buffer.len() {
+                        *ptr.add(offset - 1) = 1;
+                    } else {
+                        *ptr.add(offset) = 2;
+                    }
+                }
+            }
+        "#);
+        assert_eq!(found[0].offset_expr.as_deref(), Some("offset - 1"));
+        assert_eq!(found[0].path_conditions, vec!["!(offset == 0)", "offset < buffer.len()"]);
+        assert_eq!(found[1].path_conditions, vec!["!(offset == 0)", "!(offset < buffer.len())"]);
+    }
+
+    #[test]
+    fn test_pointer_casts_track_element_type() {
+        let found = candidates(r#"
+            fn main() {
+                let mut buf = vec![0u8; 10];
+                let words = buf.as_mut_ptr() as *mut u32;
+                let bytes: Vec<u32> = vec![0; 4];
+                unsafe {
+                    *(buf.as_mut_ptr() as *mut u32).add(3) = 1;
+                    *words.cast::<u16>().add(4) = 1;
+                    let _ = *(bytes.as_ptr() as *const u32).add(1);
+                }
+            }
+        "#);
+        let types: Vec<_> = found.iter()
+            .map(|c| (c.buffer_element_type.as_deref(), c.element_type.as_deref()))
+            .collect();
+        assert_eq!(types, vec![(Some("u8"), Some("u32")), (Some("u8"), Some("u16")), (Some("u32"), None)]);
+        assert_eq!(found[0].buffer_name, "buf");
+        assert_eq!(found[0].buffer_size, Some(10));
+    }
+
+    #[test]
+    fn test_pointer_methods() {
+        let found = candidates(r#"
+            fn main() {
+                let mut buf = vec![0u8; 8];
+                let p = buf.as_mut_ptr();
+                unsafe {
+                    *p.sub(2) = 1;
+                    *p.byte_offset(-3) = 1;
+                    *std::ptr::mut_ptr::add(p, 1) = 1;
+                    p.is_null();
+                }
+            }
+        "#);
+        let methods: Vec<_> = found.iter()
+            .map(|c| (c.pointer_method.as_deref(), c.offset, c.offset_expr.as_deref()))
+            .collect();
+        assert_eq!(methods, vec![(Some("sub"), Some(2), None), (Some("byte_offset"), None, Some("-3")), (None, Some(1), None)]);
+    }
+
+    #[test]
+    fn test_unchecked_accesses() {
+        let found = candidates(r#"
+            fn read(data: &[u8], a: usize, b: usize) {
+                let buf = vec![0u16; 8];
+                unsafe {
+                    let _ = buf.get_unchecked(3);
+                    let _ = data.get_unchecked_mut(a..=b);
+                    let _ = std::slice::from_raw_parts(buf.as_ptr(), 9);
+                }
+            }
+        "#);
+        let accesses: Vec<_> = found.iter()
+            .map(|c| (c.operation.as_str(), c.buffer_name.as_str(), c.offset, c.offset_expr.as_deref(), c.count_expr.as_deref()))
+            .collect();
+        assert_eq!(accesses, vec![
+            ("unchecked_index", "buf", Some(3), None, None),
+            ("unchecked_range", "data", None, Some("a"), Some("b + 1 - a")),
+            ("from_raw_parts", "buf", Some(0), None, Some("9")),
+        ]);
+        assert_eq!(found[0].buffer_size, Some(8));
+        assert_eq!(found[1].buffer_size, None);
+    }
+
+    #[test]
+    fn test_vec_length_and_capacity() {
+        let found = candidates(r#"
+            fn fill(n: usize) {
+                let mut v: Vec<u8> = Vec::with_capacity(10);
+                let p = v.as_mut_ptr();
+                unsafe {
+                    *p.add(9) = 1;
+                    let _ = *p.add(2);
+                    v.set_len(4);
+                    let _ = *p.add(5);
+                    v.set_len(12);
+                }
+                let mut w = vec![0u8; 4];
+                w.push(1);
+                w.extend_from_slice(&[1, 2, 3]);
+                w.truncate(n);
+                let q = w.as_ptr();
+                unsafe { let _ = *q.add(7); }
+            }
+        "#);
+        let accesses: Vec<_> = found.iter()
+            .map(|c| (c.operation.as_str(), c.offset, c.buffer_size, c.buffer_size_expr.as_deref(), c.count_expr.as_deref()))
+            .collect();
+        assert_eq!(accesses, vec![
+            ("pointer_offset", Some(9), Some(10), None, None),
+            ("uninitialized_read", Some(2), Some(0), None, None),
+            ("pointer_offset", Some(2), Some(10), None, None),
+            ("set_len", Some(0), Some(10), None, Some("4")),
+            ("uninitialized_read", Some(5), Some(4), None, None),
+            ("pointer_offset", Some(5), Some(10), None, None),
+            ("set_len", Some(0), Some(10), None, Some("12")),
+            // `truncate(n)` leaves the length unknown.
+            ("uninitialized_read", Some(7), None, None, None),
+            ("pointer_offset", Some(7), None, Some("w.capacity()"), None),
+        ]);
+        assert_eq!(found[0].buffer_element_type.as_deref(), Some("u8"));
+        assert!(found[8].assumptions.contains(&"w.capacity() >= 8".to_string()));
+    }
+
+    #[test]
+    fn test_vec_methods_in_loops() {
+        let found = check_source(r#"
+            fn grow(k: usize) {
+                let mut v = Vec::with_capacity(2);
+                for i in 0..10 {
+                    v.push(i);
+                }
+                unsafe { let _ = *v.as_ptr().add(5); }
+                let mut w = vec![0u8; 8];
+                for _ in 0..k {
+                    w.pop();
+                }
+                unsafe { let _ = *w.as_ptr().add(7); }
+            }
+        "#, PointerWidth::default());
+        let accesses: Vec<_> = found.iter()
+            .map(|(c, constraint)| (c.operation.as_str(), c.buffer_size, c.buffer_size_expr.as_deref(), &constraint.verdict))
+            .collect();
+        // Neither loop runs a known number of times, so both lengths are unknown, and the
+        // pushes may have moved `v` to an allocation of any size.
+        assert_eq!(accesses, vec![
+            ("uninitialized_read", None, None, &Verdict::Unknown { reason: "size of `v` is unknown".to_string() }),
+            ("pointer_offset", None, None, &Verdict::Unknown { reason: "size of `v` is unknown".to_string() }),
+            ("uninitialized_read", None, None, &Verdict::Unknown { reason: "size of `w` is unknown".to_string() }),
+            ("pointer_offset", Some(8), None, &Verdict::ProvenSafe),
+        ]);
+    }
+
+    #[test]
+    fn test_uninitialized_reads() {
+        let found = candidates(r#"
+            fn spare(src: &[u8]) {
+                let mut v: Vec<u8> = Vec::with_capacity(10);
+                let p = v.as_mut_ptr();
+                unsafe {
+                    std::ptr::copy_nonoverlapping(src.as_ptr(), p.add(1), 2);
+                    std::ptr::write_bytes(p.add(3), 0, 2);
+                    let q = p.add(5);
+                    let r = &mut *p.add(6);
+                    let _ = p.add(7).read();
+                    std::ptr::copy(p.add(8), p, 1);
+                }
+            }
+        "#);
+        // Only the last two read the spare capacity.
+        let reads: Vec<_> = found.iter()
+            .filter(|c| c.operation == "uninitialized_read")
+            .map(|c| (c.line, c.offset))
+            .collect();
+        assert_eq!(reads, vec![(10, Some(7)), (11, Some(8))]);
+    }
+
+    #[test]
+    fn test_wrapping_offsets() {
+        let found = candidates(r#"
+            fn wrap(buf: &mut [u8; 4]) {
+                let p = buf.as_mut_ptr();
+                let end = p.wrapping_add(10);
+                let back = end.wrapping_sub(10);
+                unsafe {
+                    *p.wrapping_add(5) = 1;
+                    let _ = p.wrapping_byte_add(6).read();
+                    std::ptr::write_bytes(p.wrapping_add(7), 0, 1);
+                }
+            }
+        "#);
+        // Pointers moved out and back are fine as long as nothing is accessed out there.
+        let offsets: Vec<_> = found.iter().map(|c| (c.operation.as_str(), c.line, c.offset)).collect();
+        assert_eq!(offsets, vec![
+            ("pointer_offset", 7, Some(5)),
+            ("pointer_offset", 8, Some(6)),
+            ("write_bytes", 9, Some(7)),
+            ("pointer_offset", 9, Some(7)),
+        ]);
+    }
+
+    #[test]
+    fn test_dangling_pointers() {
+        let found = candidates(r#"
+            fn dangle(flag: bool) {
+                let mut v = vec![0u8; 4];
+                let p = v.as_mut_ptr();
+                v.push(1);
+                unsafe { *p = 1; }
+                let q = v.as_mut_ptr();
+                unsafe { *q.add(1) = 2; }
+                drop(v);
+                unsafe { let _ = q.read(); }
+                let r;
+                {
+                    let w = Vec::<u8>::with_capacity(8);
+                    r = w.as_ptr();
+                    let s = w.as_ptr();
+                    unsafe { let _ = *s; }
+                }
+                unsafe { let _ = *r; }
+                let mut x = Vec::<u8>::with_capacity(2);
+                let t = x.as_mut_ptr();
+                x.push(1);
+                if flag {
+                    x.reserve(100);
+                }
+                unsafe { std::ptr::write_bytes(t, 0, 2); }
+                let mut y = vec![0u8; 4];
+                let u = y.as_ptr();
+                for i in 0..100 {
+                    y.push(i);
+                }
+                unsafe { let _ = *u; }
+                let z = { let b = vec![0u8; 4]; b.as_ptr() };
+                unsafe { let _ = *z.add(0); }
+            }
+        "#);
+        let dangling: Vec<_> = found.iter()
+            .filter(|c| c.operation == "use_after_free")
+            .map(|c| {
+                let invalidation = c.invalidation.as_ref().unwrap();
+                (c.pointer_name.as_deref().unwrap(), invalidation.operation.as_str(), invalidation.line, invalidation.certain)
+            })
+            .collect();
+        assert_eq!(dangling, vec![
+            ("p", "push", 5, true),
+            ("q", "drop", 9, true),
+            ("r", "end of scope", 17, true),
+            ("t", "reserve", 23, false),
+            // Any of the pushes may outgrow the capacity, and `b` is dropped with its block.
+            ("u", "push", 29, false),
+            ("z", "end of scope", 32, true),
+        ]);
+        // `q` was taken after the push, and `s` is used before `w` goes out of scope.
+        assert!(found.iter().any(|c| c.operation == "pointer_offset" && c.pointer_name.as_deref() == Some("q")));
+        assert!(!found.iter().any(|c| c.operation == "use_after_free" && c.pointer_name.as_deref() == Some("s")));
+    }
+
+    #[test]
+    fn test_function_summaries() {
+        let found = candidates(r#"
+            fn main() {
+                let mut small = vec![0u8; 5];
+                let large = vec![0u32; 20];
+                forward(&mut small, 2);
+                write_at(large.as_ptr() as *mut u32, 3);
+            }
+
+            fn forward(buffer: &mut Vec<u8>, n: usize) {
+                write_at(buffer.as_mut_ptr(), n + 1);
+            }
+
+            fn write_at(p: *mut u8, i: usize) {
+                for j in 0..i {
+                    unsafe { *p.add(j) = 0; }
+                }
+            }
+        "#);
+        let calls: Vec<_> = found.iter()
+            .filter_map(|c| Some((c.location.as_str(), c.span.start_line, c.buffer_name.as_str(), c.callee.as_deref()?, c.callee_span.as_ref()?.start_line)))
+            .collect();
+        assert_eq!(calls, vec![("main", 5, "small", "write_at", 15), ("main", 6, "large", "write_at", 15)]);
+
+        // The loop bound is rewritten into `main`'s arguments, and the loop variable is
+        // prefixed with the functions it was passed through.
+        let through_forward = &found[0];
+        assert_eq!(through_forward.buffer_size, Some(5));
+        assert_eq!(through_forward.offset_expr.as_deref(), Some("forward_write_at_j"));
+        assert_eq!(through_forward.callee_locals, vec!["forward_write_at_j"]);
+        assert_eq!(through_forward.path_conditions, vec!["forward_write_at_j >= 0 && forward_write_at_j < (2 + 1)"]);
+        assert_eq!(through_forward.element_type, None);
+        // `write_at` sees the `u32` elements of `large` as bytes.
+        assert_eq!(found[1].element_type.as_deref(), Some("u8"));
+        // The accesses are still reported on their own, where the sizes are unknown.
+        assert_eq!(found.iter().filter(|c| c.callee_span.is_none()).count(), 1);
+    }
+
+    #[test]
+    fn test_item_paths() {
+        let found = candidates(r#"
+            fn main() {
+                let mut large = vec![0u8; 8];
+                io::helper(&mut large);
+            }
+
+            mod io {
+                pub fn helper(buf: &mut Vec<u8>) {
+                    let p = buf.as_mut_ptr();
+                    unsafe { *p.add(6) = 0; }
+                }
+
+                pub fn local() {
+                    let mut small = vec![0u8; 4];
+                    helper(&mut small);
+                }
+            }
+        "#);
+        let accesses: Vec<_> = found.iter()
+            .map(|c| (c.location.as_str(), c.callee.as_deref(), c.buffer_name.as_str(), c.buffer_size))
+            .collect();
+        assert_eq!(accesses, vec![
+            ("main", Some("io::helper"), "large", Some(8)),
+            ("io::helper", None, "buf", None),
+            ("io::local", Some("io::helper"), "small", Some(4)),
+        ]);
+    }
+
+    #[test]
+    fn test_scoped_bindings() {
+        let found = candidates(r#"
+            fn first() {
+                let buf = vec![0u8; 4];
+                let p = buf.as_ptr();
+                {
+                    let buf = vec![0u8; 16];
+                    let p = buf.as_ptr();
+                    unsafe { let _ = *p.add(10); }
+                }
+                unsafe { let _ = *p.add(3); }
+                let p = 0usize;
+                let _ = p.wrapping_add(1);
+            }
+
+            fn second(buf: &[u8]) {
+                let p = buf.as_ptr();
+                unsafe { let _ = *p.add(10); }
+            }
+        "#);
+        let accesses: Vec<_> = found.iter()
+            .map(|c| (c.location.as_str(), c.line, c.buffer_size, c.offset))
+            .collect();
+        assert_eq!(accesses, vec![
+            ("first", 8, Some(16), Some(10)),
+            ("first", 10, Some(4), Some(3)),
+            ("second", 17, None, Some(10)),
+        ]);
+        // The inner `buf` going out of scope leaves the outer one and its pointer alone.
+        assert!(!found.iter().any(|c| c.operation == "use_after_free"));
+    }
+
+    #[test]
+    fn test_derived_pointers() {
+        let found = candidates(r#"
+            fn derived(i: usize) {
+                let mut data = vec![0u8; 8];
+                let p = data.as_mut_ptr();
+                let q = unsafe { p.add(2) };
+                let r = unsafe { q.add(3) };
+                unsafe { *r.add(4) = 1; }
+                unsafe { let _ = *r; }
+                let s = unsafe { r.sub(5).add(i) };
+                unsafe { std::ptr::copy_nonoverlapping(q, p, 4); }
+                unsafe { std::ptr::write_bytes(s, 0, 2); }
+                let mut t = p;
+                if i > 0 {
+                    t = q;
+                }
+                unsafe { let _ = *t; }
+            }
+        "#);
+        let accesses: Vec<_> = found.iter()
+            .map(|c| (c.operation.as_str(), c.line, c.offset, c.offset_expr.as_deref()))
+            .collect();
+        assert_eq!(accesses, vec![
+            ("pointer_offset", 5, Some(2), None),
+            ("pointer_offset", 6, Some(5), None),
+            ("pointer_offset", 7, Some(9), None),
+            ("pointer_offset", 8, Some(5), None),
+            ("pointer_offset", 9, None, Some("i")),
+            ("pointer_offset", 9, Some(0), None),
+            ("copy_src", 10, Some(2), None),
+            ("copy_dst", 10, Some(0), None),
+            ("copy_overlap", 10, Some(2), None),
+            ("write_bytes", 11, None, Some("i")),
+        ]);
+        // Each side of the copy knows where the other one starts.
+        let origins: Vec<_> = found.iter()
+            .filter(|c| c.operation.starts_with("copy_") && c.operation != "copy_overlap")
+            .map(|c| c.origin_offset.as_deref())
+            .collect();
+        assert_eq!(origins, vec![Some("0"), Some("2")]);
+        // `t` may point to either `p` or `q` after the branch, so `*t` is not checked.
+        assert!(found.iter().all(|c| c.buffer_name == "data" && c.buffer_size == Some(8)));
+    }
+
+    #[test]
+    fn test_derived_pointers_moved_back() {
+        let found = check_source(r#"
+            fn back(n: usize) {
+                let mut data = vec![0u8; 8];
+                let p = data.as_mut_ptr();
+                let q = unsafe { p.add(3) };
+                unsafe { *q.sub(5) = 1; }
+                unsafe { *q.offset(-n) = 1; }
+                unsafe { *q.offset(-2) = 1; }
+            }
+        "#, PointerWidth::default());
+        let offsets: Vec<_> = found.iter()
+            .skip(1)
+            .map(|(c, _)| (c.offset, c.offset_expr.as_deref()))
+            .collect();
+        assert_eq!(offsets, vec![(None, Some("-2")), (None, Some("3 - n")), (Some(1), None)]);
+        // `q.sub(5)` points two elements before `data`, and `q.offset(-n)` does for `n > 3`.
+        assert_eq!(found[1].1.verdict, Verdict::DefiniteOverflow);
+        assert!(matches!(&found[2].1.verdict, Verdict::PossibleOverflow { witness } if witness["n"] > 3));
+        assert_eq!(found[3].1.verdict, Verdict::ProvenSafe);
+    }
+
+    #[test]
+    fn test_tracked_buffers() {
+        let found = candidates(r#"
+            const SIZE: usize = 64;
+
+            struct Reader {
+                buf: [u16; 32],
+                data: Vec<u8>,
+            }
+
+            impl Reader {
+                fn fill(&mut self, i: usize) {
+                    unsafe { *self.buf.as_mut_ptr().add(i) = 0; }
+                    unsafe { *self.data.as_mut_ptr().add(i) = 0; }
+                }
+            }
+
+            fn locals(input: &mut [u32]) {
+                let mut stack = [0u8; SIZE];
+                let mut typed: [u64; 4] = [0; 4];
+                let boxed = Box::new([1i32, 2, 3]);
+                let shrunk = vec![0u8; 10].into_boxed_slice();
+                let mut text = String::from("hello");
+                text.push_str(" world");
+                let bytes = unsafe { text.as_mut_vec() };
+                unsafe {
+                    *stack.as_mut_ptr().add(1) = 0;
+                    *typed.as_mut_ptr().add(1) = 0;
+                    let _ = *boxed.as_ptr().add(1);
+                    let _ = *shrunk.as_ptr().add(1);
+                    let _ = *bytes.as_ptr().add(1);
+                    *input.as_mut_ptr().add(1) = 0;
+                }
+            }
+        "#);
+        let buffers: Vec<_> = found.iter()
+            .filter(|c| c.operation == "pointer_offset")
+            .map(|c| (c.buffer_name.as_str(), c.buffer_size, c.buffer_size_expr.as_deref(), c.buffer_element_type.as_deref()))
+            .collect();
+        assert_eq!(buffers, vec![
+            ("self.buf", Some(32), None, Some("u16")),
+            ("self.data", None, Some("self.data.len()"), Some("u8")),
+            ("stack", Some(64), None, Some("u8")),
+            ("typed", Some(4), None, Some("u64")),
+            ("boxed", Some(3), None, Some("i32")),
+            ("shrunk", Some(10), None, Some("u8")),
+            ("bytes", None, Some("bytes.capacity()"), Some("u8")),
+            ("input", None, Some("input.len()"), Some("u32")),
+        ]);
+        // `push_str` grew the string from its literal, so it holds at least 11 bytes.
+        let bytes = found.iter().find(|c| c.operation == "pointer_offset" && c.buffer_name == "bytes").unwrap();
+        assert!(bytes.assumptions.contains(&"bytes.capacity() >= 11".to_string()));
+    }
+
+    #[test]
+    fn test_heap_allocations() {
+        let found = candidates(r#"
+            use std::alloc::{alloc, alloc_zeroed, dealloc, realloc, Layout};
+
+            fn heap(n: usize) {
+                unsafe {
+                    let layout = Layout::array::<u32>(n).unwrap();
+                    let p = alloc(layout) as *mut u32;
+                    *p.add(n) = 1;
+                    let q = p.add(1);
+                    let bytes = alloc_zeroed(Layout::from_size_align(16, 8).unwrap());
+                    *bytes.add(16) = 0;
+                    let grown = realloc(bytes, Layout::from_size_align(16, 8).unwrap(), 32);
+                    *grown.add(20) = 0;
+                    let _ = *bytes;
+                    dealloc(p as *mut u8, Layout::array::<u32>(n + 1).unwrap());
+                    let _ = *q;
+                    let _ = *p;
+                }
+            }
+        "#);
+        let accesses: Vec<_> = found.iter()
+            .map(|c| (c.operation.as_str(), c.line, c.buffer_name.as_str(), c.buffer_size, c.buffer_size_expr.as_deref()))
+            .collect();
+        assert_eq!(accesses, vec![
+            ("pointer_offset", 8, "p", None, Some("n")),
+            ("pointer_offset", 9, "p", None, Some("n")),
+            ("pointer_offset", 11, "bytes", Some(16), None),
+            ("dealloc_layout", 12, "bytes", Some(16), None),
+            ("pointer_offset", 13, "grown", Some(32), None),
+            ("use_after_free", 14, "bytes", Some(16), None),
+            ("dealloc_layout", 15, "p", None, Some("n")),
+            ("use_after_free", 16, "p", None, Some("n")),
+            ("use_after_free", 17, "p", None, Some("n")),
+        ]);
+        // The `u32` elements of `p` are counted as such, and `realloc` may keep `bytes` in place.
+        assert_eq!(found[0].element_type, None);
+        assert_eq!(found[6].count_expr.as_deref(), Some("n + 1"));
+        assert!(!found[5].invalidation.as_ref().unwrap().certain);
+        assert!(found[7].invalidation.as_ref().unwrap().certain);
+    }
+
+    #[test]
+    fn test_raw_ownership() {
+        let found = candidates(r#"
+            fn raw(flag: bool, n: usize) {
+                let p = Box::into_raw(Box::new(5u32));
+                let b = unsafe { Box::from_raw(p) };
+                let c = unsafe { Box::from_raw(p) };
+                let q = Box::into_raw(Box::new(1u8));
+                if flag {
+                    drop(unsafe { Box::from_raw(q) });
+                } else {
+                    drop(unsafe { Box::from_raw(q) });
+                }
+                let mut v = vec![1u32, 2, 3];
+                let r = v.as_mut_ptr();
+                std::mem::forget(v);
+                let rebuilt = unsafe { Vec::from_raw_parts(r, 3, 3) };
+                let mut me = std::mem::ManuallyDrop::new(vec![0u8; n]);
+                let s = me.as_mut_ptr();
+                let longer = unsafe { Vec::from_raw_parts(s, n + 1, n) };
+                let mut owned = vec![0u8; 4];
+                let t = owned.as_mut_ptr();
+                let twice = unsafe { Vec::from_raw_parts(t, 4, 4) };
+            }
+        "#);
+        let checks: Vec<_> = found.iter()
+            .map(|c| (c.operation.as_str(), c.line, c.count_expr.as_deref(), c.origin_buffer.as_deref()))
+            .collect();
+        assert_eq!(checks, vec![
+            ("double_reclaim", 5, None, Some("b")),
+            ("reclaim_len", 15, Some("3"), None),
+            ("reclaim_cap", 15, Some("3"), None),
+            ("reclaim_len", 18, Some("n + 1"), None),
+            ("reclaim_cap", 18, Some("n"), None),
+            ("double_reclaim", 21, None, Some("owned")),
+            ("reclaim_len", 21, Some("4"), None),
+            ("reclaim_cap", 21, Some("4"), None),
+        ]);
+        // `v` gave up its allocation, while `owned` still frees its own.
+        assert_eq!(found[0].invalidation.as_ref().map(|invalidation| invalidation.operation.as_str()), Some("Box::from_raw"));
+        assert_eq!(found[5].invalidation.as_ref().map(|invalidation| invalidation.operation.as_str()), Some("allocation"));
+        assert_eq!(found[3].buffer_size_expr.as_deref(), Some("n"));
+        assert_eq!(found[4].buffer_size_expr.as_deref(), Some("n"));
+    }
+
+    #[test]
+    fn test_offset_ranges() {
+        let found = candidates(r#"
+            fn fill(n: usize) {
+                let mut buf = vec![0u8; 8];
+                let p = buf.as_mut_ptr();
+                for i in 0..=8 {
+                    unsafe { *p.add(i) = 0; }
+                }
+                for &j in &[2, 5] {
+                    unsafe { *p.add(j + 1) = 0; }
+                }
+                unsafe { *p.add(n) = 0; }
+            }
+        "#);
+        let ranges: Vec<_> = found.iter().map(|c| c.offset_range).collect();
+        assert_eq!(ranges, vec![
+            Some(Interval::new(Some(0), Some(8))),
+            Some(Interval::new(Some(3), Some(6))),
+            Some(Interval::non_negative()),
+        ]);
+        assert!(found[0].assumptions.contains(&"i <= 8".to_string()));
+        assert!(found[1].assumptions.contains(&"j >= 2".to_string()));
+    }
+
+    #[test]
+    fn test_field_and_array_buffers() {
+        let found: Vec<Verdict> = check_source(r#"
+            struct Ring {
+                slots: [u32; 8],
+                data: Vec<u8>,
+            }
+
+            impl Ring {
+                fn put(&mut self, i: usize, value: u32) {
+                    let mut scratch = [0u8; 4];
+                    unsafe {
+                        *self.slots.as_mut_ptr().add(8) = value;
+                        if i < self.data.len() {
+                            *self.data.as_mut_ptr().add(i) = 0;
+                        }
+                        *scratch.as_mut_ptr().add(i % 4) = 0;
+                    }
+                }
+            }
+        "#, PointerWidth::default())
+            .into_iter()
+            .map(|(_, constraint)| constraint.verdict)
+            .collect();
+        assert_eq!(found[0], Verdict::DefiniteOverflow);
+        assert_eq!(found[1], Verdict::ProvenSafe);
+        assert_eq!(found[2], Verdict::ProvenSafe);
+    }
+
+    #[test]
+    fn test_dealloc_layout() {
+        let found: Vec<Verdict> = check_source(r#"
+            use std::alloc::{alloc, dealloc, realloc, Layout};
+
+            fn free(n: usize) {
+                unsafe {
+                    let layout = Layout::array::<u32>(n).unwrap();
+                    let p = alloc(layout) as *mut u32;
+                    let one = alloc(Layout::new::<u64>()) as *mut u64;
+                    let bytes = alloc(Layout::from_size_align(8, 8).unwrap());
+                    dealloc(p as *mut u8, Layout::array::<u32>(n + 1).unwrap());
+                    dealloc(one as *mut u8, Layout::new::<u64>());
+                    dealloc(bytes, Layout::new::<u64>());
+                    let loose = alloc(Layout::from_size_align(16, 1).unwrap());
+                    dealloc(loose, Layout::from_size_align(16, 8).unwrap());
+                    let wide = alloc(Layout::array::<u16>(4).unwrap());
+                    let moved = realloc(wide, Layout::from_size_align(8, 1).unwrap(), 16);
+                    dealloc(moved, Layout::from_size_align(16, 1).unwrap());
+                }
+            }
+        "#, PointerWidth::default())
+            .into_iter()
+            .map(|(_, constraint)| constraint.verdict)
+            .collect();
+        // The sizes match, but `loose` was allocated with an alignment of 1, `wide` with 2,
+        // and `realloc` keeps the alignment of the layout it is given.
+        assert_eq!(found, vec![
+            Verdict::DefiniteOverflow, Verdict::ProvenSafe, Verdict::ProvenSafe,
+            Verdict::DefiniteOverflow, Verdict::DefiniteOverflow, Verdict::ProvenSafe,
+        ]);
+    }
+
+    #[test]
+    fn test_unchecked_access_verdicts() {
+        let found: Vec<Verdict> = check_source(r#"
+            fn read(data: &[u8], a: usize, b: usize) {
+                let buf = vec![0u16; 8];
+                let bytes = buf.as_ptr() as *const u8;
+                unsafe {
+                    let _ = buf.get_unchecked(7);
+                    let _ = buf.get_unchecked(8);
+                    let _ = buf.get_unchecked(2..8);
+                    let _ = buf.get_unchecked(..=8);
+                    if a <= b && b < data.len() {
+                        let _ = data.get_unchecked(a..=b);
+                    }
+                    let _ = data.get_unchecked(a..b);
+                    let _ = std::slice::from_raw_parts(buf.as_ptr(), 8);
+                    let _ = std::slice::from_raw_parts(bytes, 17);
+                }
+            }
+        "#, PointerWidth::default())
+            .into_iter()
+            .map(|(_, constraint)| constraint.verdict)
+            .collect();
+        assert_eq!(found[0], Verdict::ProvenSafe);
+        assert_eq!(found[1], Verdict::DefiniteOverflow);
+        assert_eq!(found[2], Verdict::ProvenSafe);
+        assert_eq!(found[3], Verdict::DefiniteOverflow);
+        assert_eq!(found[4], Verdict::ProvenSafe);
+        assert!(matches!(&found[5], Verdict::PossibleOverflow { .. }));
+        assert_eq!(found[6], Verdict::ProvenSafe);
+        assert_eq!(found[7], Verdict::DefiniteOverflow);
+    }
+
+    #[test]
+    fn test_bulk_memory_operations() {
+        let code = r#"
+            fn copy(n: usize) {
+                let src = vec![0u8; 4];
+                let mut dst = vec![0u8; 8];
+                unsafe {
+                    std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), 4);
+                    std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), 6);
+                    std::ptr::write_bytes(dst.as_mut_ptr(), 0, 9);
+                    let p = dst.as_mut_ptr();
+                    std::ptr::copy_nonoverlapping(p, p, n);
+                    p.copy_from(src.as_ptr(), 2);
+                }
+            }
+        "#;
+        let found: Vec<_> = check_source(code, PointerWidth::default())
+            .into_iter()
+            .map(|(candidate, constraint)| (candidate.operation, constraint.verdict))
+            .collect();
+        let operations: Vec<&str> = found.iter().map(|(operation, _)| operation.as_str()).collect();
+        assert_eq!(operations, vec![
+            "copy_src", "copy_dst", "copy_src", "copy_dst", "write_bytes",
+            "copy_src", "copy_dst", "copy_overlap", "copy_src", "copy_dst",
+        ]);
+        assert_eq!(found[0].1, Verdict::ProvenSafe);
+        assert_eq!(found[1].1, Verdict::ProvenSafe);
+        assert_eq!(found[2].1, Verdict::DefiniteOverflow);
+        assert_eq!(found[3].1, Verdict::ProvenSafe);
+        assert_eq!(found[4].1, Verdict::DefiniteOverflow);
+        assert!(matches!(&found[5].1, Verdict::PossibleOverflow { witness } if witness["n"] > 8));
+        assert!(matches!(&found[7].1, Verdict::PossibleOverflow { witness } if witness["n"] > 0));
+        assert_eq!(found[8].1, Verdict::ProvenSafe);
+        assert_eq!(found[9].1, Verdict::ProvenSafe);
+    }
+
+    #[test]
+    fn test_vec_capacity() {
+        let code = r#"
+            fn fill(n: usize) {
+                let mut v = Vec::<u32>::with_capacity(8);
+                let p = v.as_mut_ptr();
+                unsafe {
+                    *p.add(7) = 1;
+                    v.set_len(8);
+                    let _ = *p.add(7);
+                    v.set_len(9);
+                }
+                v.truncate(2);
+                let q = v.as_ptr();
+                unsafe { let _ = *q.add(3); }
+                let mut w = vec![0u32; 4];
+                w.push(5);
+                let r = w.as_mut_ptr();
+                unsafe {
+                    *r.add(4) = 1;
+                    *r.add(n) = 1;
+                }
+            }
+        "#;
+        let found: Vec<_> = check_source(code, PointerWidth::default())
+            .into_iter()
+            .map(|(candidate, constraint)| (candidate.operation, constraint.verdict))
+            .collect();
+        let operations: Vec<&str> = found.iter().map(|(operation, _)| operation.as_str()).collect();
+        assert_eq!(operations, vec![
+            "pointer_offset", "set_len", "uninitialized_read", "pointer_offset", "set_len",
+            "uninitialized_read", "pointer_offset", "pointer_offset", "pointer_offset",
+        ]);
+        // Writing into spare capacity and reading it after `set_len` are fine.
+        assert_eq!(found[0].1, Verdict::ProvenSafe);
+        assert_eq!(found[1].1, Verdict::ProvenSafe);
+        assert_eq!(found[2].1, Verdict::ProvenSafe);
+        assert_eq!(found[3].1, Verdict::ProvenSafe);
+        assert_eq!(found[4].1, Verdict::DefiniteOverflow);
+        // After `truncate(2)` the element at 3 is still allocated but no longer initialized.
+        assert_eq!(found[5].1, Verdict::DefiniteOverflow);
+        assert_eq!(found[6].1, Verdict::ProvenSafe);
+        // `push` reallocated `w` to hold at least 5 elements.
+        assert_eq!(found[7].1, Verdict::ProvenSafe);
+        assert!(matches!(&found[8].1, Verdict::PossibleOverflow { witness } if witness["n"] >= 5));
+    }
+
+    #[test]
+    fn test_use_after_free() {
+        let code = r#"
+            fn dangle(n: usize) {
+                let mut v = vec![0u8; 4];
+                let p = v.as_mut_ptr();
+                v.push(1);
+                unsafe { *p.add(1) = 1; }
+                let mut w = Vec::<u8>::with_capacity(8);
+                let q = w.as_mut_ptr();
+                w.push(1);
+                w.extend_from_slice(&[1, 2]);
+                unsafe { *q.add(2) = 1; }
+                w.resize(n, 0);
+                unsafe { *q.add(0) = 1; }
+            }
+        "#;
+        let found: Vec<_> = check_source(code, PointerWidth::default())
+            .into_iter()
+            .map(|(candidate, constraint)| (candidate.operation, constraint.verdict))
+            .collect();
+        let operations: Vec<&str> = found.iter().map(|(operation, _)| operation.as_str()).collect();
+        assert_eq!(operations, vec!["use_after_free", "pointer_offset", "use_after_free"]);
+        // `vec!` allocates exactly 4 elements, so the push moves them.
+        assert_eq!(found[0].1, Verdict::DefiniteOverflow);
+        // Three elements still fit into the capacity of 8.
+        assert_eq!(found[1].1, Verdict::ProvenSafe);
+        // `resize(n, 0)` only reallocates when `n` exceeds the capacity.
+        assert_eq!(found[2].1, Verdict::PossibleOverflow { witness: BTreeMap::new() });
+    }
+
+    #[test]
+    fn test_raw_ownership_verdicts() {
+        let code = r#"
+            fn rebuild(flag: bool, n: usize) {
+                let mut v = vec![0u8; n];
+                let p = v.as_mut_ptr();
+                let len = v.len();
+                std::mem::forget(v);
+                let rebuilt = unsafe { Vec::from_raw_parts(p, len, n) };
+                let q = Box::into_raw(Box::new(1u32));
+                if flag {
+                    let b = unsafe { Box::from_raw(q) };
+                }
+                let c = unsafe { Box::from_raw(q) };
+                let mut w = Vec::<u8>::with_capacity(8);
+                let (r, len, cap) = w.into_raw_parts();
+                let grown = unsafe { Vec::from_raw_parts(r, cap + 1, cap) };
+                let mut x = vec![0u8; n];
+                let s = x.as_mut_ptr();
+                std::mem::forget(x);
+                let wider = unsafe { Vec::from_raw_parts(s, n, n + 1) };
+                let r = Box::into_raw(Box::new(2u32));
+                let first = unsafe { Box::from_raw(r) };
+                let second = unsafe { Box::from_raw(r) };
+            }
+        "#;
+        let found: Vec<_> = check_source(code, PointerWidth::default())
+            .into_iter()
+            .map(|(candidate, constraint)| (candidate.operation, constraint.verdict))
+            .collect();
+        let operations: Vec<&str> = found.iter().map(|(operation, _)| operation.as_str()).collect();
+        assert_eq!(operations, vec![
+            "reclaim_len", "reclaim_cap", "use_after_free", "reclaim_len", "reclaim_cap", "reclaim_len", "reclaim_cap",
+            "double_reclaim",
+        ]);
+        // `len` and `n` are the length and capacity `v` had when it was forgotten.
+        assert_eq!(found[0].1, Verdict::ProvenSafe);
+        assert_eq!(found[1].1, Verdict::ProvenSafe);
+        // `b` frees the box at the end of its block, but only when `flag` is set.
+        assert_eq!(found[2].1, Verdict::PossibleOverflow { witness: BTreeMap::from([("flag".to_string(), 1)]) });
+        assert_eq!(found[3].1, Verdict::DefiniteOverflow);
+        assert_eq!(found[4].1, Verdict::ProvenSafe);
+        // `x` had a capacity of `n`, not `n + 1`.
+        assert_eq!(found[5].1, Verdict::ProvenSafe);
+        assert_eq!(found[6].1, Verdict::DefiniteOverflow);
+        // `first` still owns the box when `second` takes it again.
+        assert_eq!(found[7].1, Verdict::DefiniteOverflow);
+    }
+
+    #[test]
+    fn test_loop_ranges() {
+        let found: Vec<Verdict> = check_source(r#"
+            const STEPS: [usize; 3] = [1, 4, 9];
+
+            fn fill(data: &mut [u8; 16]) {
+                let len = 16;
+                let ptr = data.as_mut_ptr();
+                unsafe {
+                    for i in 0..=len {
+                        *ptr.add(i) = 1;
+                    }
+                    for i in (0..len).rev() {
+                        *ptr.add(len - 1 - i) = 2;
+                    }
+                    for &step in STEPS.iter() {
+                        *ptr.add(step * 2) = 3;
+                    }
+                    let mut k = 0;
+                    while k < len {
+                        *ptr.add(k) = 4;
+                        k += 1;
+                    }
+                    *ptr.add(k - 1) = 5;
+                }
+            }
+        "#, PointerWidth::default())
+            .into_iter()
+            .map(|(_, constraint)| constraint.verdict)
+            .collect();
+        let overflows: Vec<bool> = found.iter().map(Verdict::is_overflow).collect();
+        // `0..=len` goes one past the end, and `STEPS` holds 9, for an offset of 18.
+        // The `while` loop leaves `k` at exactly `len`.
+        assert_eq!(overflows, vec![true, false, true, false, false]);
+    }
+
+    #[test]
+    fn test_loops_over_array_values() {
+        let found: Vec<Verdict> = check_source(r#"
+            fn fill(data: &mut [u8; 10]) {
+                let ptr = data.as_mut_ptr();
+                unsafe {
+                    for o in [1, 5, 12] {
+                        *ptr.add(o) = 1;
+                    }
+                    for o in [1, 5, 8] {
+                        *ptr.add(o) = 2;
+                    }
+                    let offsets = [0, 2, 4, 6, 8, 10, 12];
+                    for &o in &offsets {
+                        if o > 8 {
+                            *ptr.add(o - 1) = 3;
+                        }
+                    }
+                }
+            }
+        "#, PointerWidth::default())
+            .into_iter()
+            .map(|(_, constraint)| constraint.verdict)
+            .collect();
+        // The witnesses are values the loops take, not just any within their bounds.
+        let witnesses: Vec<Option<i128>> = found.iter()
+            .map(|verdict| match verdict {
+                Verdict::PossibleOverflow { witness } => witness.get("o").copied(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(witnesses, vec![Some(12), None, Some(12)]);
+        assert_eq!(found[1], Verdict::ProvenSafe);
+    }
+
+    #[test]
+    fn test_ranges_of_escaped_variables() {
+        let found: Vec<Verdict> = check_source(r#"
+            fn fill(data: &mut [u8; 16]) {
+                let ptr = data.as_mut_ptr();
+                let mut i = 0usize;
+                let r = &mut i as *mut usize;
+                let mut k = 0usize;
+                let q = &raw mut k;
+                let mut n = 0usize;
+                i = 2;
+                k = 3;
+                n = 4;
+                unsafe {
+                    *r = 100;
+                    *q = 100;
+                    *ptr.add(i) = 1;
+                    *ptr.add(k) = 2;
+                    *ptr.add(n) = 3;
+                }
+            }
+        "#, PointerWidth::default())
+            .into_iter()
+            .map(|(_, constraint)| constraint.verdict)
+            .collect();
+        // Writes through `r` and `q` change `i` and `k` after their last assignment.
+        let safe: Vec<bool> = found.iter().map(|verdict| *verdict == Verdict::ProvenSafe).collect();
+        assert_eq!(safe, vec![false, false, true]);
+    }
+}
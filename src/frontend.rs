@@ -1,11 +1,13 @@
 use std::path::PathBuf;
 use anyhow::Result;
-use syn::{self, parse_file,spanned::Spanned};
+use syn::{self, parse_file, spanned::Spanned, visit::{self, Visit}};
 use std::fs;
 use quote::ToTokens;
-use proc_macro2::{Span, LineColumn};
+use proc_macro2::Span;
 
-use crate::analyzer::OverflowCandidate;
+use std::collections::HashMap;
+
+use crate::analyzer::{OverflowCandidate, BufferLen, SourceSpan, extract_vec_size, find_buffer_overflows, self_type_name};
 
 #[derive(Debug)]
 pub struct AnalysisResult {
@@ -51,6 +53,7 @@ impl Frontend {
         result.unsafe_blocks = visitor.unsafe_blocks;
         result.allocations = visitor.allocations;
         result.overflow_candidates = visitor.overflow_candidates;
+        result.overflow_candidates.extend(find_buffer_overflows(&ast, Vec::new()));
         Ok(())
     }
 }
@@ -59,6 +62,9 @@ struct AstVisitor {
     unsafe_blocks: Vec<String>,
     allocations: Vec<String>,
     overflow_candidates: Vec<OverflowCandidate>,
+    current_binding: Option<String>,
+    /// Modules, types, traits and functions enclosing the code being visited.
+    item_path: Vec<String>,
 }
 
 impl AstVisitor {
@@ -67,96 +73,111 @@ impl AstVisitor {
             unsafe_blocks: Vec::new(),
             allocations: Vec::new(),
             overflow_candidates: Vec::new(),
+            current_binding: None,
+            item_path: Vec::new(),
         }
     }
 
-    fn visit_file(&mut self, file: &syn::File) {
-        for item in &file.items {
-            self.visit_item(item);
-        }
+    fn with_item(&mut self, name: String, visit: impl FnOnce(&mut Self)) {
+        self.item_path.push(name);
+        visit(self);
+        self.item_path.pop();
     }
 
-    fn visit_item(&mut self, item: &syn::Item) {
-        match item {
-            syn::Item::Fn(func) => {
-                for stmt in &func.block.stmts {
-                    self.visit_stmt(stmt);
-                }
+    /// Visits a function or method, whose body counts as an unsafe block for an `unsafe fn`.
+    fn visit_function(&mut self, sig: &syn::Signature, block: Option<&syn::Block>, visit: impl FnOnce(&mut Self)) {
+        self.with_item(sig.ident.to_string(), |v| {
+            if sig.unsafety.is_some() && let Some(block) = block {
+                let start = block.span().start();
+                println!("Unsafe fn {} at line {}, column {}", v.item_path.join("::"), start.line, start.column); // 调试
+                v.unsafe_blocks.push(block.to_token_stream().to_string());
             }
-            _ => {}
-        }
+            visit(v);
+        });
     }
 
-    fn visit_stmt(&mut self, stmt: &syn::Stmt) {
-        match stmt {
-            syn::Stmt::Item(item) => self.visit_item(item),
-            syn::Stmt::Expr(expr, ..) => self.visit_expr(expr),
-            syn::Stmt::Local(syn::Local { init: Some(init), .. }) => {
-                self.visit_expr(&init.expr);
-            }
-            syn::Stmt::Local(_) => {},
-            stmt @ syn::Stmt::Macro(..) => {
-                if let syn::Stmt::Macro(macro_stmt) = stmt {
-                    if let Some(ident) = macro_stmt.mac.path.get_ident() {
-                        if ident == "vec" {
-                            let span = macro_stmt.span();
-                            let start = span.start();
-                            println!("Vec macro at line {}, column {}", start.line, start.column); // 调试
-                            self.allocations.push(macro_stmt.to_token_stream().to_string());
-                        }
-                    }
-                }
-            }
-        }
+    fn allocation(&mut self, mac: &syn::Macro, span: Span) {
+        let start = span.start();
+        println!("Vec macro at line {}, column {}", start.line, start.column); // 调试
+        self.allocations.push(mac.to_token_stream().to_string());
+        let (buffer_size, buffer_size_expr) = match extract_vec_size(mac, &HashMap::new()) {
+            Some(BufferLen::Known(len)) => (Some(len), None),
+            Some(BufferLen::Symbolic(len)) => (None, Some(len)),
+            None => (None, None),
+        };
+        self.overflow_candidates.push(OverflowCandidate {
+            location: self.item_path.join("::"),
+            buffer_name: self.current_binding.clone().unwrap_or_else(|| "vec".to_string()),
+            operation: "allocation".to_string(),
+            line: start.line,
+            column: start.column,
+            buffer_size,
+            buffer_size_expr,
+            offset: None,
+            span: SourceSpan::from_span(span),
+            ..Default::default()
+        });
     }
+}
 
-    fn visit_expr(&mut self, expr: &syn::Expr) {
-        match expr {
-            syn::Expr::Unsafe(expr) => {
-                let span = expr.span();
-                let start = span.start();
-                println!("Unsafe block at line {}, column {}", start.line, start.column); // 调试
-                self.unsafe_blocks.push(expr.to_token_stream().to_string());
-                for stmt in &expr.block.stmts {
-                    if let syn::Stmt::Expr(syn::Expr::MethodCall(method_call), _) = stmt {
-                        if method_call.method == "add" {
-                            let span = method_call.span();
-                            let start = span.start();
-                            println!("ptr.add at line {}, column {}", start.line, start.column); // 调试
-                            self.overflow_candidates.push(OverflowCandidate {
-                                location: method_call.to_token_stream().to_string(),
-                                buffer_name: "buffer".to_string(),
-                                operation: "pointer_offset".to_string(),
-                                line: start.line,
-                                column: start.column,
-                                buffer_size: Some(10),
-                                offset: Some(15),
-                            });
-                        }
-                    }
-                }
+impl<'ast> Visit<'ast> for AstVisitor {
+    fn visit_item_mod(&mut self, module: &'ast syn::ItemMod) {
+        self.with_item(module.ident.to_string(), |v| visit::visit_item_mod(v, module));
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        self.with_item(self_type_name(&item.self_ty), |v| visit::visit_item_impl(v, item));
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast syn::ItemTrait) {
+        self.with_item(item.ident.to_string(), |v| visit::visit_item_trait(v, item));
+    }
+
+    fn visit_item_fn(&mut self, func: &'ast syn::ItemFn) {
+        self.visit_function(&func.sig, Some(&func.block), |v| visit::visit_item_fn(v, func));
+    }
+
+    fn visit_impl_item_fn(&mut self, method: &'ast syn::ImplItemFn) {
+        self.visit_function(&method.sig, Some(&method.block), |v| visit::visit_impl_item_fn(v, method));
+    }
+
+    fn visit_trait_item_fn(&mut self, method: &'ast syn::TraitItemFn) {
+        self.visit_function(&method.sig, method.default.as_ref(), |v| visit::visit_trait_item_fn(v, method));
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        let outer = self.current_binding.take();
+        self.current_binding = match &local.pat {
+            syn::Pat::Ident(pat_ident) => Some(pat_ident.ident.to_string()),
+            syn::Pat::Type(typed) => match &*typed.pat {
+                syn::Pat::Ident(pat_ident) => Some(pat_ident.ident.to_string()),
+                _ => None,
             },
-            syn::Expr::Macro(expr) => {
-                if let Some(ident) = expr.mac.path.get_ident() {
-                    if ident == "vec" {
-                        let span = expr.span();
-                        let start = span.start();
-                        println!("Vec macro at line {}, column {}", start.line, start.column); // 调试
-                        self.allocations.push(expr.to_token_stream().to_string());
-                        self.overflow_candidates.push(OverflowCandidate {
-                            location: expr.to_token_stream().to_string(),
-                            buffer_name: "vec".to_string(),
-                            operation: "allocation".to_string(),
-                            line: start.line,
-                            column: start.column,
-                            buffer_size: None,
-                            offset: None,
-                        });
-                    }
-                }
-            }
-            _ => {}
+            _ => None,
+        };
+        visit::visit_local(self, local);
+        self.current_binding = outer;
+    }
+
+    fn visit_expr_unsafe(&mut self, expr: &'ast syn::ExprUnsafe) {
+        let start = expr.span().start();
+        println!("Unsafe block at line {}, column {}", start.line, start.column); // 调试
+        self.unsafe_blocks.push(expr.to_token_stream().to_string());
+        visit::visit_expr_unsafe(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if mac.path.is_ident("vec") {
+            self.allocation(mac, mac.span());
         }
+        visit::visit_macro(self, mac);
+    }
+
+    fn visit_expr_closure(&mut self, closure: &'ast syn::ExprClosure) {
+        // A closure's own bindings are not the enclosing `let`'s.
+        let outer = self.current_binding.take();
+        visit::visit_expr_closure(self, closure);
+        self.current_binding = outer;
     }
 }
 
@@ -172,4 +193,72 @@ mod tests {
         let result = frontend.analyze().unwrap();
         assert!(!result.unsafe_blocks.is_empty());
     }
+
+    #[test]
+    fn test_pointer_candidates() {
+        let mut frontend = Frontend::new();
+        frontend.set_source_file(PathBuf::from("examples/buggy.rs"));
+
+        let result = frontend.analyze().unwrap();
+        let access = result.overflow_candidates.iter()
+            .find(|c| c.operation == "pointer_offset")
+            .unwrap();
+        assert_eq!(access.buffer_name, "v");
+        assert_eq!(access.pointer_name.as_deref(), Some("p"));
+        assert_eq!(access.buffer_size, Some(3));
+        assert_eq!(access.offset, Some(10));
+    }
+
+    #[test]
+    fn test_nested_items() {
+        let code = r#"
+            mod io {
+                pub struct Reader;
+
+                impl Reader {
+                    pub fn fill(&self) {
+                        let run = || {
+                            let scratch = vec![0u8; 4];
+                            let p = scratch.as_ptr();
+                            unsafe { let _ = *p.add(4); }
+                        };
+                        run();
+                    }
+                }
+
+                pub trait Source {
+                    fn read(&self) {
+                        for _ in 0..2 {
+                            let buf = vec![0u8; 2];
+                            let p = buf.as_ptr();
+                            unsafe { let _ = *p.add(3); }
+                        }
+                    }
+                }
+            }
+
+            unsafe fn raw() {
+                let data = vec![0u8; 1];
+                let p = data.as_ptr();
+                let _ = *p.add(1);
+            }
+        "#;
+        let ast = parse_file(code).unwrap();
+        let mut visitor = AstVisitor::new();
+        visitor.visit_file(&ast);
+        assert_eq!(visitor.unsafe_blocks.len(), 3);
+        let allocations: Vec<_> = visitor.overflow_candidates.iter()
+            .map(|c| (c.location.as_str(), c.buffer_name.as_str()))
+            .collect();
+        assert_eq!(allocations, vec![("io::Reader::fill", "scratch"), ("io::Source::read", "buf"), ("raw", "data")]);
+
+        let accesses: Vec<_> = find_buffer_overflows(&ast, Vec::new()).into_iter()
+            .map(|c| (c.location, c.buffer_name, c.offset))
+            .collect();
+        assert_eq!(accesses, vec![
+            ("io::Reader::fill".to_string(), "scratch".to_string(), Some(4)),
+            ("io::Source::read".to_string(), "buf".to_string(), Some(3)),
+            ("raw".to_string(), "data".to_string(), Some(1)),
+        ]);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,1292 @@
+//! Interval abstract interpretation over function bodies.
+//!
+//! Every integer variable of a function is given a range of values at each point of its
+//! body: `for` loops over `Range`/`RangeInclusive` bind their variable to an exact range
+//! and loops over constant arrays to the array's values, branch and loop conditions narrow the ranges on each side,
+//! and `while`/`loop` bodies are iterated to a fixpoint, widening the bounds that keep
+//! growing. The ranges are recorded at every call, method call, dereference and index
+//! expression, which is where the analyzer finds its accesses.
+
+use crate::analyzer::{eval_const_expr, SourceSpan};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use syn::{BinOp, Block, Expr, ExprLit, File, FnArg, Item, Lit, Pat, Stmt, Type, UnOp};
+
+/// A range of integers `lo..=hi`; a missing bound is unbounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub lo: Option<i128>,
+    pub hi: Option<i128>,
+}
+
+impl Interval {
+    pub const TOP: Interval = Interval { lo: None, hi: None };
+
+    pub fn constant(value: i128) -> Self {
+        Interval { lo: Some(value), hi: Some(value) }
+    }
+
+    pub fn new(lo: Option<i128>, hi: Option<i128>) -> Self {
+        Interval { lo, hi }
+    }
+
+    /// Values of an unsigned integer, `0..`.
+    pub fn non_negative() -> Self {
+        Interval { lo: Some(0), hi: None }
+    }
+
+    /// The smallest interval containing both.
+    pub fn join(&self, other: &Interval) -> Interval {
+        Interval {
+            lo: self.lo.zip(other.lo).map(|(a, b)| a.min(b)),
+            hi: self.hi.zip(other.hi).map(|(a, b)| a.max(b)),
+        }
+    }
+
+    /// Values in both intervals, `None` when there are none.
+    pub fn meet(&self, other: &Interval) -> Option<Interval> {
+        let lo = match (self.lo, other.lo) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        let hi = match (self.hi, other.hi) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        match (lo, hi) {
+            (Some(lo), Some(hi)) if lo > hi => None,
+            _ => Some(Interval { lo, hi }),
+        }
+    }
+
+    /// `self` with the bounds that grew since `old` dropped, so that loops stabilize.
+    fn widen(old: &Interval, new: &Interval) -> Interval {
+        Interval {
+            lo: old.lo.zip(new.lo).and_then(|(old, new)| (new >= old).then_some(old)),
+            hi: old.hi.zip(new.hi).and_then(|(old, new)| (new <= old).then_some(old)),
+        }
+    }
+
+    pub fn contains(&self, other: &Interval) -> bool {
+        let lo = match (self.lo, other.lo) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(a), Some(b)) => a <= b,
+        };
+        let hi = match (self.hi, other.hi) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(a), Some(b)) => a >= b,
+        };
+        lo && hi
+    }
+
+    fn add(&self, other: &Interval) -> Interval {
+        Interval {
+            lo: self.lo.zip(other.lo).and_then(|(a, b)| a.checked_add(b)),
+            hi: self.hi.zip(other.hi).and_then(|(a, b)| a.checked_add(b)),
+        }
+    }
+
+    fn neg(&self) -> Interval {
+        Interval { lo: self.hi.and_then(i128::checked_neg), hi: self.lo.and_then(i128::checked_neg) }
+    }
+
+    fn sub(&self, other: &Interval) -> Interval {
+        self.add(&other.neg())
+    }
+
+    fn mul(&self, other: &Interval) -> Interval {
+        if let (Some(a), Some(b), Some(c), Some(d)) = (self.lo, self.hi, other.lo, other.hi) {
+            let products = [a.checked_mul(c), a.checked_mul(d), b.checked_mul(c), b.checked_mul(d)];
+            if products.iter().all(Option::is_some) {
+                let products = products.map(Option::unwrap_or_default);
+                return Interval::new(products.iter().min().copied(), products.iter().max().copied());
+            }
+        }
+        match (self.lo, other.lo) {
+            (Some(a), Some(b)) if a >= 0 && b >= 0 => Interval {
+                lo: a.checked_mul(b),
+                hi: self.hi.zip(other.hi).and_then(|(a, b)| a.checked_mul(b)),
+            },
+            _ => Interval::TOP,
+        }
+    }
+
+    fn div(&self, other: &Interval) -> Interval {
+        match (self.lo, other.lo) {
+            (Some(a), Some(divisor)) if a >= 0 && divisor > 0 => Interval {
+                lo: Some(a / other.hi.unwrap_or(i128::MAX)),
+                hi: self.hi.map(|hi| hi / divisor),
+            },
+            _ => Interval::TOP,
+        }
+    }
+
+    fn rem(&self, other: &Interval) -> Interval {
+        match (self.lo, other.lo) {
+            (Some(a), Some(divisor)) if a >= 0 && divisor > 0 => {
+                let hi = match (self.hi, other.hi) {
+                    (Some(a), Some(b)) => Some(a.min(b - 1)),
+                    (a, b) => a.or(b.map(|b| b - 1)),
+                };
+                Interval { lo: Some(0), hi }
+            }
+            _ => Interval::TOP,
+        }
+    }
+
+    fn min(&self, other: &Interval) -> Interval {
+        Interval {
+            lo: self.lo.zip(other.lo).map(|(a, b)| a.min(b)),
+            hi: match (self.hi, other.hi) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+
+    fn max(&self, other: &Interval) -> Interval {
+        Interval {
+            lo: match (self.lo, other.lo) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            },
+            hi: self.hi.zip(other.hi).map(|(a, b)| a.max(b)),
+        }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.lo, self.hi) {
+            (Some(lo), Some(hi)) => write!(f, "[{}, {}]", lo, hi),
+            (Some(lo), None) => write!(f, "[{}, +inf)", lo),
+            (None, Some(hi)) => write!(f, "(-inf, {}]", hi),
+            (None, None) => write!(f, "(-inf, +inf)"),
+        }
+    }
+}
+
+/// Ranges of the integer variables at a program point; variables without an entry may
+/// hold any value.
+pub type Env = HashMap<String, Interval>;
+
+/// Ranges of the variables of one function body, recorded where each call, method call,
+/// dereference and index expression starts to execute.
+#[derive(Debug, Default)]
+pub struct FunctionRanges {
+    points: HashMap<Range<usize>, Env>,
+    /// The bodies of the `for` loops by their byte range, with the loop variables and the
+    /// values they take when the loop goes over an array of known values.
+    loops: Vec<(Range<usize>, String, Option<Vec<i128>>)>,
+}
+
+impl FunctionRanges {
+    /// Runs the analysis over `body`, with the parameters of `sig` in their types' ranges.
+    /// `arrays` gives the elements of the file's constant arrays.
+    pub fn analyze(sig: &syn::Signature, body: &Block, consts: &HashMap<String, usize>, arrays: &HashMap<String, Vec<i128>>) -> Self {
+        let escaped = escaped_variables(body);
+        let mut env = Env::new();
+        for input in &sig.inputs {
+            if let FnArg::Typed(arg) = input
+                && let Pat::Ident(ident) = &*arg.pat
+                && let Some(range) = type_range(&arg.ty)
+                && !escaped.contains(&ident.ident.to_string())
+            {
+                env.insert(ident.ident.to_string(), range);
+            }
+        }
+        let mut interpreter = Interpreter {
+            consts,
+            arrays: arrays.iter().map(|(name, values)| (name.clone(), hull(values))).collect(),
+            values: arrays.clone(),
+            escaped,
+            loops: Vec::new(),
+            recording: true,
+            points: HashMap::new(),
+            loop_values: Vec::new(),
+        };
+        let mut state = Some(env);
+        interpreter.block(body, &mut state);
+        FunctionRanges { points: interpreter.points, loops: interpreter.loop_values }
+    }
+
+    /// The ranges where the expression covering `span` executes, if it is reachable.
+    pub fn at(&self, span: &SourceSpan) -> Option<&Env> {
+        self.points.get(&span.byte_range)
+    }
+
+    /// The values `name` takes where the expression covering `span` executes, when it is
+    /// the variable of the innermost loop binding it and that loop goes over an array of
+    /// known values, e.g. `[1, 5, 12]` for `o` in `for o in [1, 5, 12]`. Values out of the
+    /// variable's range there, e.g. under an `if o < 10`, are left out.
+    pub fn values_at(&self, span: &SourceSpan, name: &str) -> Option<Vec<i128>> {
+        let range = self.at(span)?.get(name)?;
+        let (_, _, values) = self.loops.iter()
+            .filter(|(body, variable, _)| {
+                variable == name && body.start <= span.byte_range.start && span.byte_range.end <= body.end
+            })
+            .min_by_key(|(body, _, _)| body.len())?;
+        let mut values: Vec<i128> = values.as_ref()?.iter().copied().filter(|value| range.contains(&Interval::constant(*value))).collect();
+        values.sort();
+        values.dedup();
+        Some(values)
+    }
+}
+
+/// Range of an integer expression given as source text, e.g. `i + 1`.
+pub fn eval_text(text: &str, env: &Env, consts: &HashMap<String, usize>) -> Option<Interval> {
+    let expr = syn::parse_str::<Expr>(text).ok()?;
+    Some(eval(&expr, env, consts))
+}
+
+/// Elements of the file's `const` and `static` arrays of integer literals, e.g. `[1, 4, 9]`
+/// for `const STEPS: [usize; 3] = [1, 4, 9];`.
+pub fn const_arrays(ast: &File) -> HashMap<String, Vec<i128>> {
+    let mut arrays = HashMap::new();
+    for item in &ast.items {
+        let (ident, expr) = match item {
+            Item::Const(item) => (&item.ident, &*item.expr),
+            Item::Static(item) => (&item.ident, &*item.expr),
+            _ => continue,
+        };
+        if let Some(elements) = literal_elements(expr) {
+            arrays.insert(ident.to_string(), elements);
+        }
+    }
+    arrays
+}
+
+/// Elements of an array of integer literals such as `[0, 2, 4]` or `[0; 8]`.
+fn literal_elements(expr: &Expr) -> Option<Vec<i128>> {
+    match expr {
+        Expr::Array(array) if !array.elems.is_empty() => array.elems.iter().map(integer_literal).collect(),
+        Expr::Repeat(repeat) => integer_literal(&repeat.expr).map(|value| vec![value]),
+        Expr::Reference(reference) => literal_elements(&reference.expr),
+        _ => None,
+    }
+}
+
+/// The smallest interval containing all of `values`.
+fn hull(values: &[i128]) -> Interval {
+    Interval::new(values.iter().min().copied(), values.iter().max().copied())
+}
+
+fn integer_literal(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(int), .. }) => int.base10_parse().ok(),
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => integer_literal(&unary.expr).map(|value| -value),
+        Expr::Paren(paren) => integer_literal(&paren.expr),
+        _ => None,
+    }
+}
+
+/// Values of an integer type, e.g. `[0, 255]` for `u8`; `None` for other types.
+fn type_range(ty: &Type) -> Option<Interval> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let bits = match path.path.get_ident()?.to_string().as_str() {
+        "u8" => 8,
+        "u16" => 16,
+        "u32" => 32,
+        "u64" => 64,
+        "usize" | "u128" => return Some(Interval::non_negative()),
+        _ => return None,
+    };
+    Some(Interval::new(Some(0), Some((1i128 << bits) - 1)))
+}
+
+/// Range of an expression without side effects under `env`.
+fn eval(expr: &Expr, env: &Env, consts: &HashMap<String, usize>) -> Interval {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(int), .. }) => int.base10_parse().map(Interval::constant).unwrap_or(Interval::TOP),
+        Expr::Path(path) => match path.path.get_ident().map(|ident| ident.to_string()) {
+            Some(name) if env.contains_key(&name) => env[&name],
+            _ => match eval_const_expr(expr, consts) {
+                Some(value) => Interval::constant(value as i128),
+                None => Interval::TOP,
+            },
+        },
+        Expr::Paren(paren) => eval(&paren.expr, env, consts),
+        Expr::Group(group) => eval(&group.expr, env, consts),
+        Expr::Cast(cast) => {
+            let value = eval(&cast.expr, env, consts);
+            match type_range(&cast.ty) {
+                // A cast of a value that does not fit wraps around.
+                Some(range) if !range.contains(&value) => range,
+                _ => value,
+            }
+        }
+        Expr::Unary(unary) => match unary.op {
+            UnOp::Neg(_) => eval(&unary.expr, env, consts).neg(),
+            UnOp::Deref(_) => eval(&unary.expr, env, consts),
+            _ => Interval::TOP,
+        },
+        Expr::Reference(reference) => eval(&reference.expr, env, consts),
+        Expr::Binary(binary) => {
+            let left = eval(&binary.left, env, consts);
+            let right = eval(&binary.right, env, consts);
+            match binary.op {
+                BinOp::Add(_) => left.add(&right),
+                BinOp::Sub(_) => left.sub(&right),
+                BinOp::Mul(_) => left.mul(&right),
+                BinOp::Div(_) => left.div(&right),
+                BinOp::Rem(_) => left.rem(&right),
+                _ => Interval::TOP,
+            }
+        }
+        Expr::MethodCall(call) => {
+            let receiver = || eval(&call.receiver, env, consts);
+            let arg = || call.args.first().map(|arg| eval(arg, env, consts)).unwrap_or(Interval::TOP);
+            match call.method.to_string().as_str() {
+                "len" | "capacity" | "count_ones" | "leading_zeros" | "trailing_zeros" => Interval::non_negative(),
+                "min" => receiver().min(&arg()),
+                "max" => receiver().max(&arg()),
+                "clone" | "to_owned" => receiver(),
+                _ => Interval::TOP,
+            }
+        }
+        _ => Interval::TOP,
+    }
+}
+
+/// States at the `break`s and `continue`s of a loop being analyzed.
+struct LoopExits {
+    label: Option<String>,
+    breaks: Option<Env>,
+    continues: Option<Env>,
+}
+
+/// Walks a function body, carrying the ranges of its variables. A state of `None` is
+/// unreachable, e.g. after a `return`.
+struct Interpreter<'c> {
+    consts: &'c HashMap<String, usize>,
+    /// Element ranges of the arrays in scope, joined over every write to them.
+    arrays: HashMap<String, Interval>,
+    /// Elements of the arrays in scope that hold known values and were not written since.
+    values: HashMap<String, Vec<i128>>,
+    /// Variables whose address is taken, which any write through a pointer may change.
+    escaped: HashSet<String>,
+    loops: Vec<LoopExits>,
+    /// Off while a loop body is iterated towards its fixpoint, so that only the final pass
+    /// is recorded.
+    recording: bool,
+    points: HashMap<Range<usize>, Env>,
+    loop_values: Vec<(Range<usize>, String, Option<Vec<i128>>)>,
+}
+
+fn join(a: Option<Env>, b: Option<Env>) -> Option<Env> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.iter()
+            .filter_map(|(name, range)| Some((name.clone(), range.join(b.get(name)?))))
+            .collect()),
+        (a, b) => a.or(b),
+    }
+}
+
+fn widen(old: &Option<Env>, new: Option<Env>) -> Option<Env> {
+    match (old, new) {
+        (Some(old), Some(new)) => Some(old.iter()
+            .filter_map(|(name, range)| Some((name.clone(), Interval::widen(range, new.get(name)?))))
+            .collect()),
+        (_, new) => new,
+    }
+}
+
+/// Whether every state of `inner` is a state of `outer`.
+fn includes(outer: &Option<Env>, inner: &Option<Env>) -> bool {
+    match (outer, inner) {
+        (_, None) => true,
+        (None, Some(_)) => false,
+        (Some(outer), Some(inner)) => outer.iter().all(|(name, range)| inner.get(name).is_some_and(|value| range.contains(value))),
+    }
+}
+
+/// Names a pattern binds.
+fn pattern_names(pat: &Pat, names: &mut Vec<String>) {
+    match pat {
+        Pat::Ident(ident) => {
+            names.push(ident.ident.to_string());
+            if let Some((_, sub)) = &ident.subpat {
+                pattern_names(sub, names);
+            }
+        }
+        Pat::Type(typed) => pattern_names(&typed.pat, names),
+        Pat::Reference(reference) => pattern_names(&reference.pat, names),
+        Pat::Paren(paren) => pattern_names(&paren.pat, names),
+        Pat::Tuple(tuple) => tuple.elems.iter().for_each(|pat| pattern_names(pat, names)),
+        Pat::TupleStruct(tuple) => tuple.elems.iter().for_each(|pat| pattern_names(pat, names)),
+        Pat::Slice(slice) => slice.elems.iter().for_each(|pat| pattern_names(pat, names)),
+        Pat::Struct(pat) => pat.fields.iter().for_each(|field| pattern_names(&field.pat, names)),
+        Pat::Or(or) => or.cases.iter().for_each(|pat| pattern_names(pat, names)),
+        _ => {}
+    }
+}
+
+/// The variable an expression names, e.g. `i` in `i` or `(i)`.
+fn variable(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) => Some(path.path.get_ident()?.to_string()),
+        Expr::Paren(paren) => variable(&paren.expr),
+        Expr::Group(group) => variable(&group.expr),
+        _ => None,
+    }
+}
+
+/// Variables a closure assigns or borrows mutably, which may change whenever it runs.
+fn captured_writes(expr: &Expr) -> Vec<String> {
+    use syn::visit::Visit;
+    struct Writes(Vec<String>);
+    impl<'ast> Visit<'ast> for Writes {
+        fn visit_expr(&mut self, expr: &'ast Expr) {
+            match expr {
+                Expr::Assign(assign) => self.0.extend(variable(&assign.left)),
+                Expr::Binary(binary) if is_compound_assignment(&binary.op) => self.0.extend(variable(&binary.left)),
+                Expr::Reference(reference) if reference.mutability.is_some() => self.0.extend(variable(&reference.expr)),
+                _ => {}
+            }
+            syn::visit::visit_expr(self, expr);
+        }
+    }
+    let mut writes = Writes(Vec::new());
+    writes.visit_expr(expr);
+    writes.0
+}
+
+/// Variables whose address a body takes mutably or as a raw pointer, e.g. `i` in
+/// `&mut i as *mut usize`. The pointer may outlive the borrow, so no range holds for them.
+fn escaped_variables(body: &Block) -> HashSet<String> {
+    use syn::visit::Visit;
+    struct Escaped(HashSet<String>);
+    impl<'ast> Visit<'ast> for Escaped {
+        fn visit_expr(&mut self, expr: &'ast Expr) {
+            match expr {
+                Expr::Reference(reference) if reference.mutability.is_some() => self.0.extend(variable(&reference.expr)),
+                Expr::RawAddr(raw) => self.0.extend(variable(&raw.expr)),
+                _ => {}
+            }
+            syn::visit::visit_expr(self, expr);
+        }
+        fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+            if mac.path.segments.last().is_some_and(|segment| segment.ident == "addr_of" || segment.ident == "addr_of_mut")
+                && let Ok(place) = mac.parse_body::<Expr>()
+            {
+                self.0.extend(variable(&place));
+            }
+        }
+    }
+    let mut escaped = Escaped(HashSet::new());
+    escaped.visit_block(body);
+    escaped.0
+}
+
+/// Variables a loop body assigns, borrows mutably or binds again, e.g. `o` in
+/// `let o = o * 2;`.
+fn rebound_variables(body: &Block) -> HashSet<String> {
+    use syn::visit::Visit;
+    struct Rebound(HashSet<String>);
+    impl<'ast> Visit<'ast> for Rebound {
+        fn visit_expr(&mut self, expr: &'ast Expr) {
+            match expr {
+                Expr::Assign(assign) => self.0.extend(variable(&assign.left)),
+                Expr::Binary(binary) if is_compound_assignment(&binary.op) => self.0.extend(variable(&binary.left)),
+                Expr::Reference(reference) if reference.mutability.is_some() => self.0.extend(variable(&reference.expr)),
+                _ => {}
+            }
+            syn::visit::visit_expr(self, expr);
+        }
+        fn visit_pat_ident(&mut self, ident: &'ast syn::PatIdent) {
+            self.0.insert(ident.ident.to_string());
+            syn::visit::visit_pat_ident(self, ident);
+        }
+    }
+    let mut rebound = Rebound(HashSet::new());
+    rebound.visit_block(body);
+    rebound.0
+}
+
+fn is_compound_assignment(op: &BinOp) -> bool {
+    matches!(op, BinOp::AddAssign(_) | BinOp::SubAssign(_) | BinOp::MulAssign(_) | BinOp::DivAssign(_) | BinOp::RemAssign(_)
+        | BinOp::BitAndAssign(_) | BinOp::BitOrAssign(_) | BinOp::BitXorAssign(_) | BinOp::ShlAssign(_) | BinOp::ShrAssign(_))
+}
+
+/// The arithmetic operator of a compound assignment, e.g. `+` for `+=`.
+fn arithmetic(op: &BinOp) -> Option<fn(&Interval, &Interval) -> Interval> {
+    Some(match op {
+        BinOp::AddAssign(_) => Interval::add,
+        BinOp::SubAssign(_) => Interval::sub,
+        BinOp::MulAssign(_) => Interval::mul,
+        BinOp::DivAssign(_) => Interval::div,
+        BinOp::RemAssign(_) => Interval::rem,
+        _ => return None,
+    })
+}
+
+impl Interpreter<'_> {
+    /// Sets `name` to `value`, forgetting it when nothing is known or its address escapes.
+    fn assign(&self, env: &mut Env, name: String, value: Interval) {
+        if value == Interval::TOP || self.escaped.contains(&name) {
+            env.remove(&name);
+        } else {
+            env.insert(name, value);
+        }
+    }
+
+    fn eval(&self, expr: &Expr, state: &Option<Env>) -> Interval {
+        match state {
+            Some(env) => eval(expr, env, self.consts),
+            None => Interval::TOP,
+        }
+    }
+
+    fn record(&mut self, expr: &Expr, state: &Option<Env>) {
+        if !self.recording {
+            return;
+        }
+        let Some(env) = state else {
+            return;
+        };
+        let span = SourceSpan::from_span(syn::spanned::Spanned::span(expr));
+        match self.points.get_mut(&span.byte_range) {
+            Some(recorded) => *recorded = join(Some(recorded.clone()), Some(env.clone())).unwrap_or_default(),
+            None => {
+                self.points.insert(span.byte_range, env.clone());
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block, state: &mut Option<Env>) -> Interval {
+        let outer = state.clone();
+        let mut declared = Vec::new();
+        let mut value = Interval::TOP;
+        for stmt in &block.stmts {
+            value = Interval::TOP;
+            match stmt {
+                Stmt::Local(local) => {
+                    let init = local.init.as_ref().map(|init| self.expr(&init.expr, state));
+                    if let Some(diverge) = local.init.as_ref().and_then(|init| init.diverge.as_ref()) {
+                        // The `else` of a `let`-`else` must diverge.
+                        let mut otherwise = state.clone();
+                        self.expr(&diverge.1, &mut otherwise);
+                    }
+                    let mut names = Vec::new();
+                    pattern_names(&local.pat, &mut names);
+                    if let Some(init) = &local.init
+                        && let Some(elements) = self.elements(&init.expr, state)
+                        && let [name] = names.as_slice()
+                    {
+                        self.arrays.insert(name.clone(), elements);
+                    }
+                    for name in &names {
+                        self.values.remove(name);
+                    }
+                    if let Some(init) = &local.init
+                        && let Some(values) = self.element_values(&init.expr, state)
+                        && let [name] = names.as_slice()
+                    {
+                        self.values.insert(name.clone(), values);
+                    }
+                    if let Some(env) = state {
+                        for name in &names {
+                            env.remove(name);
+                        }
+                        if let (Pat::Ident(_) | Pat::Type(_), [name], Some(init)) = (&local.pat, names.as_slice(), init) {
+                            let value = match &local.pat {
+                                Pat::Type(typed) => match type_range(&typed.ty) {
+                                    Some(range) => range.meet(&init).unwrap_or(range),
+                                    None => init,
+                                },
+                                _ => init,
+                            };
+                            self.assign(env, name.clone(), value);
+                        }
+                    }
+                    declared.extend(names);
+                }
+                Stmt::Expr(expr, _) => value = self.expr(expr, state),
+                Stmt::Macro(stmt) => self.mac(&stmt.mac, state),
+                Stmt::Item(_) => {}
+            }
+        }
+        // Locals of the block go out of scope, uncovering the bindings they shadowed.
+        if let Some(env) = state {
+            for name in declared {
+                match outer.as_ref().and_then(|outer| outer.get(&name)) {
+                    Some(range) => env.insert(name, *range),
+                    None => env.remove(&name),
+                };
+            }
+        }
+        value
+    }
+
+    /// Element range of an array expression: a literal, or an array variable.
+    fn elements(&self, expr: &Expr, state: &Option<Env>) -> Option<Interval> {
+        match expr {
+            Expr::Array(array) => array.elems.iter()
+                .map(|elem| self.eval(elem, state))
+                .reduce(|a, b| a.join(&b)),
+            Expr::Repeat(repeat) => Some(self.eval(&repeat.expr, state)),
+            Expr::Paren(paren) => self.elements(&paren.expr, state),
+            Expr::Reference(reference) => self.elements(&reference.expr, state),
+            Expr::Path(path) => self.arrays.get(&path.path.get_ident()?.to_string()).copied(),
+            // `arr.iter()`, `arr.into_iter()` and `arr.iter().copied()` go over the elements.
+            Expr::MethodCall(call) if matches!(call.method.to_string().as_str(), "iter" | "into_iter" | "copied" | "cloned" | "rev") => {
+                self.elements(&call.receiver, state)
+            }
+            _ => None,
+        }
+    }
+
+    /// Values of the elements of an array expression, when all of them are known: a
+    /// literal of constants, or an array variable holding such values.
+    fn element_values(&self, expr: &Expr, state: &Option<Env>) -> Option<Vec<i128>> {
+        match expr {
+            Expr::Array(array) if !array.elems.is_empty() => array.elems.iter()
+                .map(|elem| {
+                    let value = self.eval(elem, state);
+                    value.lo.filter(|_| value.lo == value.hi)
+                })
+                .collect(),
+            Expr::Repeat(repeat) => {
+                let value = self.eval(&repeat.expr, state);
+                value.lo.filter(|_| value.lo == value.hi).map(|value| vec![value])
+            }
+            Expr::Paren(paren) => self.element_values(&paren.expr, state),
+            Expr::Reference(reference) => self.element_values(&reference.expr, state),
+            Expr::Path(path) => self.values.get(&path.path.get_ident()?.to_string()).cloned(),
+            Expr::MethodCall(call) if matches!(call.method.to_string().as_str(), "iter" | "into_iter" | "copied" | "cloned" | "rev") => {
+                self.element_values(&call.receiver, state)
+            }
+            _ => None,
+        }
+    }
+
+    fn mac(&mut self, mac: &syn::Macro, state: &mut Option<Env>) {
+        let name = mac.path.segments.last().map(|segment| segment.ident.to_string()).unwrap_or_default();
+        match name.as_str() {
+            "panic" | "unreachable" | "todo" | "unimplemented" => *state = None,
+            "assert" | "debug_assert" => {
+                let condition = mac.parse_body_with(syn::punctuated::Punctuated::<Expr, syn::Token![,]>::parse_terminated)
+                    .ok()
+                    .and_then(|args| args.into_iter().next());
+                if let Some(condition) = condition {
+                    self.expr(&condition, state);
+                    self.refine(&condition, true, state);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Evaluates `expr`, applying its effects on the variables to `state`.
+    fn expr(&mut self, expr: &Expr, state: &mut Option<Env>) -> Interval {
+        match expr {
+            Expr::Lit(_) | Expr::Path(_) => self.eval(expr, state),
+            Expr::Paren(paren) => self.expr(&paren.expr, state),
+            Expr::Group(group) => self.expr(&group.expr, state),
+            Expr::Cast(cast) => {
+                self.expr(&cast.expr, state);
+                self.eval(expr, state)
+            }
+            Expr::Unary(unary) => {
+                self.record(expr, state);
+                self.expr(&unary.expr, state);
+                self.eval(expr, state)
+            }
+            Expr::Reference(reference) => {
+                self.expr(&reference.expr, state);
+                // Borrowed mutably, the variable may change behind our back.
+                if reference.mutability.is_some()
+                    && let Some(name) = variable(&reference.expr)
+                {
+                    if let Some(env) = state {
+                        env.remove(&name);
+                    }
+                    self.arrays.remove(&name);
+                    self.values.remove(&name);
+                }
+                Interval::TOP
+            }
+            Expr::Binary(binary) if is_compound_assignment(&binary.op) => {
+                let right = self.expr(&binary.right, state);
+                self.expr(&binary.left, state);
+                if let Some(name) = variable(&binary.left)
+                    && let Some(env) = state
+                {
+                    let left = eval(&binary.left, env, self.consts);
+                    let value = arithmetic(&binary.op).map_or(Interval::TOP, |op| op(&left, &right));
+                    self.assign(env, name, value);
+                }
+                Interval::TOP
+            }
+            Expr::Binary(binary) if matches!(binary.op, BinOp::And(_) | BinOp::Or(_)) => {
+                self.expr(&binary.left, state);
+                // The right operand only runs when the left one did not decide.
+                let mut right = state.clone();
+                self.refine(&binary.left, matches!(binary.op, BinOp::And(_)), &mut right);
+                self.expr(&binary.right, &mut right);
+                *state = join(state.take(), right);
+                Interval::TOP
+            }
+            Expr::Binary(binary) => {
+                self.expr(&binary.left, state);
+                self.expr(&binary.right, state);
+                self.eval(expr, state)
+            }
+            Expr::Assign(assign_expr) => {
+                let value = self.expr(&assign_expr.right, state);
+                match variable(&assign_expr.left) {
+                    Some(name) => {
+                        if let Some(elements) = self.elements(&assign_expr.right, state) {
+                            self.arrays.insert(name.clone(), elements);
+                        }
+                        match self.element_values(&assign_expr.right, state) {
+                            Some(values) => self.values.insert(name.clone(), values),
+                            None => self.values.remove(&name),
+                        };
+                        if let Some(env) = state {
+                            self.assign(env, name, value);
+                        }
+                    }
+                    None => {
+                        // `arr[i] = v` adds `v` to the elements of `arr`.
+                        if let Expr::Index(index) = &*assign_expr.left
+                            && let Some(name) = variable(&index.expr)
+                        {
+                            if let Some(elements) = self.arrays.get_mut(&name) {
+                                *elements = elements.join(&value);
+                            }
+                            self.values.remove(&name);
+                        }
+                        self.expr(&assign_expr.left, state);
+                    }
+                }
+                Interval::TOP
+            }
+            Expr::Index(index) => {
+                self.record(expr, state);
+                self.expr(&index.expr, state);
+                self.expr(&index.index, state);
+                match variable(&index.expr) {
+                    Some(name) => self.arrays.get(&name).copied().unwrap_or(Interval::TOP),
+                    None => Interval::TOP,
+                }
+            }
+            Expr::Field(field) => {
+                self.expr(&field.base, state);
+                Interval::TOP
+            }
+            Expr::MethodCall(call) => {
+                self.record(expr, state);
+                self.expr(&call.receiver, state);
+                for arg in &call.args {
+                    self.expr(arg, state);
+                }
+                self.eval(expr, state)
+            }
+            Expr::Call(call) => {
+                self.record(expr, state);
+                self.expr(&call.func, state);
+                for arg in &call.args {
+                    self.expr(arg, state);
+                }
+                Interval::TOP
+            }
+            Expr::Macro(expr_macro) => {
+                self.mac(&expr_macro.mac, state);
+                Interval::TOP
+            }
+            Expr::Block(block) => self.block(&block.block, state),
+            Expr::Unsafe(unsafe_expr) => self.block(&unsafe_expr.block, state),
+            Expr::If(if_expr) => {
+                self.expr(&if_expr.cond, state);
+                let mut then = state.clone();
+                self.refine(&if_expr.cond, true, &mut then);
+                let then_value = self.block(&if_expr.then_branch, &mut then);
+                let mut otherwise = state.clone();
+                self.refine(&if_expr.cond, false, &mut otherwise);
+                let else_value = match &if_expr.else_branch {
+                    Some((_, else_branch)) => self.expr(else_branch, &mut otherwise),
+                    None => Interval::TOP,
+                };
+                let value = match (&then, &otherwise) {
+                    (Some(_), Some(_)) => then_value.join(&else_value),
+                    (Some(_), None) => then_value,
+                    (None, _) => else_value,
+                };
+                *state = join(then, otherwise);
+                value
+            }
+            Expr::Match(match_expr) => {
+                self.expr(&match_expr.expr, state);
+                let mut after = None;
+                let mut value: Option<Interval> = None;
+                for arm in &match_expr.arms {
+                    let mut arm_state = state.clone();
+                    let mut names = Vec::new();
+                    pattern_names(&arm.pat, &mut names);
+                    if let Some(env) = &mut arm_state {
+                        for name in &names {
+                            env.remove(name);
+                        }
+                    }
+                    if let Some((_, guard)) = &arm.guard {
+                        self.expr(guard, &mut arm_state);
+                        self.refine(guard, true, &mut arm_state);
+                    }
+                    let arm_value = self.expr(&arm.body, &mut arm_state);
+                    if arm_state.is_some() {
+                        value = Some(value.map_or(arm_value, |value| value.join(&arm_value)));
+                    }
+                    if let Some(env) = &mut arm_state {
+                        for name in &names {
+                            match state.as_ref().and_then(|outer| outer.get(name)) {
+                                Some(range) => env.insert(name.clone(), *range),
+                                None => env.remove(name),
+                            };
+                        }
+                    }
+                    after = join(after, arm_state);
+                }
+                *state = after;
+                value.unwrap_or(Interval::TOP)
+            }
+            Expr::Let(let_expr) => {
+                self.expr(&let_expr.expr, state);
+                let mut names = Vec::new();
+                pattern_names(&let_expr.pat, &mut names);
+                if let Some(env) = state {
+                    for name in names {
+                        env.remove(&name);
+                    }
+                }
+                Interval::TOP
+            }
+            Expr::While(while_expr) => {
+                let label = while_expr.label.as_ref().map(|label| label.name.ident.to_string());
+                let entry = state.clone();
+                let cond = &while_expr.cond;
+                let body = &while_expr.body;
+                let head = self.fixpoint(label, entry, |this, head| {
+                    let mut body_state = head.clone();
+                    this.expr(cond, &mut body_state);
+                    this.refine(cond, true, &mut body_state);
+                    this.block(body, &mut body_state);
+                    body_state
+                });
+                let (head, exits) = head;
+                let mut exit = head;
+                self.expr(cond, &mut exit);
+                self.refine(cond, false, &mut exit);
+                *state = join(exit, exits.breaks);
+                Interval::TOP
+            }
+            Expr::Loop(loop_expr) => {
+                let label = loop_expr.label.as_ref().map(|label| label.name.ident.to_string());
+                let entry = state.clone();
+                let body = &loop_expr.body;
+                let (_, exits) = self.fixpoint(label, entry, |this, head| {
+                    let mut body_state = head.clone();
+                    this.block(body, &mut body_state);
+                    body_state
+                });
+                *state = exits.breaks;
+                Interval::TOP
+            }
+            Expr::ForLoop(for_expr) => {
+                self.expr(&for_expr.expr, state);
+                let item = self.iteration(&for_expr.expr, state);
+                let label = for_expr.label.as_ref().map(|label| label.name.ident.to_string());
+                let mut names = Vec::new();
+                pattern_names(&for_expr.pat, &mut names);
+                let pat = &for_expr.pat;
+                let body = &for_expr.body;
+                if self.recording {
+                    // The variable only takes the array's values while the body keeps it.
+                    let values = self.element_values(&for_expr.expr, state)
+                        .filter(|_| matches!(&**pat, Pat::Ident(_) | Pat::Reference(_)) && names.len() == 1);
+                    let rebound = rebound_variables(body);
+                    let span = SourceSpan::from_span(syn::spanned::Spanned::span(body));
+                    for name in &names {
+                        let values = values.clone().filter(|_| !rebound.contains(name));
+                        self.loop_values.push((span.byte_range.clone(), name.clone(), values));
+                    }
+                }
+                let outer = state.clone();
+                let (head, exits) = self.fixpoint(label, state.clone(), |this, head| {
+                    let mut body_state = head.clone();
+                    if let Some(env) = &mut body_state {
+                        for name in &names {
+                            env.remove(name);
+                        }
+                        // `for i in ..` and `for &x in &arr` bind the item itself.
+                        if let (Pat::Ident(_) | Pat::Reference(_), [name], Some(item)) = (&**pat, names.as_slice(), item) {
+                            this.assign(env, name.clone(), item);
+                        }
+                    }
+                    this.block(body, &mut body_state);
+                    body_state
+                });
+                // The loop variable is not in scope after the loop.
+                let mut after = join(head, exits.breaks);
+                if let Some(env) = &mut after {
+                    for name in &names {
+                        match outer.as_ref().and_then(|outer| outer.get(name)) {
+                            Some(range) => env.insert(name.clone(), *range),
+                            None => env.remove(name),
+                        };
+                    }
+                }
+                *state = after;
+                Interval::TOP
+            }
+            Expr::Break(break_expr) => {
+                if let Some(value) = &break_expr.expr {
+                    self.expr(value, state);
+                }
+                let label = break_expr.label.as_ref().map(|label| label.ident.to_string());
+                if let Some(exits) = self.target(label) {
+                    exits.breaks = join(exits.breaks.take(), state.clone());
+                }
+                *state = None;
+                Interval::TOP
+            }
+            Expr::Continue(continue_expr) => {
+                let label = continue_expr.label.as_ref().map(|label| label.ident.to_string());
+                if let Some(exits) = self.target(label) {
+                    exits.continues = join(exits.continues.take(), state.clone());
+                }
+                *state = None;
+                Interval::TOP
+            }
+            Expr::Return(return_expr) => {
+                if let Some(value) = &return_expr.expr {
+                    self.expr(value, state);
+                }
+                *state = None;
+                Interval::TOP
+            }
+            Expr::Try(try_expr) => {
+                self.expr(&try_expr.expr, state);
+                Interval::TOP
+            }
+            Expr::Closure(closure) => {
+                // The closure may run at any later point.
+                if let Some(env) = state {
+                    for name in captured_writes(&closure.body) {
+                        env.remove(&name);
+                    }
+                }
+                Interval::TOP
+            }
+            Expr::Array(array) => {
+                for elem in &array.elems {
+                    self.expr(elem, state);
+                }
+                Interval::TOP
+            }
+            Expr::Tuple(tuple) => {
+                for elem in &tuple.elems {
+                    self.expr(elem, state);
+                }
+                Interval::TOP
+            }
+            Expr::Repeat(repeat) => {
+                self.expr(&repeat.expr, state);
+                Interval::TOP
+            }
+            Expr::Struct(expr_struct) => {
+                for field in &expr_struct.fields {
+                    self.expr(&field.expr, state);
+                }
+                Interval::TOP
+            }
+            Expr::Range(range) => {
+                if let Some(start) = &range.start {
+                    self.expr(start, state);
+                }
+                if let Some(end) = &range.end {
+                    self.expr(end, state);
+                }
+                Interval::TOP
+            }
+            _ => Interval::TOP,
+        }
+    }
+
+    /// The loop a `break` or `continue` with `label` leaves.
+    fn target(&mut self, label: Option<String>) -> Option<&mut LoopExits> {
+        match label {
+            Some(label) => self.loops.iter_mut().rev().find(|exits| exits.label.as_ref() == Some(&label)),
+            None => self.loops.last_mut(),
+        }
+    }
+
+    /// Iterates a loop body from `entry` until the state at the loop head is stable,
+    /// widening after the first rounds, then narrows it once and records a final pass.
+    /// Returns the state at the head and the exits of the final pass.
+    fn fixpoint(
+        &mut self,
+        label: Option<String>,
+        entry: Option<Env>,
+        mut body: impl FnMut(&mut Self, &Option<Env>) -> Option<Env>,
+    ) -> (Option<Env>, LoopExits) {
+        let recording = std::mem::replace(&mut self.recording, false);
+        let mut round = |this: &mut Self, head: &Option<Env>| {
+            this.loops.push(LoopExits { label: label.clone(), breaks: None, continues: None });
+            let end = body(this, head);
+            let exits = this.loops.pop().expect("loop exits were pushed");
+            let next = join(entry.clone(), join(end, exits.continues.clone()));
+            (next, exits)
+        };
+        let mut head = entry.clone();
+        for iteration in 0.. {
+            let (next, _) = round(self, &head);
+            if includes(&head, &next) {
+                break;
+            }
+            head = if iteration < 2 { join(head, next) } else { widen(&head, join(head.clone(), next)) };
+        }
+        // One more round without widening recovers bounds that only the loop condition gives.
+        let (narrowed, _) = round(self, &head);
+        if includes(&head, &narrowed) {
+            head = narrowed;
+        }
+        self.recording = recording;
+        let (_, exits) = round(self, &head);
+        (head, exits)
+    }
+
+    /// Range of the items a `for` loop iterates over: a `start..end` or `start..=end`
+    /// range, or the elements of an array.
+    fn iteration(&self, iter: &Expr, state: &Option<Env>) -> Option<Interval> {
+        match iter {
+            Expr::Range(range) => {
+                let start = range.start.as_ref().map_or(Interval::TOP, |start| self.eval(start, state));
+                let end = range.end.as_ref().map_or(Interval::TOP, |end| self.eval(end, state));
+                let last = match range.limits {
+                    syn::RangeLimits::HalfOpen(_) => end.sub(&Interval::constant(1)),
+                    syn::RangeLimits::Closed(_) => end,
+                };
+                Some(Interval::new(start.lo, last.hi))
+            }
+            Expr::Paren(paren) => self.iteration(&paren.expr, state),
+            // Every other step or the items in reverse are still within the range.
+            Expr::MethodCall(call) if matches!(call.method.to_string().as_str(), "rev" | "step_by") => {
+                self.iteration(&call.receiver, state)
+            }
+            iter => self.elements(iter, state),
+        }
+    }
+
+    /// Narrows `state` to the executions where `cond` evaluates to `truth`.
+    fn refine(&self, cond: &Expr, truth: bool, state: &mut Option<Env>) {
+        let Some(env) = state else {
+            return;
+        };
+        match cond {
+            Expr::Paren(paren) => self.refine(&paren.expr, truth, state),
+            Expr::Group(group) => self.refine(&group.expr, truth, state),
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Not(_)) => self.refine(&unary.expr, !truth, state),
+            Expr::Binary(binary) => match (&binary.op, truth) {
+                (BinOp::And(_), true) | (BinOp::Or(_), false) => {
+                    self.refine(&binary.left, truth, state);
+                    self.refine(&binary.right, truth, state);
+                }
+                (BinOp::And(_), false) | (BinOp::Or(_), true) => {
+                    let mut left = state.clone();
+                    self.refine(&binary.left, truth, &mut left);
+                    let mut right = state.clone();
+                    self.refine(&binary.left, !truth, &mut right);
+                    self.refine(&binary.right, truth, &mut right);
+                    *state = join(left, right);
+                }
+                (op, truth) => {
+                    let left = eval(&binary.left, env, self.consts);
+                    let right = eval(&binary.right, env, self.consts);
+                    // `a < b` holds when `b > a` does.
+                    let narrowed = [(&binary.left, right, *op), (&binary.right, left, mirror(op))];
+                    for (side, other, op) in narrowed {
+                        let Some(name) = variable(side) else {
+                            continue;
+                        };
+                        let Some(op) = (if truth { Some(op) } else { negation(&op) }) else {
+                            continue;
+                        };
+                        let current = env.get(&name).copied().unwrap_or(Interval::TOP);
+                        let allowed = match op {
+                            BinOp::Lt(_) => Interval::new(None, other.hi.map(|hi| hi - 1)),
+                            BinOp::Le(_) => Interval::new(None, other.hi),
+                            BinOp::Gt(_) => Interval::new(other.lo.map(|lo| lo + 1), None),
+                            BinOp::Ge(_) => Interval::new(other.lo, None),
+                            BinOp::Eq(_) => other,
+                            BinOp::Ne(_) => match (other.lo, other.hi) {
+                                (Some(value), Some(hi)) if value == hi && current.lo == Some(value) => Interval::new(Some(value + 1), None),
+                                (Some(value), Some(hi)) if value == hi && current.hi == Some(value) => Interval::new(None, Some(value - 1)),
+                                _ => Interval::TOP,
+                            },
+                            _ => Interval::TOP,
+                        };
+                        match current.meet(&allowed) {
+                            Some(range) => self.assign(env, name, range),
+                            None => {
+                                *state = None;
+                                return;
+                            }
+                        }
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+/// The comparison with its operands swapped, e.g. `>` for `<`.
+fn mirror(op: &BinOp) -> BinOp {
+    match op {
+        BinOp::Lt(token) => BinOp::Gt(syn::Token![>](token.span)),
+        BinOp::Le(token) => BinOp::Ge(syn::Token![>=](token.spans)),
+        BinOp::Gt(token) => BinOp::Lt(syn::Token![<](token.span)),
+        BinOp::Ge(token) => BinOp::Le(syn::Token![<=](token.spans)),
+        op => *op,
+    }
+}
+
+/// The comparison that holds when `op` does not.
+fn negation(op: &BinOp) -> Option<BinOp> {
+    Some(match op {
+        BinOp::Lt(token) => BinOp::Ge(syn::Token![>=]([token.span; 2])),
+        BinOp::Le(token) => BinOp::Gt(syn::Token![>](token.spans[0])),
+        BinOp::Gt(token) => BinOp::Le(syn::Token![<=]([token.span; 2])),
+        BinOp::Ge(token) => BinOp::Lt(syn::Token![<](token.spans[0])),
+        BinOp::Eq(token) => BinOp::Ne(syn::Token![!=](token.spans)),
+        BinOp::Ne(token) => BinOp::Eq(syn::Token![==](token.spans)),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::visit::Visit;
+
+    /// Ranges of the variables at each `ptr.add(..)` call of `func`, in source order.
+    fn ranges_at_adds(code: &str) -> Vec<Option<Env>> {
+        struct Adds(Vec<SourceSpan>);
+        impl<'ast> Visit<'ast> for Adds {
+            fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+                if call.method == "add" {
+                    self.0.push(SourceSpan::from_span(syn::spanned::Spanned::span(call)));
+                }
+                syn::visit::visit_expr_method_call(self, call);
+            }
+        }
+        let ast = syn::parse_file(code).unwrap();
+        let Item::Fn(func) = &ast.items[0] else {
+            panic!("expected a function");
+        };
+        let ranges = FunctionRanges::analyze(&func.sig, &func.block, &HashMap::new(), &const_arrays(&ast));
+        let mut adds = Adds(Vec::new());
+        adds.visit_item_fn(func);
+        adds.0.iter().map(|span| ranges.at(span).cloned()).collect()
+    }
+
+    fn range(env: &Option<Env>, name: &str) -> Interval {
+        env.as_ref().and_then(|env| env.get(name)).copied().unwrap_or(Interval::TOP)
+    }
+
+    #[test]
+    fn test_interval_arithmetic() {
+        let a = Interval::new(Some(0), Some(10));
+        let b = Interval::constant(3);
+        assert_eq!(a.add(&b), Interval::new(Some(3), Some(13)));
+        assert_eq!(a.sub(&b), Interval::new(Some(-3), Some(7)));
+        assert_eq!(a.mul(&b), Interval::new(Some(0), Some(30)));
+        assert_eq!(a.rem(&b), Interval::new(Some(0), Some(2)));
+        assert_eq!(a.join(&Interval::constant(20)), Interval::new(Some(0), Some(20)));
+        assert_eq!(a.meet(&Interval::new(Some(11), None)), None);
+        assert_eq!(Interval::widen(&a, &Interval::new(Some(0), Some(11))), Interval::non_negative());
+    }
+
+    #[test]
+    fn test_loop_ranges() {
+        let found = ranges_at_adds(r#"
+            fn fill(p: *mut u8, n: usize, flags: u8) {
+                for i in 0..=n {
+                    p.add(i);
+                }
+                for &x in &[3, 1, 7] {
+                    p.add(x);
+                }
+                let mut k = 0;
+                while k < 10 {
+                    p.add(k);
+                    k += 1;
+                }
+                p.add(k);
+                'outer: loop {
+                    if k > 20 {
+                        break 'outer;
+                    }
+                    k += 3;
+                }
+                p.add(k);
+                if flags >= 4 {
+                    return;
+                }
+                p.add(flags as usize);
+            }
+        "#);
+        assert_eq!(range(&found[0], "i"), Interval::non_negative());
+        assert_eq!(range(&found[1], "x"), Interval::new(Some(1), Some(7)));
+        assert_eq!(range(&found[2], "k"), Interval::new(Some(0), Some(9)));
+        assert_eq!(range(&found[3], "k"), Interval::constant(10));
+        assert_eq!(range(&found[4], "k"), Interval::new(Some(21), Some(23)));
+        assert_eq!(range(&found[5], "flags"), Interval::new(Some(0), Some(3)));
+    }
+
+    #[test]
+    fn test_unreachable_code_has_no_ranges() {
+        let found = ranges_at_adds(r#"
+            fn write(p: *mut u8, i: usize) {
+                if i < 4 {
+                    if i > 8 {
+                        p.add(i);
+                    }
+                    p.add(i);
+                }
+                panic!("done");
+                p.add(i);
+            }
+        "#);
+        assert!(found[0].is_none());
+        assert_eq!(range(&found[1], "i"), Interval::new(Some(0), Some(3)));
+        assert!(found[2].is_none());
+    }
+
+    #[test]
+    fn test_escaped_variables_have_no_ranges() {
+        let found = ranges_at_adds(r#"
+            fn write(p: *mut u8, i: usize) {
+                let r = std::ptr::addr_of_mut!(i);
+                let mut k = 0;
+                let q = &mut k as *mut i32;
+                let mut n = 0;
+                for j in 0..4 {
+                    k = j;
+                    n = j;
+                    if i < 4 {
+                        p.add(i + k + n);
+                    }
+                }
+            }
+        "#);
+        let env = found[0].as_ref().unwrap();
+        assert!(!env.contains_key("i") && !env.contains_key("k"));
+        assert_eq!(range(&found[0], "n"), Interval::new(Some(0), Some(3)));
+    }
+}
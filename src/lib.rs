@@ -6,17 +6,30 @@
 
 #[cfg(feature = "with-rustc")]
 extern crate rustc_driver;
+#[cfg(feature = "with-rustc")]
+extern crate rustc_hir;
+#[cfg(feature = "with-rustc")]
+extern crate rustc_interface;
+#[cfg(feature = "with-rustc")]
+extern crate rustc_middle;
+#[cfg(feature = "with-rustc")]
+extern crate rustc_span;
 
 pub mod frontend;
 pub mod analyzer;
+pub mod interval;
 pub mod solver;
 pub mod rectifier;
 pub mod validator;
 pub mod mir_analyzer;
+pub mod mir_parser;
+pub mod mir_symbolic;
+#[cfg(feature = "with-rustc")]
+pub mod mir_driver;
 
 pub use analyzer::OverflowCandidate;
 pub use rectifier::{CodeFix, Rectifier, FixType, ErrorReport};
-pub use solver::{BufferSolver, BufferConstraint};
+pub use solver::{BufferSolver, BufferConstraint, PointerWidth, Verdict};
 pub use validator::*;
 pub use mir_analyzer::MirAnalyzer;
 
@@ -47,14 +60,29 @@ impl RuPair {
         
         let mut fixed = content.clone();
         let mut fixes = Vec::new();
+        let mut issues = Vec::new();
+        let mut proven_safe = 0;
         
         for candidate in &candidates {
-            let constraint = solver.check_overflow(candidate);
+            let constraint = solver.check_candidate(candidate);
+            println!("{}: {} at {}", constraint.verdict, candidate.operation, candidate.span.location());
+            if constraint.verdict == Verdict::ProvenSafe {
+                proven_safe += 1;
+                continue;
+            }
+            // 记录问题对应的修复，报告中按下标取回
+            let mut fix_index = None;
             if constraint.is_overflow {
                 let fix = rectifier.generate_fix(candidate, &constraint)?;
-                fixes.push(fix);
+                if fix.fixed_code != fix.original_code {
+                    fix_index = Some(fixes.len());
+                    fixes.push(fix);
+                }
             }
+            issues.push((candidate, constraint.verdict, fix_index));
         }
+        // 先报告确定的溢出，再报告可能的和无法判定的
+        issues.sort_by_key(|(_, verdict, _)| verdict.priority());
     
         // 调试：打印 fixes
         println!("Generated {} fixes", fixes.len());
@@ -64,15 +92,21 @@ impl RuPair {
     
         // 替换修复代码
         for fix in &fixes {
-            let re = Regex::new(r"(?s)unsafe\s*\{[^{}]*\*ptr\.add\s*\(\d+\)[^{}]*\}").unwrap();
+            // 原地替换为带检查的调用
+            if matches!(fix.fix_type, FixType::CheckedCall) {
+                fixed = fixed.replacen(&fix.original_code, &fix.fixed_code, 1);
+                continue;
+            }
+            let re = Regex::new(&format!(r"(?s)unsafe\s*\{{[^{{}}]*{}[^{{}}]*\}}", regex::escape(&fix.original_code)))?;
             if re.is_match(&fixed) {
                 fixed = re.replace(&fixed, &fix.fixed_code).to_string();
             } else {
                 println!("Warning: Could not find unsafe block for fix: {:?}", fix);
                 // 后备替换：基于行号
                 let lines: Vec<&str> = fixed.lines().collect();
-                if fix.location.contains("Line") {
-                    if let Ok(line_num) = fix.location.replace("Line ", "").parse::<usize>() {
+                if let Some(position) = fix.location.strip_prefix("Line ") {
+                    let line = position.split(',').next().unwrap_or(position);
+                    if let Ok(line_num) = line.trim().parse::<usize>() {
                         if line_num > 0 && line_num <= lines.len() {
                             let mut new_lines = lines.to_vec();
                             new_lines[line_num - 1] = &fix.fixed_code;
@@ -86,17 +120,24 @@ impl RuPair {
         let mut report = String::from("# Buffer Overflow Analysis Report\n\n");
         report.push_str("## Analysis Overview\n\n");
         report.push_str(&format!("- Source File: {}\n", self.source_file.display()));
-        report.push_str(&format!("- Issues Found: {}\n\n", candidates.len()));
+        report.push_str(&format!("- Candidates Checked: {}\n", candidates.len()));
+        report.push_str(&format!("- Issues Found: {}\n", issues.len()));
+        let count = |priority| issues.iter().filter(|(_, verdict, _)| verdict.priority() == priority).count();
+        report.push_str(&format!("  - Definite Overflows: {}\n", count(0)));
+        report.push_str(&format!("  - Possible Overflows: {}\n", count(1)));
+        report.push_str(&format!("  - Unknown: {}\n", count(2)));
+        report.push_str(&format!("- Proven Safe: {}\n\n", proven_safe));
         
-        for (i, candidate) in candidates.iter().enumerate() {
+        for (i, (candidate, verdict, fix_index)) in issues.iter().enumerate() {
             let error_report = rectifier.generate_error_report(candidate);
             report.push_str(&format!("## Issue #{}\n\n", i + 1));
+            report.push_str(&format!("### Verdict\n{}\n\n", verdict));
             report.push_str(&format!("### Location\n{}\n\n", error_report.location));
             report.push_str(&format!("### Operation Type\n{}\n\n", error_report.issue_type));
             report.push_str(&format!("### Description\n{}\n\n", error_report.description));
             report.push_str(&format!("### Fix Suggestion\n{}\n\n", error_report.recommendation));
             
-            if let Some(fix) = fixes.iter().find(|f| f.location == error_report.location) {
+            if let Some(fix) = fix_index.map(|index| &fixes[index]) {
                 report.push_str("### Original Code\n```rust\n");
                 report.push_str(&fix.original_code);
                 report.push_str("\n```\n\n");
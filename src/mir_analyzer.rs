@@ -1,16 +1,22 @@
-#[cfg(feature = "with-rustc")]
-extern crate rustc_driver;
-
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+#[cfg(not(feature = "with-rustc"))]
+use std::process::Command;
+#[cfg(not(feature = "with-rustc"))]
+use anyhow::bail;
 use anyhow::Result;
 use walkdir::WalkDir;
 use regex::Regex;
 use syn::{self, parse_file};
 use std::fs;
+use std::collections::HashMap;
 
-use crate::analyzer::OverflowCandidate;
+use crate::analyzer::{OverflowCandidate, SourceSpan, find_buffer_overflows, POINTER_METHODS};
 use crate::rectifier::Rectifier;
 use crate::solver::BufferSolver;
+use crate::mir_symbolic::SymbolicExecutor;
+use crate::mir_parser::{
+    parse_mir, BinOp, Local, MirBody, MirSpan, Operand, Place, PlaceElem, Rvalue, StatementKind, Terminator,
+};
 
 pub struct MirAnalyzer {
     output_dir: PathBuf,
@@ -45,34 +51,109 @@ impl MirAnalyzer {
 
     pub fn analyze(&mut self) -> Result<()> {
         self.analyze_source_code()?;
-        
-        for entry in WalkDir::new(&self.output_dir) {
-            let entry = entry?;
-            if entry.path().extension().map_or(false, |ext| ext == "mir") {
-                let content = fs::read_to_string(entry.path())?;
-                self.analyze_mir_content(&content)?;
-            }
+
+        #[cfg(feature = "with-rustc")]
+        if let Err(e) = self.analyze_compiled_mir() {
+            println!("无法编译获取 MIR ({})，使用 {} 中已有的 MIR 文件", e, self.output_dir.display());
+            self.analyze_existing_dumps()?;
         }
 
+        #[cfg(not(feature = "with-rustc"))]
+        self.analyze_dumped_mir()?;
+
         self.print_analysis_results();
         Ok(())
     }
 
+    /// Runs `rustc_driver` on the source file and merges the candidates found in its MIR,
+    /// then executes the MIR of its functions symbolically.
+    #[cfg(feature = "with-rustc")]
+    fn analyze_compiled_mir(&mut self) -> Result<()> {
+        let candidates = crate::mir_driver::collect_candidates(&self.source_file, crate::mir_driver::MirSource::Optimized)?;
+        for candidate in candidates {
+            if !self.is_reported(&candidate) {
+                self.overflow_candidates.push(candidate);
+            }
+        }
+        let bodies = crate::mir_driver::collect_bodies(&self.source_file)?;
+        self.explore_paths(&bodies);
+        Ok(())
+    }
+
+    /// Dumps the MIR of the source file with the local nightly `rustc` and analyzes it.
+    ///
+    /// When `rustc` cannot produce dumps (e.g. a stable toolchain), falls back to the dumps
+    /// already in `output_dir` that belong to the source file.
+    #[cfg(not(feature = "with-rustc"))]
+    fn analyze_dumped_mir(&mut self) -> Result<()> {
+        let dumps = match dump_mir(&self.source_file, &crate_name(&self.source_file)) {
+            Ok(dumps) => dumps,
+            Err(e) => {
+                println!("无法生成 MIR ({})，使用 {} 中已有的 MIR 文件", e, self.output_dir.display());
+                return self.analyze_existing_dumps();
+            }
+        };
+
+        self.analyze_mir_dumps(&dumps)
+    }
+
+    /// Analyzes the dumps in `output_dir` that were produced for the source file.
+    fn analyze_existing_dumps(&mut self) -> Result<()> {
+        let dumps = select_dumps(&self.output_dir, &crate_name(&self.source_file))?
+            .iter()
+            .map(|path| Ok(fs::read_to_string(path)?))
+            .collect::<Result<Vec<String>>>()?;
+        self.analyze_mir_dumps(&dumps)
+    }
+
+    /// Whether the same access was already found, e.g. by the AST pass.
+    fn is_reported(&self, candidate: &OverflowCandidate) -> bool {
+        let offset = |c: &OverflowCandidate| c.offset_expr.clone().or_else(|| c.offset.map(|offset| offset.to_string()));
+        self.overflow_candidates.iter().any(|c| {
+            if c.span.start_line > 0 && candidate.span.start_line > 0 {
+                return (c.span.start_line, c.span.start_column, c.span.end_line, c.span.end_column)
+                    == (candidate.span.start_line, candidate.span.start_column, candidate.span.end_line, candidate.span.end_column);
+            }
+            // Without a span, only an access at the same known offset is the same.
+            c.location == candidate.location
+                && c.pointer_name == candidate.pointer_name
+                && offset(c).is_some()
+                && offset(c) == offset(candidate)
+        })
+    }
+
     fn analyze_source_code(&mut self) -> Result<()> {
         let content = fs::read_to_string(&self.source_file)?;
-        let _ast = parse_file(&content)?;
+        let ast = parse_file(&content)?;
         self.find_vec_allocations(&content)?;
-        self.find_pointer_operations(&content)?;
+        for candidate in find_buffer_overflows(&ast, Vec::new()) {
+            if let Some(snippet) = candidate.span.snippet(&content) {
+                self.pointer_operations.push(snippet.to_string());
+            }
+            self.overflow_candidates.push(candidate);
+        }
         Ok(())
     }
 
-    fn analyze_mir_content(&mut self, content: &str) -> Result<()> {
+    /// Analyzes the text of a MIR dump, e.g. one produced with `-Zdump-mir`.
+    pub fn analyze_mir_content(&mut self, content: &str) -> Result<()> {
         self.find_vec_allocations(content)?;
-        self.find_pointer_operations(content)?;
         self.detect_buffer_overflows(content)?;
         Ok(())
     }
 
+    /// Analyzes the texts of several MIR dumps of one crate together, e.g. the per-body
+    /// dumps of `-Zdump-mir`, so that calls between their bodies are followed.
+    pub fn analyze_mir_dumps(&mut self, dumps: &[String]) -> Result<()> {
+        let mut bodies = Vec::new();
+        for content in dumps {
+            self.find_vec_allocations(content)?;
+            bodies.extend(parse_mir(content)?);
+        }
+        self.detect_in_bodies(&bodies);
+        Ok(())
+    }
+
     fn print_analysis_results(&self) {
         if !self.overflow_candidates.is_empty() {
             println!("\n发现潜在的缓冲区溢出问题：");
@@ -104,91 +185,166 @@ impl MirAnalyzer {
         Ok(())
     }
 
-    fn find_pointer_operations(&mut self, content: &str) -> Result<()> {
-        let ptr_regex = Regex::new(r"\*ptr\.add\s*\((\d+)\)")?;
-        
-        for (i, line) in content.lines().enumerate() {
-            if line.contains("unsafe") {
-                println!("Found unsafe block");
-            }
-            
-            if let Some(caps) = ptr_regex.captures(line) {
-                if let Some(offset) = caps.get(1) {
-                    println!("Found add for pointer ptr with offset Some({}) at line {}", offset.as_str(), i + 1);
-                    self.pointer_operations.push(line.trim().to_string());
-                    let offset_value = offset.as_str().parse::<usize>().unwrap_or(0);
-                    self.overflow_candidates.push(OverflowCandidate {
-                        location: line.trim().to_string(),
-                        buffer_name: "buffer".to_string(),
-                        operation: "pointer_offset".to_string(),
-                        line: i + 1,
-                        column: 0,
-                        buffer_size: Some(10),
-                        offset: Some(offset_value),
-                    });
-                }
+    /// Finds pointer arithmetic and constant indexing in MIR text and resolves it back
+    /// to named locals.
+    ///
+    /// The dump is parsed into typed bodies first; local names, copies and borrows,
+    /// vector sizes and `as_ptr`/`as_mut_ptr` sources are collected per body, then every
+    /// `Offset`, `add`/`offset` call and constant `Index` becomes a candidate against the
+    /// buffer it was derived from. Accesses already reported from the source are skipped.
+    fn detect_buffer_overflows(&mut self, content: &str) -> Result<()> {
+        let bodies = parse_mir(content)?;
+        self.detect_in_bodies(&bodies);
+        Ok(())
+    }
+
+    /// Checks every body on its own, then executes them together.
+    fn detect_in_bodies(&mut self, bodies: &[MirBody]) {
+        for body in bodies {
+            let locals = MirLocals::collect(body);
+            self.detect_pointer_offsets(body, &locals);
+            self.detect_constant_indexing(body, &locals);
+        }
+        self.explore_paths(bodies);
+    }
+
+    /// Executes the bodies symbolically and adds the pointer offsets that go out of bounds
+    /// on some path, with the inputs leading there as assumptions.
+    fn explore_paths(&mut self, bodies: &[MirBody]) {
+        let ctx = z3::Context::new(&z3::Config::new());
+        let exploration = SymbolicExecutor::new(&ctx).explore(bodies);
+        for finding in exploration.findings {
+            let candidate = OverflowCandidate {
+                location: finding.function.clone(),
+                buffer_name: finding.buffer_name.clone(),
+                pointer_name: finding.pointer_name.clone(),
+                operation: "pointer_offset".to_string(),
+                buffer_size: usize::try_from(finding.buffer_len).ok(),
+                // The offset is relative to the start of the buffer, whatever the method.
+                offset: usize::try_from(finding.offset).ok(),
+                offset_expr: (finding.offset < 0).then(|| finding.offset.to_string()),
+                pointer_method: (finding.offset < 0).then(|| "offset".to_string()),
+                assumptions: finding.inputs.iter().map(|(name, value)| format!("{} == {}", name, value)).collect(),
+                ..mir_span_candidate(finding.span.as_ref())
+            };
+            if self.is_reported(&candidate) {
+                continue;
             }
+
+            println!(
+                "Symbolic execution reaches {} at offset {} of {} (len {}) in {} with inputs [{}]",
+                finding.method, finding.offset, finding.buffer_name, finding.buffer_len, finding.function, candidate.assumptions.join(", "),
+            );
+            self.overflow_candidates.push(candidate);
+        }
+        if exploration.truncated > 0 {
+            println!("Symbolic execution: {} paths explored, {} cut off by the loop bound", exploration.paths, exploration.truncated);
         }
-        
-        Ok(())
     }
 
-    fn detect_buffer_overflows(&mut self, content: &str) -> Result<()> {
-        let lines: Vec<&str> = content.lines().collect();
-        let mut i = 0;
-        
-        while i < lines.len() {
-            let line = lines[i].trim();
-            
-            if line.contains("unsafe") {
-                let mut block_start = i;
-                let mut block_end = i;
-                let mut brace_count = 0;
-                
-                while block_start > 0 && !lines[block_start].contains("unsafe") {
-                    block_start -= 1;
+    fn detect_pointer_offsets(&mut self, body: &MirBody, locals: &MirLocals) {
+        for block in &body.blocks {
+            let mut offsets = Vec::new();
+            for statement in &block.statements {
+                if let StatementKind::Assign(_, Rvalue::BinaryOp(BinOp::Offset, pointer, offset)) = &statement.kind {
+                    offsets.push(("Offset", pointer, offset, statement.span.as_ref()));
                 }
-                
-                while block_end < lines.len() {
-                    let current_line = lines[block_end].trim();
-                    if current_line.contains("{") {
-                        brace_count += 1;
-                    }
-                    if current_line.contains("}") {
-                        brace_count -= 1;
-                        if brace_count == 0 {
-                            break;
-                        }
-                    }
-                    block_end += 1;
+            }
+            // A `wrapping_*` offset may leave the allocation, so it is only checked where the
+            // symbolic execution sees the result accessed.
+            if let Terminator::Call { func, args, .. } = &block.terminator
+                && args.len() == 2
+                && let Some(method) = POINTER_METHODS.into_iter().find(|m| is_method(func, m))
+                && !method.starts_with("wrapping_")
+            {
+                offsets.push((method, &args[0], &args[1], block.terminator_span.as_ref()));
+            }
+
+            for (method, pointer, offset, span) in offsets {
+                let Some(operand) = pointer.place().and_then(Place::as_local) else {
+                    continue;
+                };
+                let pointer = locals.root(operand);
+                let Some(&buffer) = locals.pointer_sources.get(&pointer) else {
+                    continue;
+                };
+                let Some(value) = offset.const_value() else {
+                    continue;
+                };
+                // Negative `isize` offsets are kept as source text for the solver.
+                let (offset, offset_expr) = match usize::try_from(value) {
+                    Ok(offset) => (Some(offset), None),
+                    Err(_) => (None, Some(value.to_string())),
+                };
+                let pointer_method = match method {
+                    "Offset" | "add" => (value < 0).then_some("offset"),
+                    method => Some(method),
+                };
+                let pointer_name = locals.name(pointer);
+                // The offset counts elements of the operand's pointee, which differs from
+                // the buffer's element type after a pointer cast.
+                let buffer_element_type = body.local_ty(buffer).and_then(element_type);
+                let pointee = body.local_ty(operand).and_then(pointee_type);
+
+                let candidate = OverflowCandidate {
+                    location: body.name.clone(),
+                    buffer_name: locals.name(buffer),
+                    pointer_name: Some(pointer_name.clone()),
+                    operation: "pointer_offset".to_string(),
+                    buffer_size: locals.size(buffer),
+                    offset,
+                    offset_expr,
+                    pointer_method: pointer_method.map(str::to_string),
+                    element_type: pointee.filter(|pointee| Some(pointee) != buffer_element_type.as_ref()),
+                    buffer_element_type,
+                    ..mir_span_candidate(span)
+                };
+                if self.is_reported(&candidate) {
+                    continue;
                 }
-                
-                let unsafe_block = lines[block_start..=block_end].join("\n");
-                
-                let offset_regex = Regex::new(r"\*ptr\.add\s*\((\d+)\)")?;
-                if let Some(caps) = offset_regex.captures(&unsafe_block) {
-                    if let Some(offset) = caps.get(1) {
-                        let offset_value = offset.as_str().parse::<usize>().unwrap_or(0);
-                        println!("Detected overflow at line {} with offset {}", block_start + 1, offset_value);
-                        self.overflow_candidates.push(OverflowCandidate {
-                            location: unsafe_block.trim().to_string(),
-                            buffer_name: "buffer".to_string(),
-                            operation: "pointer_offset".to_string(),
-                            line: block_start + 1,
-                            column: 0,
-                            buffer_size: Some(10),
-                            offset: Some(offset_value),
-                        });
+
+                println!("Found {} for pointer {} with offset {} in MIR of {}", method, pointer_name, value, body.name);
+                self.overflow_candidates.push(candidate);
+            }
+        }
+    }
+
+    /// Constant indices into fixed-size arrays, e.g. `_3 = copy _1[_2]` with `_2 = const 7_usize`.
+    fn detect_constant_indexing(&mut self, body: &MirBody, locals: &MirLocals) {
+        for block in &body.blocks {
+            for statement in &block.statements {
+                let StatementKind::Assign(target, rvalue) = &statement.kind else {
+                    continue;
+                };
+                for place in std::iter::once(target).chain(rvalue_places(rvalue)) {
+                    let Some(index) = place.projection.iter().find_map(|elem| match elem {
+                        PlaceElem::Index(local) => locals.constants.get(local).copied(),
+                        _ => None,
+                    }) else {
+                        continue;
+                    };
+                    let buffer = locals.root(place.local);
+                    let Some(size) = body.local_ty(buffer).and_then(array_len) else {
+                        continue;
+                    };
+
+                    let candidate = OverflowCandidate {
+                        location: body.name.clone(),
+                        buffer_name: locals.name(buffer),
+                        operation: "index".to_string(),
+                        buffer_size: Some(size),
+                        offset: Some(index),
+                        ..mir_span_candidate(statement.span.as_ref())
+                    };
+                    if self.is_reported(&candidate) {
+                        continue;
                     }
+
+                    println!("Found index {} into {} in MIR of {}", index, candidate.buffer_name, body.name);
+                    self.overflow_candidates.push(candidate);
                 }
-                
-                i = block_end + 1;
-            } else {
-                i += 1;
             }
         }
-    
-        Ok(())
     }
 
     pub fn get_fixes(&self) -> Vec<OverflowCandidate> {
@@ -202,4 +358,491 @@ impl MirAnalyzer {
     pub fn get_solver(&mut self) -> &mut BufferSolver<'static> {
         self.solver.as_mut().expect("Solver not initialized")
     }
-}
\ No newline at end of file
+}
+
+/// Facts about the locals of one parsed MIR body, keyed by local index (`_N`).
+#[derive(Debug, Default)]
+struct MirLocals {
+    names: HashMap<Local, String>,
+    aliases: HashMap<Local, Local>,
+    sizes: HashMap<Local, usize>,
+    constants: HashMap<Local, usize>,
+    pointer_sources: HashMap<Local, Local>,
+}
+
+impl MirLocals {
+    fn collect(body: &MirBody) -> Self {
+        let mut locals = MirLocals::default();
+        let mut pending_ptrs = Vec::new();
+        let mut pending_into_vec = Vec::new();
+
+        for var in &body.debug_vars {
+            if let Some(local) = var.value.place().and_then(Place::as_local) {
+                locals.names.insert(local, var.name.clone());
+            }
+        }
+
+        for block in &body.blocks {
+            for statement in &block.statements {
+                let StatementKind::Assign(target, rvalue) = &statement.kind else {
+                    continue;
+                };
+                let Some(target) = target.as_local() else {
+                    continue;
+                };
+                let source = match rvalue {
+                    Rvalue::Use(Operand::Constant(constant)) => {
+                        if let Some(value) = constant.value.and_then(|v| usize::try_from(v).ok()) {
+                            locals.constants.insert(target, value);
+                        }
+                        None
+                    }
+                    Rvalue::Use(operand) | Rvalue::Cast { operand, .. } => operand.place(),
+                    Rvalue::Ref { place, .. } | Rvalue::RawPtr { place, .. } => Some(place),
+                    _ => None,
+                };
+                if let Some(source) = source.filter(|p| p.projection.iter().all(|e| *e == PlaceElem::Deref)) {
+                    locals.aliases.insert(target, source.local);
+                }
+            }
+
+            let Terminator::Call { destination, func, args, .. } = &block.terminator else {
+                continue;
+            };
+            let (Some(target), Some(first)) = (destination.as_local(), args.first()) else {
+                continue;
+            };
+            if is_method(func, "from_elem") {
+                if let Some(size) = args.get(1).and_then(Operand::const_value).and_then(|v| usize::try_from(v).ok()) {
+                    locals.sizes.insert(target, size);
+                }
+            } else if let Some(source) = first.place().map(|p| p.local) {
+                if is_method(func, "into_vec") {
+                    pending_into_vec.push((target, source));
+                } else if is_method(func, "as_ptr") || is_method(func, "as_mut_ptr") {
+                    pending_ptrs.push((target, source));
+                }
+            }
+        }
+
+        // Aliases may be defined after their use in block order, so resolve them last.
+        for (target, source) in pending_into_vec {
+            if let Some(size) = body.local_ty(locals.root(source)).and_then(array_len) {
+                locals.sizes.insert(target, size);
+            }
+        }
+        for (target, source) in pending_ptrs {
+            let buffer = locals.root(source);
+            locals.pointer_sources.insert(target, buffer);
+        }
+
+        locals
+    }
+
+    /// Follows copies, moves, borrows and casts back to the local they originate from.
+    fn root(&self, mut local: Local) -> Local {
+        let mut steps = 0;
+        while let Some(&next) = self.aliases.get(&local) {
+            if next == local || steps > self.aliases.len() {
+                break;
+            }
+            local = next;
+            steps += 1;
+        }
+        local
+    }
+
+    /// Name of a local, borrowed from a named copy of it when the local itself is a temporary.
+    fn name(&self, local: Local) -> String {
+        if let Some(name) = self.names.get(&local) {
+            return name.clone();
+        }
+        self.names.iter()
+            .filter(|(other, _)| self.root(**other) == local)
+            .min_by_key(|(other, _)| **other)
+            .map(|(_, name)| name.clone())
+            .unwrap_or_else(|| format!("_{}", local))
+    }
+
+    fn size(&self, local: Local) -> Option<usize> {
+        self.sizes.get(&local).copied()
+    }
+}
+
+/// Crate name rustc derives for a single source file, also the prefix of its dump files.
+fn crate_name(source_file: &Path) -> String {
+    source_file.file_stem()
+        .map(|stem| stem.to_string_lossy().replace('-', "_"))
+        .unwrap_or_else(|| "main".to_string())
+}
+
+/// Edition of the package `source_file` belongs to, from the nearest `Cargo.toml` above
+/// it. A manifest without an `edition` key means 2015, as for Cargo; a file outside any
+/// package is compiled as 2021.
+pub(crate) fn crate_edition(source_file: &Path) -> String {
+    let edition = Regex::new(r#"(?m)^\s*edition\s*=\s*"(\d{4})""#).unwrap();
+    let manifest = source_file.ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file());
+    match manifest.and_then(|manifest| fs::read_to_string(manifest).ok()) {
+        Some(manifest) => edition.captures(&manifest)
+            .map(|captures| captures[1].to_string())
+            .unwrap_or_else(|| "2015".to_string()),
+        None => "2021".to_string(),
+    }
+}
+
+/// Runs `rustc -Zdump-mir=all` on the source file in a temporary directory and returns
+/// the contents of the selected dumps.
+#[cfg(not(feature = "with-rustc"))]
+fn dump_mir(source_file: &Path, crate_name: &str) -> Result<Vec<String>> {
+    let dir = tempfile::tempdir()?;
+    let dump_dir = dir.path().join("mir");
+    let output = Command::new("rustc")
+        .arg(source_file)
+        .args(["--crate-type=lib", "-Awarnings", "--emit=metadata", "--crate-name", crate_name])
+        .arg(format!("--edition={}", crate_edition(source_file)))
+        .arg("-o")
+        .arg(dir.path().join(format!("lib{}.rmeta", crate_name)))
+        .arg("-Zdump-mir=all")
+        .arg(format!("-Zdump-mir-dir={}", dump_dir.display()))
+        .output()?;
+    if !output.status.success() {
+        bail!("rustc failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let dumps = select_dumps(&dump_dir, crate_name)?;
+    if dumps.is_empty() {
+        bail!("rustc produced no MIR dumps for {}", source_file.display());
+    }
+    dumps.iter().map(|path| Ok(fs::read_to_string(path)?)).collect()
+}
+
+/// Picks one dump per body of `crate_name` from a `-Zdump-mir` directory.
+///
+/// Dump names look like `<crate>.<item>.<pass>.<phase>.mir`. The `nll` dump is preferred:
+/// it still has the calls to `add`/`offset` (nothing is inlined yet), user variable names
+/// and span comments. Bodies without one use their last `after` dump. A whole-crate
+/// `<crate>.mir` from `--emit=mir` is used only when there are no per-body dumps.
+fn select_dumps(dir: &Path, crate_name: &str) -> Result<Vec<PathBuf>> {
+    let dump_regex = Regex::new(&format!(
+        r"^{}\.(.+)\.(-------|\d+-\d+-\d+)\.([^.]+)\.(before|after|\d+)\.mir$",
+        regex::escape(crate_name)
+    ))?;
+    let mut selected: HashMap<String, ((u8, String), PathBuf)> = HashMap::new();
+    let mut whole_crate = None;
+
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy();
+        if file_name == format!("{}.mir", crate_name) {
+            whole_crate = Some(entry.path().to_path_buf());
+            continue;
+        }
+        let Some(caps) = dump_regex.captures(&file_name) else {
+            continue;
+        };
+        let rank = match (&caps[3], &caps[4]) {
+            ("nll", _) => (2, String::new()),
+            (_, "after") => (1, caps[2].to_string()),
+            _ => continue,
+        };
+        let current = selected.entry(caps[1].to_string()).or_insert_with(|| (rank.clone(), entry.path().to_path_buf()));
+        if rank > current.0 {
+            *current = (rank, entry.path().to_path_buf());
+        }
+    }
+
+    let mut dumps: Vec<PathBuf> = selected.into_values().map(|(_, path)| path).collect();
+    dumps.sort();
+    if dumps.is_empty() {
+        dumps.extend(whole_crate);
+    }
+    Ok(dumps)
+}
+
+/// Whether a MIR callee path names `method`, e.g. `std::ptr::mut_ptr::<impl *mut u8>::add`
+/// or `from_elem::<u8>`.
+pub(crate) fn is_method(func: &str, method: &str) -> bool {
+    let func = callee_path(func);
+    func == method || func.ends_with(&format!("::{}", method))
+}
+
+/// A MIR callee path without its trailing generic arguments, e.g.
+/// `slice::<impl [i32]>::into_vec` for `slice::<impl [i32]>::into_vec::<std::alloc::Global>`.
+pub(crate) fn callee_path(func: &str) -> &str {
+    let func = func.trim_start_matches("const ");
+    if !func.ends_with('>') {
+        return func;
+    }
+    let mut depth = 0;
+    for (index, c) in func.char_indices().rev() {
+        match c {
+            '>' => depth += 1,
+            '<' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return func[..index].strip_suffix("::").unwrap_or(func);
+        }
+    }
+    func
+}
+
+/// Length of a fixed-size array type such as `[i32; 8]`.
+pub(crate) fn array_len(ty: &str) -> Option<usize> {
+    let inner = ty.trim().strip_prefix('[')?.strip_suffix(']')?;
+    inner.rsplit_once("; ")?.1.trim().parse().ok()
+}
+
+/// Element type of a `Vec<T>`, `[T; N]` or `[T]` type as printed in MIR.
+fn element_type(ty: &str) -> Option<String> {
+    let ty = ty.trim();
+    if let Some(inner) = ty.strip_prefix('[').and_then(|inner| inner.strip_suffix(']')) {
+        let elem = inner.rsplit_once("; ").map_or(inner, |(elem, _)| elem);
+        return Some(elem.trim().to_string());
+    }
+    let inner = ty.strip_prefix("std::vec::Vec<").or_else(|| ty.strip_prefix("Vec<"))?.strip_suffix('>')?;
+    let inner = [", Global", ", std::alloc::Global"].iter().find_map(|alloc| inner.strip_suffix(alloc)).unwrap_or(inner);
+    Some(inner.trim().to_string())
+}
+
+/// `T` of a `*mut T` or `*const T` type.
+fn pointee_type(ty: &str) -> Option<String> {
+    let ty = ty.trim();
+    let pointee = ty.strip_prefix("*mut ").or_else(|| ty.strip_prefix("*const "))?;
+    Some(pointee.trim().to_string())
+}
+
+fn rvalue_places(rvalue: &Rvalue) -> Vec<&Place> {
+    match rvalue {
+        Rvalue::Use(operand) | Rvalue::Cast { operand, .. } | Rvalue::UnaryOp(_, operand) | Rvalue::Repeat(operand, _) => {
+            operand.place().into_iter().collect()
+        }
+        Rvalue::Ref { place, .. } | Rvalue::RawPtr { place, .. } | Rvalue::Len(place) | Rvalue::Discriminant(place) => vec![place],
+        Rvalue::BinaryOp(_, left, right) => left.place().into_iter().chain(right.place()).collect(),
+        Rvalue::Aggregate(_, operands) => operands.iter().filter_map(Operand::place).collect(),
+        Rvalue::NullaryOp(..) | Rvalue::Other(_) => Vec::new(),
+    }
+}
+
+/// Candidate skeleton positioned at a span comment from the dump, when there is one.
+fn mir_span_candidate(span: Option<&MirSpan>) -> OverflowCandidate {
+    let Some(span) = span else {
+        return OverflowCandidate::default();
+    };
+    OverflowCandidate {
+        line: span.start_line,
+        column: span.start_column.saturating_sub(1),
+        span: SourceSpan {
+            start_line: span.start_line,
+            start_column: span.start_column.saturating_sub(1),
+            end_line: span.end_line,
+            end_column: span.end_column.saturating_sub(1),
+            byte_range: 0..0,
+        },
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_dumps_for_source_file() {
+        let dumps = select_dumps(Path::new("mir_dump"), "buffer_overflow_test").unwrap();
+        let names: Vec<String> = dumps.iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert!(names.contains(&"buffer_overflow_test.test_buffer_overflow.-------.nll.0.mir".to_string()));
+        assert!(names.iter().all(|name| name.starts_with("buffer_overflow_test.")));
+        assert_eq!(names.iter().filter(|name| name.contains(".test_buffer_overflow.")).count(), 1);
+
+        let dumps = select_dumps(Path::new("mir_output"), "safe_buffer").unwrap();
+        assert_eq!(dumps, vec![PathBuf::from("mir_output/safe_buffer.mir")]);
+    }
+
+    #[test]
+    fn test_reported_accesses() {
+        let mut analyzer = MirAnalyzer::new(PathBuf::from("mir_output"));
+        let at = |start_column, end_column, offset_expr: &str| OverflowCandidate {
+            location: "read".to_string(),
+            pointer_name: Some("p".to_string()),
+            offset_expr: Some(offset_expr.to_string()),
+            span: SourceSpan { start_line: 3, start_column, end_line: 3, end_column, byte_range: 0..0 },
+            ..Default::default()
+        };
+        analyzer.overflow_candidates.push(at(4, 12, "i"));
+        assert!(analyzer.is_reported(&at(4, 12, "i")));
+        // Another access through `p` on the same line, or one the first only starts.
+        assert!(!analyzer.is_reported(&at(16, 24, "j")));
+        assert!(!analyzer.is_reported(&at(4, 20, "i")));
+
+        let unplaced = |offset_expr: Option<&str>| OverflowCandidate {
+            span: SourceSpan::default(),
+            offset_expr: offset_expr.map(str::to_string),
+            ..at(0, 0, "")
+        };
+        assert!(analyzer.is_reported(&unplaced(Some("i"))));
+        assert!(!analyzer.is_reported(&unplaced(Some("j"))));
+        // Symbolic accesses without any offset are never merged.
+        analyzer.overflow_candidates.push(unplaced(None));
+        assert!(!analyzer.is_reported(&unplaced(None)));
+    }
+
+    #[test]
+    fn test_crate_edition() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir(&src).unwrap();
+        let file = src.join("main.rs");
+        assert_eq!(crate_edition(&file), "2021");
+        fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"old\"\n").unwrap();
+        assert_eq!(crate_edition(&file), "2015");
+        fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"new\"\nedition = \"2018\"\n").unwrap();
+        assert_eq!(crate_edition(&file), "2018");
+        assert_eq!(crate_edition(Path::new("src/mir_analyzer.rs")), "2024");
+    }
+
+    #[cfg(not(feature = "with-rustc"))]
+    #[test]
+    fn test_dump_mir_for_source_file() {
+        let dumps = dump_mir(Path::new("examples/buffer_overflow_test.rs"), "buffer_overflow_test").unwrap();
+        let mut analyzer = MirAnalyzer::new(PathBuf::from("output"));
+        analyzer.analyze_mir_dumps(&dumps).unwrap();
+
+        let found = analyzer.get_fixes();
+        let ptr = found.iter().find(|c| c.pointer_name.as_deref() == Some("ptr")).unwrap();
+        assert_eq!((ptr.buffer_size, ptr.offset), (Some(5), Some(8)));
+        assert_eq!(ptr.line, 11);
+    }
+
+    #[test]
+    fn test_mir_pointer_offsets_use_real_names() {
+        let content = fs::read_to_string("mir_output/buffer_overflow_test.mir").unwrap();
+        let mut analyzer = MirAnalyzer::new(PathBuf::from("output"));
+        analyzer.detect_buffer_overflows(&content).unwrap();
+
+        let found = analyzer.get_fixes();
+        let ptr = found.iter().find(|c| c.pointer_name.as_deref() == Some("ptr")).unwrap();
+        assert_eq!(ptr.buffer_name, "buffer");
+        assert_eq!(ptr.buffer_size, Some(5));
+        assert_eq!(ptr.offset, Some(8));
+        assert_eq!((ptr.buffer_element_type.as_deref(), ptr.element_type.as_deref()), (Some("u8"), None));
+
+        let another = found.iter().find(|c| c.pointer_name.as_deref() == Some("another_ptr")).unwrap();
+        assert_eq!(another.buffer_name, "small_buffer");
+        assert_eq!(another.buffer_size, Some(3));
+        assert_eq!(another.offset, Some(5));
+    }
+
+    #[test]
+    fn test_symbolic_offsets_carry_inputs() {
+        let content = r#"
+fn read_at(_1: &[u8], _2: usize) -> u8 {
+    debug buf => _1;
+    debug n => _2;
+    let mut _0: u8;
+    let mut _3: *const u8;
+    let mut _4: *const u8;
+
+    bb0: {
+        _3 = core::slice::<impl [u8]>::as_ptr(copy _1) -> [return: bb1, unwind continue];
+    }
+
+    bb1: {
+        _4 = Offset(copy _3, copy _2);
+        _0 = copy (*_4);
+        return;
+    }
+}
+"#;
+        let mut analyzer = MirAnalyzer::new(PathBuf::from("output"));
+        analyzer.detect_buffer_overflows(content).unwrap();
+
+        let found = analyzer.get_fixes();
+        let access = found.iter().find(|c| c.location == "read_at").unwrap();
+        assert_eq!(access.buffer_name, "buf");
+        assert!(access.offset.unwrap() >= access.buffer_size.unwrap());
+        assert_eq!(access.assumptions.len(), 2);
+        assert!(access.assumptions.iter().any(|a| a.starts_with("n == ")));
+    }
+
+    #[test]
+    fn test_calls_between_dumps() {
+        let dir = tempfile::tempdir().unwrap();
+        let dumps = [
+            ("caller", r#"
+fn caller() -> u8 {
+    debug buffer => _1;
+    debug ptr => _2;
+    let mut _0: u8;
+    let _1: std::vec::Vec<u8>;
+    let _2: *const u8;
+    let mut _3: &std::vec::Vec<u8>;
+    let mut _4: usize;
+    let mut _5: *const u8;
+    let mut _6: usize;
+    let mut _7: *const u8;
+
+    bb0: {
+        _1 = from_elem::<u8>(const 0_u8, const 4_usize) -> [return: bb1, unwind continue];
+    }
+
+    bb1: {
+        _3 = &_1;
+        _2 = Vec::<u8>::as_ptr(move _3) -> [return: bb2, unwind continue];
+    }
+
+    bb2: {
+        _4 = near() -> [return: bb3, unwind continue];
+    }
+
+    bb3: {
+        _5 = Offset(copy _2, copy _4);
+        _6 = far() -> [return: bb4, unwind continue];
+    }
+
+    bb4: {
+        _7 = Offset(copy _2, copy _6);
+        _0 = copy (*_7);
+        return;
+    }
+}
+"#),
+            ("near", "fn near() -> usize {\n    let mut _0: usize;\n\n    bb0: {\n        _0 = const 2_usize;\n        return;\n    }\n}\n"),
+            ("far", "fn far() -> usize {\n    let mut _0: usize;\n\n    bb0: {\n        _0 = const 6_usize;\n        return;\n    }\n}\n"),
+        ];
+        for (function, content) in dumps {
+            fs::write(dir.path().join(format!("calls.{}.-------.nll.0.mir", function)), content).unwrap();
+        }
+        let mut analyzer = MirAnalyzer::new(dir.path().to_path_buf());
+        analyzer.source_file = PathBuf::from("calls.rs");
+        analyzer.analyze_existing_dumps().unwrap();
+
+        // The offsets come from the other dumps' bodies: 2 is in bounds, 6 is not.
+        let found = analyzer.get_fixes();
+        let offsets: Vec<_> = found.iter().map(|c| (c.location.as_str(), c.offset, c.buffer_size)).collect();
+        assert_eq!(offsets, vec![("caller", Some(6), Some(4))]);
+    }
+
+    #[test]
+    fn test_element_and_pointee_types() {
+        assert_eq!(element_type("std::vec::Vec<u8>").as_deref(), Some("u8"));
+        assert_eq!(element_type("Vec<(u8, u16), Global>").as_deref(), Some("(u8, u16)"));
+        assert_eq!(element_type("[u32; 4]").as_deref(), Some("u32"));
+        assert_eq!(pointee_type("*const [u8; 2]").as_deref(), Some("[u8; 2]"));
+        assert_eq!(pointee_type("&mut u8"), None);
+    }
+
+    #[test]
+    fn test_callee_paths() {
+        assert_eq!(callee_path("from_elem::<u8>"), "from_elem");
+        assert_eq!(callee_path("slice::<impl [i32]>::into_vec::<std::alloc::Global>"), "slice::<impl [i32]>::into_vec");
+        assert_eq!(callee_path("<Vec<i32> as Deref>::deref"), "<Vec<i32> as Deref>::deref");
+        assert!(is_method("std::ptr::mut_ptr::<impl *mut u8>::add", "add"));
+        assert!(is_method("slice::<impl [i32]>::into_vec::<std::alloc::Global>", "into_vec"));
+    }
+}
@@ -0,0 +1,422 @@
+//! MIR extraction through `rustc_driver`, available with the `with-rustc` feature.
+//!
+//! The source file is compiled as a library crate and every local function body is
+//! inspected directly, instead of going through textual MIR dumps.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+use rustc_driver::{Callbacks, Compilation};
+use rustc_hir::def::DefKind;
+use rustc_interface::interface::Compiler;
+use rustc_middle::mir::{
+    BinOp, Body, Local, Operand, Place, ProjectionElem, Rvalue, StatementKind, TerminatorKind,
+    VarDebugInfoContents,
+};
+use rustc_middle::mir::pretty::MirWriter;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::def_id::LocalDefId;
+use rustc_span::Span;
+
+use crate::analyzer::{OverflowCandidate, SourceSpan, POINTER_METHODS};
+use crate::mir_analyzer::crate_edition;
+use crate::mir_parser::{parse_mir, MirBody};
+
+/// Which MIR body is analyzed for each function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirSource {
+    /// `mir_built`: MIR straight out of lowering, before borrowck and optimizations.
+    Built,
+    /// `optimized_mir`: the body handed to codegen.
+    Optimized,
+}
+
+/// Compiles `source_file` with `rustc_driver` and returns the candidates found in its MIR.
+pub fn collect_candidates(source_file: &Path, source: MirSource) -> Result<Vec<OverflowCandidate>> {
+    let args = rustc_args(source_file, &[])?;
+    let mut callbacks = MirCallbacks { source, candidates: Vec::new() };
+    rustc_driver::catch_fatal_errors(|| rustc_driver::run_compiler(&args, &mut callbacks))
+        .map_err(|_| anyhow!("rustc failed to compile {}", source_file.display()))?;
+
+    Ok(callbacks.candidates)
+}
+
+/// Compiles `source_file` with `rustc_driver` and returns the optimized MIR of its
+/// functions, printed with source spans and parsed like a dump.
+pub fn collect_bodies(source_file: &Path) -> Result<Vec<MirBody>> {
+    let args = rustc_args(source_file, &["-Zmir-include-spans=yes"])?;
+    let mut callbacks = MirPrinter { text: Vec::new() };
+    rustc_driver::catch_fatal_errors(|| rustc_driver::run_compiler(&args, &mut callbacks))
+        .map_err(|_| anyhow!("rustc failed to compile {}", source_file.display()))?;
+
+    parse_mir(&String::from_utf8(callbacks.text)?)
+}
+
+fn rustc_args(source_file: &Path, extra: &[&str]) -> Result<Vec<String>> {
+    let output = Command::new("rustc").args(["--print", "sysroot"]).output()?;
+    let sysroot = String::from_utf8(output.stdout)?.trim().to_string();
+
+    let mut args = vec![
+        "rustc".to_string(),
+        source_file.display().to_string(),
+        "--crate-type=lib".to_string(),
+        format!("--edition={}", crate_edition(source_file)),
+        "--sysroot".to_string(),
+        sysroot,
+        "-Awarnings".to_string(),
+    ];
+    args.extend(extra.iter().map(|arg| arg.to_string()));
+    Ok(args)
+}
+
+struct MirPrinter {
+    text: Vec<u8>,
+}
+
+impl Callbacks for MirPrinter {
+    fn after_analysis<'tcx>(&mut self, _compiler: &Compiler, tcx: TyCtxt<'tcx>) -> Compilation {
+        let writer = MirWriter::new(tcx);
+        for def_id in local_functions(tcx) {
+            let body = tcx.optimized_mir(def_id.to_def_id());
+            if writer.write_mir_fn(body, &mut self.text).is_err() {
+                println!("无法输出 {} 的 MIR", tcx.def_path_str(def_id));
+            }
+        }
+        Compilation::Stop
+    }
+}
+
+struct MirCallbacks {
+    source: MirSource,
+    candidates: Vec<OverflowCandidate>,
+}
+
+impl Callbacks for MirCallbacks {
+    fn after_expansion<'tcx>(&mut self, _compiler: &Compiler, tcx: TyCtxt<'tcx>) -> Compilation {
+        if self.source != MirSource::Built {
+            return Compilation::Continue;
+        }
+        for def_id in local_functions(tcx) {
+            let body = tcx.mir_built(def_id).borrow();
+            self.candidates.extend(BodyScanner::new(tcx, def_id, &body).scan());
+        }
+        Compilation::Stop
+    }
+
+    fn after_analysis<'tcx>(&mut self, _compiler: &Compiler, tcx: TyCtxt<'tcx>) -> Compilation {
+        for def_id in local_functions(tcx) {
+            let body = tcx.optimized_mir(def_id.to_def_id());
+            self.candidates.extend(BodyScanner::new(tcx, def_id, body).scan());
+        }
+        Compilation::Stop
+    }
+}
+
+fn local_functions(tcx: TyCtxt<'_>) -> Vec<LocalDefId> {
+    tcx.hir_body_owners()
+        .filter(|def_id| matches!(tcx.def_kind(*def_id), DefKind::Fn | DefKind::AssocFn | DefKind::Closure))
+        .collect()
+}
+
+/// Walks one MIR body, tracking where pointers come from and reporting offsets and indexing.
+struct BodyScanner<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    body: &'a Body<'tcx>,
+    function: String,
+    names: HashMap<Local, String>,
+    aliases: HashMap<Local, Local>,
+    consts: HashMap<Local, u64>,
+    sizes: HashMap<Local, u64>,
+    pointer_sources: HashMap<Local, Local>,
+}
+
+impl<'a, 'tcx> BodyScanner<'a, 'tcx> {
+    fn new(tcx: TyCtxt<'tcx>, def_id: LocalDefId, body: &'a Body<'tcx>) -> Self {
+        BodyScanner {
+            tcx,
+            body,
+            function: tcx.def_path_str(def_id.to_def_id()),
+            names: HashMap::new(),
+            aliases: HashMap::new(),
+            consts: HashMap::new(),
+            sizes: HashMap::new(),
+            pointer_sources: HashMap::new(),
+        }
+    }
+
+    fn scan(mut self) -> Vec<OverflowCandidate> {
+        self.collect_facts();
+
+        let mut candidates = Vec::new();
+        for block in self.body.basic_blocks.iter() {
+            for statement in &block.statements {
+                let StatementKind::Assign(assign) = &statement.kind else {
+                    continue;
+                };
+                let (place, rvalue) = &**assign;
+                if let Rvalue::BinaryOp(BinOp::Offset, operands) = rvalue {
+                    let (pointer, offset) = &**operands;
+                    candidates.extend(self.pointer_offset("add", pointer, offset, statement.source_info.span));
+                }
+                candidates.extend(self.index_projection(place, statement.source_info.span));
+            }
+
+            let terminator = block.terminator();
+            let TerminatorKind::Call { func, args, .. } = &terminator.kind else {
+                continue;
+            };
+            let Some((callee, _)) = func.const_fn_def() else {
+                continue;
+            };
+            let span = terminator.source_info.span;
+            match self.tcx.item_name(callee).as_str() {
+                // A `wrapping_*` offset may leave the allocation; the accesses through it are
+                // checked by the source analysis.
+                method if POINTER_METHODS.contains(&method) && !method.starts_with("wrapping_") && args.len() == 2 => {
+                    candidates.extend(self.pointer_offset(method, &args[0].node, &args[1].node, span));
+                }
+                "index" | "index_mut" if args.len() == 2 => {
+                    let Some(buffer) = self.operand_local(&args[0].node) else {
+                        continue;
+                    };
+                    let offset = self.operand_const(&args[1].node);
+                    if self.sizes.contains_key(&buffer) && offset.is_some() {
+                        candidates.push(self.candidate(buffer, None, "index", offset, span));
+                    }
+                }
+                _ => {}
+            }
+        }
+        candidates
+    }
+
+    /// Records debug names, copies, constants, allocation sizes and pointer sources.
+    fn collect_facts(&mut self) {
+        for info in &self.body.var_debug_info {
+            if let VarDebugInfoContents::Place(place) = &info.value {
+                if let Some(local) = place.as_local() {
+                    self.names.insert(local, info.name.to_string());
+                }
+            }
+        }
+
+        for (local, decl) in self.body.local_decls.iter_enumerated() {
+            if let ty::Array(_, len) = decl.ty.kind() {
+                if let Some(len) = len.try_to_target_usize(self.tcx) {
+                    self.sizes.insert(local, len);
+                }
+            }
+        }
+
+        for block in self.body.basic_blocks.iter() {
+            for statement in &block.statements {
+                let StatementKind::Assign(assign) = &statement.kind else {
+                    continue;
+                };
+                let (place, rvalue) = &**assign;
+                let Some(target) = place.as_local() else {
+                    continue;
+                };
+                match rvalue {
+                    Rvalue::Use(Operand::Constant(constant), _) => {
+                        let typing_env = self.body.typing_env(self.tcx);
+                        if let Some(value) = constant.const_.try_eval_target_usize(self.tcx, typing_env) {
+                            self.consts.insert(target, value);
+                        }
+                    }
+                    Rvalue::Use(Operand::Copy(source) | Operand::Move(source), _)
+                    | Rvalue::Cast(_, Operand::Copy(source) | Operand::Move(source), _)
+                    | Rvalue::Ref(_, _, source)
+                    | Rvalue::RawPtr(_, source) => {
+                        if source.projection.iter().all(|elem| matches!(elem, ProjectionElem::Deref)) {
+                            self.aliases.insert(target, source.local);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let TerminatorKind::Call { func, args, destination, .. } = &block.terminator().kind else {
+                continue;
+            };
+            let (Some((callee, _)), Some(target)) = (func.const_fn_def(), destination.as_local()) else {
+                continue;
+            };
+            match self.tcx.item_name(callee).as_str() {
+                "from_elem" if args.len() == 2 => {
+                    if let Some(len) = self.operand_const(&args[1].node) {
+                        self.sizes.insert(target, len);
+                    }
+                }
+                "as_ptr" | "as_mut_ptr" if args.len() == 1 => {
+                    if let Some(buffer) = self.operand_local(&args[0].node) {
+                        self.pointer_sources.insert(target, buffer);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn pointer_offset(&self, method: &str, pointer: &Operand<'tcx>, offset: &Operand<'tcx>, span: Span) -> Option<OverflowCandidate> {
+        let operand = pointer.place()?.as_local()?;
+        let pointer = self.root(operand);
+        let buffer = self.root(*self.pointer_sources.get(&pointer)?);
+        // Negative `isize` offsets show up as huge unsigned values and are passed on as text.
+        let value = self.operand_const(offset);
+        let negative = value.filter(|value| *value > i64::MAX as u64).map(|value| value as i64);
+        let offset = value.filter(|_| negative.is_none());
+        let pointer_method = match method {
+            "add" => negative.map(|_| "offset"),
+            method => Some(method),
+        };
+
+        // The offset counts elements of the operand's pointee, which differs from the
+        // buffer's element type after a pointer cast.
+        let buffer_element_type = self.element_type(buffer).map(|ty| ty.to_string());
+        let pointee = self.body.local_decls[operand].ty.builtin_deref(true).map(|ty| ty.to_string());
+        Some(OverflowCandidate {
+            offset_expr: negative.map(|value| value.to_string()),
+            pointer_method: pointer_method.map(str::to_string),
+            element_type: pointee.filter(|pointee| Some(pointee) != buffer_element_type.as_ref()),
+            buffer_element_type,
+            ..self.candidate(buffer, Some(pointer), "pointer_offset", offset, span)
+        })
+    }
+
+    /// Element type of a local holding a `Vec<T>`, `[T; N]` or `[T]`.
+    fn element_type(&self, local: Local) -> Option<ty::Ty<'tcx>> {
+        match self.body.local_decls[local].ty.kind() {
+            ty::Array(elem, _) | ty::Slice(elem) => Some(*elem),
+            ty::Adt(def, args) if self.tcx.is_diagnostic_item(rustc_span::sym::Vec, def.did()) => Some(args.type_at(0)),
+            _ => None,
+        }
+    }
+
+    /// Reports `array[i]` places whose index is a known constant.
+    fn index_projection(&self, place: &Place<'tcx>, span: Span) -> Option<OverflowCandidate> {
+        let buffer = self.root(place.local);
+        self.sizes.get(&buffer)?;
+        place.projection.iter().find_map(|elem| match elem {
+            ProjectionElem::Index(index) => {
+                let offset = self.consts.get(&self.root(index)).copied()?;
+                Some(self.candidate(buffer, None, "index", Some(offset), span))
+            }
+            ProjectionElem::ConstantIndex { offset, from_end: false, .. } => {
+                Some(self.candidate(buffer, None, "index", Some(offset), span))
+            }
+            _ => None,
+        })
+    }
+
+    fn candidate(&self, buffer: Local, pointer: Option<Local>, operation: &str, offset: Option<u64>, span: Span) -> OverflowCandidate {
+        let span = source_span(self.tcx, span);
+        OverflowCandidate {
+            location: self.function.clone(),
+            buffer_name: self.name(buffer),
+            pointer_name: pointer.map(|pointer| self.name(pointer)),
+            operation: operation.to_string(),
+            line: span.start_line,
+            column: span.start_column,
+            buffer_size: self.sizes.get(&buffer).map(|size| *size as usize),
+            buffer_size_expr: None,
+            offset: offset.map(|offset| offset as usize),
+            span,
+            ..Default::default()
+        }
+    }
+
+    fn operand_local(&self, operand: &Operand<'tcx>) -> Option<Local> {
+        match operand {
+            Operand::Copy(place) | Operand::Move(place) => place.as_local().map(|local| self.root(local)),
+            _ => None,
+        }
+    }
+
+    fn operand_const(&self, operand: &Operand<'tcx>) -> Option<u64> {
+        match operand {
+            Operand::Constant(constant) => {
+                constant.const_.try_eval_target_usize(self.tcx, self.body.typing_env(self.tcx))
+            }
+            Operand::Copy(place) | Operand::Move(place) => {
+                self.consts.get(&self.root(place.as_local()?)).copied()
+            }
+            _ => None,
+        }
+    }
+
+    /// Follows copies, moves, borrows and casts back to the local they originate from.
+    fn root(&self, mut local: Local) -> Local {
+        let mut steps = 0;
+        while let Some(&next) = self.aliases.get(&local) {
+            if next == local || steps > self.aliases.len() {
+                break;
+            }
+            local = next;
+            steps += 1;
+        }
+        local
+    }
+
+    fn name(&self, local: Local) -> String {
+        if let Some(name) = self.names.get(&local) {
+            return name.clone();
+        }
+        self.names.iter()
+            .filter(|(other, _)| self.root(**other) == local)
+            .min_by_key(|(other, _)| **other)
+            .map(|(_, name)| name.clone())
+            .unwrap_or_else(|| format!("{:?}", local))
+    }
+}
+
+/// Maps a rustc span back to the user's source, stepping out of macro expansions.
+fn source_span(tcx: TyCtxt<'_>, span: Span) -> SourceSpan {
+    let span = span.source_callsite();
+    let source_map = tcx.sess.source_map();
+    let start = source_map.lookup_char_pos(span.lo());
+    let end = source_map.lookup_char_pos(span.hi());
+    let start_byte = source_map.lookup_byte_offset(span.lo()).pos.0 as usize;
+    let end_byte = source_map.lookup_byte_offset(span.hi()).pos.0 as usize;
+    SourceSpan {
+        start_line: start.line,
+        start_column: start.col.0,
+        end_line: end.line,
+        end_column: end.col.0,
+        byte_range: start_byte..end_byte,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_candidates_from_optimized_mir() {
+        let found = collect_candidates(Path::new("examples/buffer_overflow_test.rs"), MirSource::Optimized).unwrap();
+        let ptr = found.iter().find(|c| c.pointer_name.as_deref() == Some("ptr")).unwrap();
+        assert_eq!(ptr.buffer_name, "buffer");
+        assert_eq!(ptr.buffer_size, Some(5));
+        assert_eq!(ptr.offset, Some(8));
+        assert_eq!((ptr.line, ptr.column), (11, 9));
+
+        let another = found.iter().find(|c| c.pointer_name.as_deref() == Some("another_ptr")).unwrap();
+        assert_eq!(another.buffer_name, "small_buffer");
+        assert_eq!(another.buffer_size, Some(3));
+        assert_eq!(another.offset, Some(5));
+    }
+
+    #[test]
+    fn test_symbolic_execution_of_compiled_mir() {
+        let bodies = collect_bodies(Path::new("examples/buffer_overflow_test.rs")).unwrap();
+        assert!(bodies.iter().any(|body| body.name == "test_buffer_overflow"));
+
+        let ctx = z3::Context::new(&z3::Config::new());
+        let found = crate::mir_symbolic::SymbolicExecutor::new(&ctx).explore(&bodies).findings;
+        let ptr = found.iter().find(|f| f.pointer_name.as_deref() == Some("ptr")).unwrap();
+        assert_eq!((ptr.offset, ptr.buffer_len), (8, 5));
+        assert_eq!(ptr.span.as_ref().map(|span| span.start_line), Some(11));
+    }
+}
@@ -0,0 +1,1029 @@
+//! Parser for the textual MIR printed by `-Zdump-mir` and `--emit=mir`.
+//!
+//! Dumps are line oriented, so the overall structure (bodies, scopes, basic blocks) is
+//! recovered line by line while every declaration, statement and terminator is parsed
+//! with `nom` into the typed model below. Anything the model does not cover is kept
+//! verbatim in an `Other` variant instead of failing the whole body.
+
+use anyhow::{anyhow, Result};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, digit1, multispace0, space0},
+    combinator::{all_consuming, map, map_res, opt, rest},
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult,
+};
+
+/// Index of a MIR local, i.e. the `N` in `_N`.
+pub type Local = usize;
+
+/// Index of a basic block, i.e. the `N` in `bbN`.
+pub type BlockId = usize;
+
+/// One `fn`, `const` or `static` body of a dump.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MirBody {
+    pub name: String,
+    pub args: Vec<(Local, String)>,
+    pub return_ty: String,
+    pub locals: Vec<LocalDecl>,
+    pub debug_vars: Vec<DebugVar>,
+    pub scopes: Vec<MirScope>,
+    pub blocks: Vec<BasicBlock>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalDecl {
+    pub local: Local,
+    pub mutable: bool,
+    pub ty: String,
+    pub scope: usize,
+    pub span: Option<MirSpan>,
+}
+
+/// A `debug name => place;` line binding a user variable to a place.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugVar {
+    pub name: String,
+    pub value: Operand,
+    pub scope: usize,
+    pub span: Option<MirSpan>,
+}
+
+/// A `scope N { .. }` block, optionally marking an inlined callee.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MirScope {
+    pub id: usize,
+    pub parent: Option<usize>,
+    pub inlined: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub id: BlockId,
+    pub cleanup: bool,
+    pub statements: Vec<Statement>,
+    pub terminator: Terminator,
+    pub terminator_span: Option<MirSpan>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Option<MirSpan>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    Assign(Place, Rvalue),
+    StorageLive(Local),
+    StorageDead(Local),
+    Nop,
+    Other(String),
+}
+
+/// Source position from a `// scope N at file:l:c: l:c` comment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MirSpan {
+    pub scope: usize,
+    pub file: String,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Place {
+    pub local: Local,
+    pub projection: Vec<PlaceElem>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaceElem {
+    Deref,
+    Field(usize, String),
+    Downcast(String),
+    Index(Local),
+    ConstantIndex { offset: u64, min_length: u64, from_end: bool },
+    Subslice(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Copy(Place),
+    Move(Place),
+    Constant(Constant),
+}
+
+/// A `const ..` operand. `value` is set for integer and boolean literals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constant {
+    pub text: String,
+    pub value: Option<i128>,
+    pub ty: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    AddWithOverflow,
+    AddUnchecked,
+    Sub,
+    SubWithOverflow,
+    SubUnchecked,
+    Mul,
+    MulWithOverflow,
+    MulUnchecked,
+    Div,
+    Rem,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    ShlUnchecked,
+    Shr,
+    ShrUnchecked,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Cmp,
+    Offset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    Not,
+    Neg,
+    PtrMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rvalue {
+    Use(Operand),
+    Ref { mutable: bool, place: Place },
+    RawPtr { mutable: bool, place: Place },
+    Len(Place),
+    Discriminant(Place),
+    Cast { operand: Operand, ty: String, kind: String },
+    BinaryOp(BinOp, Operand, Operand),
+    UnaryOp(UnOp, Operand),
+    /// `SizeOf(T)`, `AlignOf(T)` and similar type-level queries.
+    NullaryOp(String, String),
+    Repeat(Operand, String),
+    Aggregate(String, Vec<Operand>),
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Goto(BlockId),
+    SwitchInt { discr: Operand, targets: Vec<(i128, BlockId)>, otherwise: BlockId },
+    Return,
+    Resume,
+    Unreachable,
+    Drop { place: Place, target: BlockId, unwind: Option<BlockId> },
+    Call { destination: Place, func: String, args: Vec<Operand>, target: Option<BlockId>, unwind: Option<BlockId> },
+    Assert { cond: Operand, expected: bool, msg: AssertMessage, target: BlockId },
+    FalseEdge { real: BlockId, imaginary: BlockId },
+    FalseUnwind { real: BlockId },
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssertMessage {
+    BoundsCheck { len: Operand, index: Operand },
+    Overflow(String, Vec<Operand>),
+    Other(String),
+}
+
+impl MirBody {
+    pub fn block(&self, id: BlockId) -> Option<&BasicBlock> {
+        self.blocks.iter().find(|block| block.id == id)
+    }
+
+    pub fn local_ty(&self, local: Local) -> Option<&str> {
+        self.locals.iter().find(|decl| decl.local == local).map(|decl| decl.ty.as_str())
+    }
+
+    /// Name of the user variable bound directly to `local`, if any.
+    pub fn local_name(&self, local: Local) -> Option<&str> {
+        self.debug_vars.iter().find_map(|var| match &var.value {
+            Operand::Copy(place) if place.projection.is_empty() && place.local == local => Some(var.name.as_str()),
+            _ => None,
+        })
+    }
+}
+
+impl Place {
+    pub fn as_local(&self) -> Option<Local> {
+        self.projection.is_empty().then_some(self.local)
+    }
+}
+
+impl Operand {
+    pub fn place(&self) -> Option<&Place> {
+        match self {
+            Operand::Copy(place) | Operand::Move(place) => Some(place),
+            Operand::Constant(_) => None,
+        }
+    }
+
+    pub fn const_value(&self) -> Option<i128> {
+        match self {
+            Operand::Constant(constant) => constant.value,
+            _ => None,
+        }
+    }
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Goto(target) => vec![*target],
+            Terminator::SwitchInt { targets, otherwise, .. } => {
+                targets.iter().map(|(_, target)| *target).chain(Some(*otherwise)).collect()
+            }
+            Terminator::Drop { target, .. } => vec![*target],
+            Terminator::Call { target, .. } => target.iter().copied().collect(),
+            Terminator::Assert { target, .. } => vec![*target],
+            Terminator::FalseEdge { real, .. } | Terminator::FalseUnwind { real } => vec![*real],
+            Terminator::Return | Terminator::Resume | Terminator::Unreachable | Terminator::Other(_) => Vec::new(),
+        }
+    }
+}
+
+/// Parses every body found in a MIR dump.
+pub fn parse_mir(input: &str) -> Result<Vec<MirBody>> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut bodies = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if line.starts_with("fn ") || line.starts_with("const ") || line.starts_with("static ") {
+            let (body, next) = parse_body(&lines, i)
+                .map_err(|e| anyhow!("line {}: {}", i + 1, e))?;
+            bodies.push(body);
+            i = next;
+        } else {
+            i += 1;
+        }
+    }
+
+    Ok(bodies)
+}
+
+fn parse_body(lines: &[&str], start: usize) -> Result<(MirBody, usize)> {
+    let (header, _) = split_comment(lines[start]);
+    let mut body = parse_header(header.trim())?;
+    let mut scope_stack: Vec<usize> = vec![0];
+    body.scopes.push(MirScope { id: 0, parent: None, inlined: None });
+
+    let mut i = start + 1;
+    while i < lines.len() {
+        let (code, comment) = split_comment(lines[i]);
+        let code = code.trim();
+        i += 1;
+
+        if code.is_empty() {
+            continue;
+        }
+        if code == "}" {
+            if scope_stack.len() > 1 {
+                scope_stack.pop();
+                continue;
+            }
+            return Ok((body, i));
+        }
+        if let Some(header) = code.strip_prefix("scope ") {
+            let (_, (id, inlined)) = scope_header(header)
+                .map_err(|e| anyhow!("bad scope `{}`: {}", code, e))?;
+            body.scopes.push(MirScope { id, parent: scope_stack.last().copied(), inlined });
+            scope_stack.push(id);
+            continue;
+        }
+
+        let scope = *scope_stack.last().unwrap_or(&0);
+        if code.starts_with("let ") {
+            let (_, (mutable, local, ty)) = local_decl(code)
+                .map_err(|e| anyhow!("bad local `{}`: {}", code, e))?;
+            body.locals.push(LocalDecl { local, mutable, ty, scope, span: comment.and_then(parse_span) });
+        } else if code.starts_with("debug ") {
+            let (_, (name, value)) = debug_var(code)
+                .map_err(|e| anyhow!("bad debug info `{}`: {}", code, e))?;
+            body.debug_vars.push(DebugVar { name, value, scope, span: comment.and_then(parse_span) });
+        } else if let Ok((_, (id, cleanup))) = block_header(code) {
+            let (block, next) = parse_block(lines, i, id, cleanup)?;
+            body.blocks.push(block);
+            i = next;
+        }
+    }
+
+    Err(anyhow!("unterminated body `{}`", body.name))
+}
+
+fn parse_block(lines: &[&str], start: usize, id: BlockId, cleanup: bool) -> Result<(BasicBlock, usize)> {
+    let mut entries: Vec<(&str, Option<MirSpan>)> = Vec::new();
+    let mut i = start;
+
+    while i < lines.len() {
+        let (code, comment) = split_comment(lines[i]);
+        let code = code.trim();
+        i += 1;
+        if code.is_empty() {
+            continue;
+        }
+        if code == "}" {
+            break;
+        }
+        entries.push((code, comment.and_then(parse_span)));
+    }
+
+    let (last, terminator_span) = entries.pop()
+        .ok_or_else(|| anyhow!("bb{} has no terminator", id))?;
+    let terminator = parse_terminator(last);
+    let statements = entries.into_iter()
+        .map(|(code, span)| Statement { kind: parse_statement(code), span })
+        .collect();
+
+    Ok((BasicBlock { id, cleanup, statements, terminator, terminator_span }, i))
+}
+
+fn parse_header(header: &str) -> Result<MirBody> {
+    let header = header.trim_end_matches('{').trim();
+    if let Some(rest) = header.strip_prefix("fn ") {
+        let open = rest.find('(').ok_or_else(|| anyhow!("missing argument list in `{}`", header))?;
+        let name = rest[..open].trim().to_string();
+        let (params, after) = split_balanced(&rest[open + 1..], ')');
+        let args = split_top_level(params, ',')
+            .into_iter()
+            .filter(|arg| !arg.trim().is_empty())
+            .map(|arg| {
+                let (local_text, ty) = arg.split_once(':').ok_or_else(|| anyhow!("bad argument `{}`", arg))?;
+                let (_, local) = local(local_text.trim()).map_err(|e| anyhow!("bad argument `{}`: {}", arg, e))?;
+                Ok((local, ty.trim().to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let return_ty = after.trim_start_matches(')').trim()
+            .strip_prefix("->")
+            .map(|ty| ty.trim().to_string())
+            .unwrap_or_else(|| "()".to_string());
+        return Ok(MirBody { name, args, return_ty, ..Default::default() });
+    }
+
+    let rest = header.strip_prefix("const ").or_else(|| header.strip_prefix("static "))
+        .ok_or_else(|| anyhow!("unknown body header `{}`", header))?;
+    let rest = rest.trim_start_matches("mut ");
+    let (name, ty) = rest.rsplit_once(": ").ok_or_else(|| anyhow!("bad const header `{}`", header))?;
+    Ok(MirBody {
+        name: name.trim().to_string(),
+        return_ty: ty.trim_end_matches('=').trim().to_string(),
+        ..Default::default()
+    })
+}
+
+/// Parses one statement line (without the trailing comment).
+pub fn parse_statement(code: &str) -> StatementKind {
+    let code = code.trim_end_matches(';').trim();
+    let parsed: IResult<&str, StatementKind> = alt((
+        map(delimited(tag("StorageLive("), local, char(')')), StatementKind::StorageLive),
+        map(delimited(tag("StorageDead("), local, char(')')), StatementKind::StorageDead),
+        map(all_consuming(tag("nop")), |_| StatementKind::Nop),
+    ))(code);
+    if let Ok(("", kind)) = parsed {
+        return kind;
+    }
+    if let Some((lhs, rhs)) = split_assignment(code)
+        && let Ok(("", place)) = place(lhs)
+    {
+        return StatementKind::Assign(place, parse_rvalue(rhs));
+    }
+    StatementKind::Other(code.to_string())
+}
+
+/// Parses the right-hand side of an assignment.
+pub fn parse_rvalue(text: &str) -> Rvalue {
+    let text = text.trim();
+    if let Ok(("", rvalue)) = rvalue(text) {
+        return rvalue;
+    }
+    if let Some((operand_text, cast)) = rsplit_top_level(text, " as ")
+        && let Ok(("", operand)) = operand(operand_text)
+    {
+        let (ty, kind) = match cast.rfind(" (") {
+            Some(pos) if cast.ends_with(')') => (&cast[..pos], &cast[pos + 2..cast.len() - 1]),
+            _ => (cast, ""),
+        };
+        return Rvalue::Cast { operand, ty: ty.trim().to_string(), kind: kind.to_string() };
+    }
+    if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        let parts = split_top_level(inner, ';');
+        if parts.len() == 2
+            && let Ok(("", element)) = operand(parts[0].trim())
+        {
+            return Rvalue::Repeat(element, parts[1].trim().to_string());
+        }
+        if let Some(operands) = operand_list(inner) {
+            return Rvalue::Aggregate("array".to_string(), operands);
+        }
+    }
+    if let Some(inner) = text.strip_prefix('(').and_then(|t| t.strip_suffix(')'))
+        && let Some(operands) = operand_list(inner)
+    {
+        return Rvalue::Aggregate("tuple".to_string(), operands);
+    }
+    // Tuple-like enum variants and structs, e.g. `Result::<(), E>::Ok(const ())`.
+    if let Some(open) = find_top_level(text, '(').filter(|&open| open > 0)
+        && let Some(operands) = text[open + 1..].strip_suffix(')').and_then(operand_list)
+    {
+        return Rvalue::Aggregate(text[..open].to_string(), operands);
+    }
+    if let Some((name, fields)) = text.split_once(" {")
+        && let Some(fields) = fields.trim().strip_suffix('}')
+    {
+        let operands = split_top_level(fields, ',')
+            .into_iter()
+            .filter_map(|field| field.split_once(": ").map(|(_, value)| value.trim()))
+            .filter_map(|value| operand(value).ok().map(|(_, operand)| operand))
+            .collect();
+        return Rvalue::Aggregate(name.trim().to_string(), operands);
+    }
+    if let Ok(("", operand)) = operand(text) {
+        return Rvalue::Use(operand);
+    }
+    Rvalue::Other(text.to_string())
+}
+
+/// Parses a terminator line (without the trailing comment).
+pub fn parse_terminator(code: &str) -> Terminator {
+    let code = code.trim_end_matches(';').trim();
+    match code {
+        "return" => return Terminator::Return,
+        "resume" => return Terminator::Resume,
+        "unreachable" => return Terminator::Unreachable,
+        _ => {}
+    }
+
+    let (head, edges) = match rsplit_top_level(code, " -> ") {
+        Some((head, edges)) => (head.trim(), edges.trim()),
+        None => return Terminator::Other(code.to_string()),
+    };
+    let edges = parse_edges(edges);
+    let edge = |label: &str| edges.iter().find(|(l, _)| l == label).and_then(|(_, target)| *target);
+
+    if head == "goto"
+        && let Some(target) = edge("")
+    {
+        return Terminator::Goto(target);
+    }
+    if head == "falseEdge"
+        && let (Some(real), Some(imaginary)) = (edge("real"), edge("imaginary"))
+    {
+        return Terminator::FalseEdge { real, imaginary };
+    }
+    if head == "falseUnwind"
+        && let Some(real) = edge("real")
+    {
+        return Terminator::FalseUnwind { real };
+    }
+    if let Some(discr) = head.strip_prefix("switchInt(").and_then(|h| h.strip_suffix(')'))
+        && let (Ok(("", discr)), Some(otherwise)) = (operand(discr), edge("otherwise"))
+    {
+        let targets = edges.iter()
+            .filter_map(|(label, target)| {
+                let value = match label.as_str() {
+                    "false" => 0,
+                    "true" => 1,
+                    label => label.parse::<i128>().ok()?,
+                };
+                Some((value, (*target)?))
+            })
+            .collect();
+        return Terminator::SwitchInt { discr, targets, otherwise };
+    }
+    if let Some(place_text) = head.strip_prefix("drop(").and_then(|h| h.strip_suffix(')'))
+        && let (Ok(("", place)), Some(target)) = (place(place_text), edge("return").or(edge("")))
+    {
+        return Terminator::Drop { place, target, unwind: edge("unwind") };
+    }
+    if let Some(args) = head.strip_prefix("assert(").and_then(|h| h.strip_suffix(')'))
+        && let Some(assert) = parse_assert(args, edge("success").or(edge("")))
+    {
+        return assert;
+    }
+    if let Some((lhs, rhs)) = split_assignment(head)
+        && let (Ok(("", destination)), Some(open)) = (place(lhs), find_top_level(rhs, '('))
+    {
+        let func = rhs[..open].trim().to_string();
+        let (args_text, after) = split_balanced(&rhs[open + 1..], ')');
+        if after == ")"
+            && let Some(args) = operand_list(args_text) {
+                return Terminator::Call {
+                    destination,
+                    func,
+                    args,
+                    target: edge("return").or(edge("")),
+                    unwind: edge("unwind"),
+                };
+            }
+    }
+
+    Terminator::Other(code.to_string())
+}
+
+fn parse_assert(args: &str, target: Option<BlockId>) -> Option<Terminator> {
+    let parts = split_top_level(args, ',');
+    let (cond_text, msg_parts) = parts.split_first()?;
+    let cond_text = cond_text.trim();
+    let (expected, cond_text) = match cond_text.strip_prefix('!') {
+        Some(negated) => (false, negated),
+        None => (true, cond_text),
+    };
+    let (_, cond) = operand(cond_text).ok()?;
+    let (msg_text, msg_args) = msg_parts.split_first()?;
+    let msg_text = msg_text.trim().trim_matches('"').to_string();
+    let msg_args: Vec<Operand> = msg_args.iter()
+        .filter_map(|arg| operand(arg.trim()).ok().map(|(_, operand)| operand))
+        .collect();
+
+    let msg = if msg_text.starts_with("index out of bounds") && msg_args.len() == 2 {
+        AssertMessage::BoundsCheck { len: msg_args[0].clone(), index: msg_args[1].clone() }
+    } else if msg_text.starts_with("attempt to") {
+        AssertMessage::Overflow(msg_text, msg_args)
+    } else {
+        AssertMessage::Other(msg_text)
+    };
+    Some(Terminator::Assert { cond, expected, msg, target: target? })
+}
+
+/// Parses `[return: bb1, unwind: bb2]`, `bb3` or `[0: bb7, otherwise: bb5]`.
+fn parse_edges(text: &str) -> Vec<(String, Option<BlockId>)> {
+    let inner = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')).unwrap_or(text);
+    split_top_level(inner, ',')
+        .into_iter()
+        .map(|edge| {
+            let edge = edge.trim();
+            let (label, target) = match edge.split_once(':') {
+                Some((label, target)) => (label.trim(), target.trim()),
+                None if edge.starts_with("unwind ") => ("unwind", ""),
+                None => ("", edge),
+            };
+            let target = block_id(target).ok().map(|(_, id)| id);
+            (label.to_string(), target)
+        })
+        .collect()
+}
+
+/// Extracts the span from a `scope N at file:l:c: l:c` comment.
+fn parse_span(comment: &str) -> Option<MirSpan> {
+    let comment = comment.trim();
+    let at = comment.find("scope ")?;
+    let (_, scope) = map_res(digit1::<&str, nom::error::Error<&str>>, str::parse)(&comment[at + 6..]).ok()?;
+    let location = comment.split_once(" at ")?.1.trim();
+    let mut parts = location.rsplitn(5, ':');
+    let end_column = parts.next()?.trim().parse().ok()?;
+    let end_line = parts.next()?.trim().parse().ok()?;
+    let start_column = parts.next()?.trim().parse().ok()?;
+    let start_line = parts.next()?.trim().parse().ok()?;
+    let file = parts.next()?.to_string();
+    Some(MirSpan { scope, file, start_line, start_column, end_line, end_column })
+}
+
+fn local(input: &str) -> IResult<&str, Local> {
+    preceded(char('_'), map_res(digit1, str::parse))(input)
+}
+
+fn block_id(input: &str) -> IResult<&str, BlockId> {
+    preceded(tag("bb"), map_res(digit1, str::parse))(input)
+}
+
+fn block_header(input: &str) -> IResult<&str, (BlockId, bool)> {
+    all_consuming(terminated(
+        tuple((block_id, map(opt(tag(" (cleanup)")), |cleanup| cleanup.is_some()))),
+        tuple((tag(":"), space0, char('{'))),
+    ))(input)
+}
+
+/// `1 {` or `2 (inlined std::ptr::mut_ptr::<impl *mut T>::add) {`.
+fn scope_header(input: &str) -> IResult<&str, (usize, Option<String>)> {
+    let header = input.trim_end().strip_suffix('{').ok_or_else(|| nom_error(input))?.trim_end();
+    let (tail, id) = map_res(digit1, str::parse)(header)?;
+    let (tail, inlined) = opt(delimited(
+        tag(" (inlined "),
+        map(rest, |text: &str| text.strip_suffix(')').unwrap_or(text).trim().to_string()),
+        multispace0,
+    ))(tail)?;
+    Ok((tail, (id, inlined)))
+}
+
+fn local_decl(input: &str) -> IResult<&str, (bool, Local, String)> {
+    let (input, _) = tag("let ")(input)?;
+    let (input, mutable) = map(opt(tag("mut ")), |m| m.is_some())(input)?;
+    let (input, local) = local(input)?;
+    let (input, _) = tuple((char(':'), space0))(input)?;
+    let (input, ty) = rest(input)?;
+    Ok((input, (mutable, local, ty.trim_end_matches(';').trim().to_string())))
+}
+
+fn debug_var(input: &str) -> IResult<&str, (String, Operand)> {
+    let (input, _) = tag("debug ")(input)?;
+    let (input, name) = take_while1(|c: char| c != ' ')(input)?;
+    let (input, _) = tag(" => ")(input)?;
+    let value_text = input.trim_end_matches(';').trim();
+    let value = match place(value_text) {
+        Ok(("", place)) => Operand::Copy(place),
+        _ => match operand(value_text) {
+            Ok((_, operand)) => operand,
+            Err(_) => Operand::Constant(Constant { text: value_text.to_string(), value: None, ty: None }),
+        },
+    };
+    Ok(("", (name.to_string(), value)))
+}
+
+/// Parses a place: `_1`, `(*_2)`, `(_3.0: T)`, `(_4 as Some)`, `_5[_6]`, `_7[1 of 3]`.
+fn place(input: &str) -> IResult<&str, Place> {
+    let (mut input, mut place) = alt((
+        map(delimited(tag("(*"), place, char(')')), |mut inner: Place| {
+            inner.projection.push(PlaceElem::Deref);
+            inner
+        }),
+        paren_place,
+        map(local, |local| Place { local, projection: Vec::new() }),
+    ))(input)?;
+
+    while let Some(rest) = input.strip_prefix('[') {
+        let (inner, after) = split_balanced(rest, ']');
+        let elem = if let Ok(("", index)) = local(inner) {
+            PlaceElem::Index(index)
+        } else if let Some((offset, min_length)) = inner.split_once(" of ") {
+            let from_end = offset.starts_with('-');
+            match (offset.trim_start_matches('-').parse(), min_length.parse()) {
+                (Ok(offset), Ok(min_length)) => PlaceElem::ConstantIndex { offset, min_length, from_end },
+                _ => PlaceElem::Subslice(inner.to_string()),
+            }
+        } else {
+            PlaceElem::Subslice(inner.to_string())
+        };
+        place.projection.push(elem);
+        input = after.strip_prefix(']').unwrap_or(after);
+    }
+
+    Ok((input, place))
+}
+
+/// `(place.N: Ty)` and `(place as Variant)`.
+fn paren_place(input: &str) -> IResult<&str, Place> {
+    let (after_open, _) = char('(')(input)?;
+    let (inner, after) = split_balanced(after_open, ')');
+    let remaining = after.strip_prefix(')').ok_or_else(|| nom_error(input))?;
+
+    let (tail, mut base) = place(inner)?;
+    if let Some(variant) = tail.strip_prefix(" as ") {
+        base.projection.push(PlaceElem::Downcast(variant.trim().to_string()));
+        return Ok((remaining, base));
+    }
+    let field: IResult<&str, (usize, &str)> = tuple((
+        preceded(char('.'), map_res(digit1, str::parse)),
+        preceded(tuple((char(':'), space0)), rest),
+    ))(tail);
+    match field {
+        Ok((_, (index, ty))) => {
+            base.projection.push(PlaceElem::Field(index, ty.trim().to_string()));
+            Ok((remaining, base))
+        }
+        Err(_) => Err(nom_error(input)),
+    }
+}
+
+fn operand(input: &str) -> IResult<&str, Operand> {
+    alt((
+        map(preceded(tag("copy "), place), Operand::Copy),
+        map(preceded(tag("move "), place), Operand::Move),
+        map(preceded(tag("deref_copy "), place), Operand::Copy),
+        map(preceded(tag("const "), rest), |text: &str| Operand::Constant(parse_constant(text))),
+        // Debug info and older dumps print bare places.
+        map(place, Operand::Copy),
+    ))(input)
+}
+
+fn operand_list(text: &str) -> Option<Vec<Operand>> {
+    if text.trim().is_empty() {
+        return Some(Vec::new());
+    }
+    split_top_level(text, ',')
+        .into_iter()
+        .map(|part| match operand(part.trim()) {
+            Ok(("", operand)) => Some(operand),
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_constant(text: &str) -> Constant {
+    let text = text.trim();
+    let (value, ty) = match text {
+        "true" => (Some(1), Some("bool".to_string())),
+        "false" => (Some(0), Some("bool".to_string())),
+        _ => match text.rsplit_once('_') {
+            Some((number, ty)) if ty.starts_with('u') || ty.starts_with('i') => {
+                match number.replace('_', "").parse::<i128>() {
+                    Ok(value) => (Some(value), Some(ty.to_string())),
+                    Err(_) => (None, None),
+                }
+            }
+            _ => (None, None),
+        },
+    };
+    Constant { text: text.to_string(), value, ty }
+}
+
+fn rvalue(input: &str) -> IResult<&str, Rvalue> {
+    alt((
+        map(preceded(tag("&raw mut "), place), |place| Rvalue::RawPtr { mutable: true, place }),
+        map(preceded(tag("&raw const "), place), |place| Rvalue::RawPtr { mutable: false, place }),
+        map(preceded(tag("&mut "), place), |place| Rvalue::Ref { mutable: true, place }),
+        map(preceded(alt((tag("&fake shallow "), tag("&fake deep "))), place), |place| Rvalue::Ref { mutable: false, place }),
+        map(preceded(char('&'), place), |place| Rvalue::Ref { mutable: false, place }),
+        map(delimited(tag("Len("), place, char(')')), Rvalue::Len),
+        map(delimited(tag("discriminant("), place, char(')')), Rvalue::Discriminant),
+        call_like_rvalue,
+    ))(input)
+}
+
+/// `Add(copy _1, const 2_usize)`, `Not(move _3)`, `SizeOf(T)`.
+fn call_like_rvalue(input: &str) -> IResult<&str, Rvalue> {
+    let (after_name, name) = take_while1(|c: char| c.is_ascii_alphanumeric())(input)?;
+    let (inner, _) = char('(')(after_name)?;
+    let (args, after) = split_balanced(inner, ')');
+    if after != ")" {
+        return Err(nom_error(input));
+    }
+
+    if let Some(op) = bin_op(name) {
+        let parts = split_top_level(args, ',');
+        if parts.len() == 2
+            && let (Ok(("", left)), Ok(("", right))) = (operand(parts[0].trim()), operand(parts[1].trim()))
+        {
+            return Ok(("", Rvalue::BinaryOp(op, left, right)));
+        }
+        return Err(nom_error(input));
+    }
+    let un_op = match name {
+        "Not" => Some(UnOp::Not),
+        "Neg" => Some(UnOp::Neg),
+        "PtrMetadata" => Some(UnOp::PtrMetadata),
+        _ => None,
+    };
+    if let Some(op) = un_op {
+        return match operand(args.trim()) {
+            Ok(("", operand)) => Ok(("", Rvalue::UnaryOp(op, operand))),
+            _ => Err(nom_error(input)),
+        };
+    }
+    if matches!(name, "SizeOf" | "AlignOf" | "OffsetOf" | "UbChecks" | "ContractChecks") {
+        return Ok(("", Rvalue::NullaryOp(name.to_string(), args.trim().to_string())));
+    }
+    Err(nom_error(input))
+}
+
+fn bin_op(name: &str) -> Option<BinOp> {
+    Some(match name {
+        "Add" => BinOp::Add,
+        "AddWithOverflow" => BinOp::AddWithOverflow,
+        "AddUnchecked" => BinOp::AddUnchecked,
+        "Sub" => BinOp::Sub,
+        "SubWithOverflow" => BinOp::SubWithOverflow,
+        "SubUnchecked" => BinOp::SubUnchecked,
+        "Mul" => BinOp::Mul,
+        "MulWithOverflow" => BinOp::MulWithOverflow,
+        "MulUnchecked" => BinOp::MulUnchecked,
+        "Div" => BinOp::Div,
+        "Rem" => BinOp::Rem,
+        "BitAnd" => BinOp::BitAnd,
+        "BitOr" => BinOp::BitOr,
+        "BitXor" => BinOp::BitXor,
+        "Shl" => BinOp::Shl,
+        "ShlUnchecked" => BinOp::ShlUnchecked,
+        "Shr" => BinOp::Shr,
+        "ShrUnchecked" => BinOp::ShrUnchecked,
+        "Eq" => BinOp::Eq,
+        "Ne" => BinOp::Ne,
+        "Lt" => BinOp::Lt,
+        "Le" => BinOp::Le,
+        "Gt" => BinOp::Gt,
+        "Ge" => BinOp::Ge,
+        "Cmp" => BinOp::Cmp,
+        "Offset" => BinOp::Offset,
+        _ => return None,
+    })
+}
+
+fn nom_error(input: &str) -> nom::Err<nom::error::Error<&str>> {
+    nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify))
+}
+
+/// Splits `place = rvalue` at the first top-level ` = `.
+fn split_assignment(code: &str) -> Option<(&str, &str)> {
+    let pos = find_top_level_str(code, " = ")?;
+    Some((code[..pos].trim(), code[pos + 3..].trim()))
+}
+
+/// Separates code from a trailing `// ...` comment. Headers such as `bb0: {` leave a
+/// bracket open, so only string literals are skipped here.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (pos, c) in line.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if line[pos..].starts_with("//") {
+            return (&line[..pos], Some(&line[pos + 2..]));
+        }
+    }
+    (line, None)
+}
+
+/// Returns the text up to the `close` delimiter that balances an already consumed opener,
+/// and the remainder starting at that delimiter.
+fn split_balanced(input: &str, close: char) -> (&str, &str) {
+    let mut depth = 0usize;
+    let mut chars = input.char_indices().peekable();
+    let mut in_string = false;
+    while let Some((pos, c)) = chars.next() {
+        if in_string {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '-' if matches!(chars.peek(), Some((_, '>'))) => {
+                chars.next();
+            }
+            c if c == close && depth == 0 => return (&input[..pos], &input[pos..]),
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    (input, "")
+}
+
+/// Splits on `separator` occurrences that are not nested in brackets or string literals.
+fn split_top_level(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = input;
+    loop {
+        let (part, after) = split_balanced(rest, separator);
+        parts.push(part);
+        match after.strip_prefix(separator) {
+            Some(after) => rest = after,
+            None => return parts,
+        }
+    }
+}
+
+fn find_top_level(input: &str, needle: char) -> Option<usize> {
+    let (before, after) = split_balanced(input, needle);
+    (!after.is_empty()).then_some(before.len())
+}
+
+fn find_top_level_str(input: &str, needle: &str) -> Option<usize> {
+    top_level_positions(input, needle).into_iter().next()
+}
+
+fn rsplit_top_level<'a>(input: &'a str, needle: &str) -> Option<(&'a str, &'a str)> {
+    let pos = top_level_positions(input, needle).into_iter().last()?;
+    Some((&input[..pos], &input[pos + needle.len()..]))
+}
+
+/// Byte positions of `needle` outside brackets and string literals.
+fn top_level_positions(input: &str, needle: &str) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (pos, c) in input.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        if depth == 0 && input[pos..].starts_with(needle) {
+            positions.push(pos);
+        }
+        match c {
+            '"' => in_string = true,
+            '>' if pos > 0 && input.as_bytes()[pos - 1] == b'-' => {}
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_statements_and_terminators() {
+        assert_eq!(
+            parse_statement("_4 = Offset(copy _2, const 8_usize);"),
+            StatementKind::Assign(
+                Place { local: 4, projection: Vec::new() },
+                Rvalue::BinaryOp(
+                    BinOp::Offset,
+                    Operand::Copy(Place { local: 2, projection: Vec::new() }),
+                    Operand::Constant(Constant { text: "8_usize".to_string(), value: Some(8), ty: Some("usize".to_string()) }),
+                ),
+            ),
+        );
+        assert!(matches!(
+            parse_statement("_7 = copy ((_8 as Some).0: usize);"),
+            StatementKind::Assign(_, Rvalue::Use(Operand::Copy(Place { local: 8, ref projection })))
+                if projection.len() == 2
+        ));
+        assert!(matches!(
+            parse_statement("_3 = copy _21 as *const () (PtrToPtr);"),
+            StatementKind::Assign(_, Rvalue::Cast { ref kind, .. }) if kind == "PtrToPtr"
+        ));
+
+        assert_eq!(
+            parse_terminator("switchInt(move _11) -> [0: bb7, 1: bb6, otherwise: bb5];"),
+            Terminator::SwitchInt {
+                discr: Operand::Move(Place { local: 11, projection: Vec::new() }),
+                targets: vec![(0, 7), (1, 6)],
+                otherwise: 5,
+            },
+        );
+        match parse_terminator("_1 = from_elem::<u8>(const 0_u8, const 5_usize) -> [return: bb1, unwind continue];") {
+            Terminator::Call { func, args, target, unwind, .. } => {
+                assert_eq!(func, "from_elem::<u8>");
+                assert_eq!(args[1].const_value(), Some(5));
+                assert_eq!((target, unwind), (Some(1), None));
+            }
+            other => panic!("unexpected terminator {:?}", other),
+        }
+        match parse_terminator(r#"assert(move _6, "index out of bounds: the length is {} but the index is {}", move _5, copy _4) -> [success: bb2, unwind continue];"#) {
+            Terminator::Assert { expected, msg: AssertMessage::BoundsCheck { .. }, target, .. } => {
+                assert!(expected);
+                assert_eq!(target, 2);
+            }
+            other => panic!("unexpected terminator {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_emitted_mir() {
+        let content = fs::read_to_string("mir_output/buffer_overflow_test.mir").unwrap();
+        let bodies = parse_mir(&content).unwrap();
+        let body = bodies.iter().find(|b| b.name == "test_buffer_overflow").unwrap();
+
+        assert_eq!(body.return_ty, "()");
+        assert!(body.debug_vars.iter().any(|var| var.name == "ptr"));
+        assert!(body.blocks.iter().all(|block| !matches!(block.terminator, Terminator::Other(_))));
+        assert!(body.blocks.iter().any(|block| matches!(
+            &block.terminator,
+            Terminator::Call { func, .. } if func.ends_with("::add")
+        )));
+    }
+
+    #[test]
+    fn test_parse_dump_spans() {
+        let content = fs::read_to_string("mir_dump/buffer_overflow_test.test_buffer_overflow.-------.nll.0.mir").unwrap();
+        let bodies = parse_mir(&content).unwrap();
+        let body = &bodies[0];
+
+        let add = body.blocks.iter()
+            .find(|block| matches!(&block.terminator, Terminator::Call { func, .. } if func.ends_with("::add")))
+            .unwrap();
+        let span = add.terminator_span.as_ref().unwrap();
+        assert_eq!(span.file, "examples/buffer_overflow_test.rs");
+        assert_eq!((span.start_line, span.start_column, span.end_line, span.end_column), (11, 10, 11, 20));
+        assert!(body.scopes.len() > 1);
+    }
+}
@@ -0,0 +1,1393 @@
+//! Bounded symbolic execution over parsed MIR bodies.
+//!
+//! Each body is executed from its entry block with its parameters as unknown inputs.
+//! Integer locals are bit-vector terms of their type's width, and buffers (`vec!`,
+//! arrays, slices) are allocations with a symbolic length and capacity. `switchInt`
+//! forks the execution once per feasible target, extending the path constraint, and
+//! `assert` terminators keep only the executions that do not panic. Calls into common
+//! standard library functions are replaced by summaries of their effect on lengths,
+//! pointers and ranges, calls into the other bodies of the dump are executed in place,
+//! and any other call returns an unknown value. Every block may be entered at most `k`
+//! times on a path, which unrolls loops up to `k` iterations.
+//!
+//! Pointer offsets (`Offset` and calls to `add`, `offset`, `sub`, ...) are checked against
+//! the capacity of the allocation the pointer comes from; a reachable offset out of
+//! bounds is reported with concrete inputs taken from a model of the path.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use z3::{Context, Model, SatResult, Solver, ast::{Ast, Bool, BV}};
+
+use crate::analyzer::POINTER_METHODS;
+use crate::mir_analyzer::{array_len, callee_path, is_method};
+use crate::mir_parser::{
+    BasicBlock, BinOp, BlockId, Local, MirBody, MirSpan, Operand, Place, PlaceElem, Rvalue, StatementKind, Terminator,
+    UnOp,
+};
+use crate::solver::PointerWidth;
+
+/// Name of the symbols standing for values that are not inputs, such as the results of
+/// unknown calls.
+const UNKNOWN: &str = "unknown";
+
+/// Extra bits of the offsets of pointers, so that positions before the start of an
+/// allocation and past the end of the address space do not wrap around.
+const POSITION_BITS: u32 = 2;
+
+/// A pointer offset that goes out of bounds on some path, with inputs that lead to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolicFinding {
+    /// Body containing the offset, which may have been reached through a call.
+    pub function: String,
+    pub block: BlockId,
+    /// `Offset` for the MIR operator, otherwise the pointer method, e.g. `add`.
+    pub method: String,
+    pub buffer_name: String,
+    pub pointer_name: Option<String>,
+    /// Offset from the start of the allocation, in elements, on the counterexample path.
+    pub offset: i128,
+    /// Capacity of the allocation on the counterexample path.
+    pub buffer_len: u64,
+    /// Values of the parameters and of the unknown values the path depends on, e.g.
+    /// `n = 6` or `buf.len() = 0`.
+    pub inputs: BTreeMap<String, i128>,
+    pub span: Option<MirSpan>,
+}
+
+/// Outcome of exploring a set of bodies.
+#[derive(Debug, Default)]
+pub struct Exploration {
+    pub findings: Vec<SymbolicFinding>,
+    /// Paths explored to their end.
+    pub paths: usize,
+    /// Paths cut off by the unrolling bound or the path budget.
+    pub truncated: usize,
+}
+
+/// Bounded symbolic executor over MIR bodies.
+pub struct SymbolicExecutor<'ctx> {
+    ctx: &'ctx Context,
+    width: PointerWidth,
+    unroll: usize,
+    max_paths: usize,
+    max_depth: usize,
+}
+
+impl<'ctx> SymbolicExecutor<'ctx> {
+    pub fn new(ctx: &'ctx Context) -> Self {
+        SymbolicExecutor { ctx, width: PointerWidth::default(), unroll: 16, max_paths: 4096, max_depth: 4 }
+    }
+
+    /// Sets the width of `usize`/`isize`.
+    pub fn with_pointer_width(mut self, width: PointerWidth) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets how many times a path may enter the same block, i.e. how far loops are unrolled.
+    pub fn with_unroll(mut self, k: usize) -> Self {
+        self.unroll = k;
+        self
+    }
+
+    /// Executes every `fn` body of `bodies` from its entry, calling into the others.
+    pub fn explore(&self, bodies: &[MirBody]) -> Exploration {
+        let mut run = Run {
+            executor: self,
+            bodies,
+            solver: Solver::new_for_logic(self.ctx, "QF_BV").unwrap_or_else(|| Solver::new(self.ctx)),
+            symbols: 0,
+            exploration: Exploration::default(),
+            reported: HashSet::new(),
+        };
+        for (index, body) in bodies.iter().enumerate() {
+            if body.blocks.is_empty() || body.name.contains('{') {
+                continue;
+            }
+            run.explore_body(index);
+        }
+        run.exploration
+    }
+}
+
+/// A symbolic value of a local or of a part of it.
+#[derive(Debug, Clone)]
+enum Value<'ctx> {
+    Int(BV<'ctx>),
+    Bool(Bool<'ctx>),
+    /// A `Vec`, array, slice or string, by allocation index; references and boxes of
+    /// buffers are the buffer itself.
+    Buffer(usize),
+    /// A raw pointer `offset` elements into an allocation. `wrapped` is the `wrapping_*`
+    /// method that last moved it unchecked, so that it is checked where it is accessed.
+    Pointer { allocation: usize, offset: BV<'ctx>, name: Option<String>, wrapped: Option<&'static str> },
+    /// A reference to a local of a frame.
+    Ref { frame: usize, local: Local, mutable: bool },
+    /// `start..end`, or `start..=end` when `exhausted` is set.
+    Range { start: BV<'ctx>, end: BV<'ctx>, exhausted: Option<Bool<'ctx>> },
+    /// An iterator at item `index` of `end`, yielding the elements of `allocation`, with
+    /// their indices when `enumerate` is set.
+    Iter { allocation: Option<usize>, index: BV<'ctx>, end: BV<'ctx>, enumerate: bool },
+    /// A tuple, struct or enum value; `variant` is the discriminant of an enum.
+    Adt { variant: Option<BV<'ctx>>, fields: Vec<Value<'ctx>> },
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+struct Allocation<'ctx> {
+    name: String,
+    len: BV<'ctx>,
+    capacity: BV<'ctx>,
+    /// Values of the elements, for arrays of known values that were not written since.
+    elements: Vec<Value<'ctx>>,
+}
+
+#[derive(Debug, Clone)]
+struct Frame<'ctx> {
+    body: usize,
+    block: BlockId,
+    locals: HashMap<Local, Value<'ctx>>,
+    /// Where the caller receives the return value, and the block it continues at.
+    destination: Option<(Place, Option<BlockId>)>,
+    visits: HashMap<BlockId, usize>,
+}
+
+/// One path being executed.
+#[derive(Debug, Clone)]
+struct State<'ctx> {
+    frames: Vec<Frame<'ctx>>,
+    allocations: Vec<Allocation<'ctx>>,
+    path: Vec<Bool<'ctx>>,
+    /// Unknown values introduced on the path, by display name, and whether they are
+    /// signed integers.
+    inputs: Vec<(String, Value<'ctx>, bool)>,
+}
+
+/// Position of a checked offset.
+#[derive(Clone, Copy)]
+struct Site<'a> {
+    block: BlockId,
+    index: usize,
+    method: &'static str,
+    span: Option<&'a MirSpan>,
+}
+
+/// Functions and methods that read or write through their pointer arguments.
+const POINTER_ACCESSES: [&str; 15] = [
+    "read", "write", "read_unaligned", "write_unaligned", "read_volatile", "write_volatile", "replace", "swap",
+    "copy", "copy_nonoverlapping", "copy_to", "copy_to_nonoverlapping", "copy_from", "copy_from_nonoverlapping",
+    "write_bytes",
+];
+
+struct Run<'e, 'ctx> {
+    executor: &'e SymbolicExecutor<'ctx>,
+    bodies: &'e [MirBody],
+    solver: Solver<'ctx>,
+    /// Number of symbols created, for unique names.
+    symbols: usize,
+    exploration: Exploration,
+    /// Offsets already reported, by body, block and statement index.
+    reported: HashSet<(String, BlockId, usize)>,
+}
+
+impl<'e, 'ctx> Run<'e, 'ctx> {
+    fn ctx(&self) -> &'ctx Context {
+        self.executor.ctx
+    }
+
+    fn width(&self) -> u32 {
+        self.executor.width.bits()
+    }
+
+    fn explore_body(&mut self, entry: usize) {
+        let body = &self.bodies[entry];
+        let mut state = State {
+            frames: vec![Frame {
+                body: entry,
+                block: 0,
+                locals: HashMap::new(),
+                destination: None,
+                visits: HashMap::new(),
+            }],
+            allocations: Vec::new(),
+            path: Vec::new(),
+            inputs: Vec::new(),
+        };
+        for (local, ty) in &body.args {
+            let name = body.local_name(*local).map(str::to_string).unwrap_or_else(|| format!("_{}", local));
+            let value = self.input(&mut state, &name, ty);
+            state.frames[0].locals.insert(*local, value);
+        }
+
+        let mut pending = vec![state];
+        let mut explored = 0;
+        while let Some(state) = pending.pop() {
+            if explored >= self.executor.max_paths {
+                self.exploration.truncated += pending.len() + 1;
+                break;
+            }
+            explored += 1;
+            pending.extend(self.execute_block(state));
+        }
+    }
+
+    /// A fresh input of type `ty` named `name`: an integer, a boolean or a buffer.
+    fn input(&mut self, state: &mut State<'ctx>, name: &str, ty: &str) -> Value<'ctx> {
+        if let Some(bits) = int_bits(ty, self.width()) {
+            return Value::Int(self.fresh_int(state, name, bits, is_signed(ty)));
+        }
+        if ty == "bool" {
+            return Value::Bool(self.fresh_bool(state, name));
+        }
+        let inner = pointee(ty);
+        if let Some(len) = array_len(inner) {
+            let len = BV::from_u64(self.ctx(), len as u64, self.width());
+            return self.allocate(state, name, len.clone(), len);
+        }
+        let unsized_buffer = inner.starts_with('[')
+            || inner == "str"
+            || inner.starts_with("Vec<")
+            || inner.starts_with("std::vec::Vec<")
+            || inner == "String"
+            || inner == "std::string::String";
+        if unsized_buffer {
+            let len = self.fresh_int(state, &format!("{}.len()", name), self.width(), false);
+            return self.allocate(state, name, len.clone(), len);
+        }
+        Value::Unknown
+    }
+
+    /// A fresh integer symbol, reported in counterexamples unless it is `UNKNOWN`.
+    fn fresh_int(&mut self, state: &mut State<'ctx>, name: &str, bits: u32, signed: bool) -> BV<'ctx> {
+        self.symbols += 1;
+        let symbol = BV::new_const(self.ctx(), format!("{}!{}", name, self.symbols), bits);
+        if name != UNKNOWN {
+            state.inputs.push((name.to_string(), Value::Int(symbol.clone()), signed));
+        }
+        symbol
+    }
+
+    fn fresh_bool(&mut self, state: &mut State<'ctx>, name: &str) -> Bool<'ctx> {
+        self.symbols += 1;
+        let symbol = Bool::new_const(self.ctx(), format!("{}!{}", name, self.symbols));
+        if name != UNKNOWN {
+            state.inputs.push((name.to_string(), Value::Bool(symbol.clone()), false));
+        }
+        symbol
+    }
+
+    fn allocate(&mut self, state: &mut State<'ctx>, name: &str, len: BV<'ctx>, capacity: BV<'ctx>) -> Value<'ctx> {
+        state.allocations.push(Allocation { name: name.to_string(), len, capacity, elements: Vec::new() });
+        Value::Buffer(state.allocations.len() - 1)
+    }
+
+    /// Whether the path constraint together with `extra` has a solution. Paths are only
+    /// extended with feasible conditions, so a constant `extra` needs no solver call.
+    fn feasible(&mut self, path: &[Bool<'ctx>], extra: &Bool<'ctx>) -> bool {
+        if let Some(value) = extra.simplify().as_bool() {
+            return value;
+        }
+        self.solver.push();
+        for condition in path {
+            self.solver.assert(condition);
+        }
+        self.solver.assert(extra);
+        let result = self.solver.check();
+        self.solver.pop(1);
+        result != SatResult::Unsat
+    }
+
+    /// Runs the current block of `state`, returning the states it continues in.
+    fn execute_block(&mut self, mut state: State<'ctx>) -> Vec<State<'ctx>> {
+        let bodies = self.bodies;
+        let frame = state.frames.last_mut().expect("a path has a frame");
+        let body = &bodies[frame.body];
+        let Some(block) = body.block(frame.block) else {
+            self.exploration.paths += 1;
+            return Vec::new();
+        };
+        let visits = frame.visits.entry(block.id).or_default();
+        *visits += 1;
+        if *visits > self.executor.unroll {
+            self.exploration.truncated += 1;
+            return Vec::new();
+        }
+
+        for (index, statement) in block.statements.iter().enumerate() {
+            let StatementKind::Assign(place, rvalue) = &statement.kind else {
+                continue;
+            };
+            let site = Site { block: block.id, index, method: "", span: statement.span.as_ref() };
+            let operands = match rvalue {
+                Rvalue::Use(operand) | Rvalue::Cast { operand, .. } | Rvalue::UnaryOp(_, operand) | Rvalue::Repeat(operand, _) => vec![operand],
+                Rvalue::BinaryOp(_, left, right) => vec![left, right],
+                Rvalue::Aggregate(_, operands) => operands.iter().collect(),
+                _ => Vec::new(),
+            };
+            for accessed in operands.into_iter().filter_map(Operand::place).chain([place]) {
+                self.dereference(&mut state, accessed, site);
+            }
+            let value = match rvalue {
+                Rvalue::BinaryOp(BinOp::Offset, pointer, count) => {
+                    let site = Site { block: block.id, index, method: "Offset", span: statement.span.as_ref() };
+                    let signed = operand_ty(body, count).is_some_and(is_signed);
+                    let pointer = self.operand(&mut state, pointer);
+                    let count = self.operand(&mut state, count);
+                    self.offset(&mut state, pointer, count, signed, false, &site)
+                }
+                rvalue => self.rvalue(&mut state, rvalue, place),
+            };
+            self.write(&mut state, place, value);
+        }
+        self.terminator(state, block)
+    }
+
+    fn goto(&self, mut state: State<'ctx>, target: BlockId) -> Vec<State<'ctx>> {
+        state.frames.last_mut().expect("a path has a frame").block = target;
+        vec![state]
+    }
+
+    fn terminator(&mut self, mut state: State<'ctx>, block: &'e BasicBlock) -> Vec<State<'ctx>> {
+        match &block.terminator {
+            Terminator::Goto(target) => self.goto(state, *target),
+            Terminator::FalseEdge { real, .. } | Terminator::FalseUnwind { real } => self.goto(state, *real),
+            Terminator::Drop { target, .. } => self.goto(state, *target),
+            Terminator::SwitchInt { discr, targets, otherwise } => {
+                let body = &self.bodies[state.frames.last().expect("a path has a frame").body];
+                let bits = operand_ty(body, discr).and_then(|ty| int_bits(ty, self.width())).unwrap_or(self.width());
+                // A discriminant that is not understood, e.g. the result of an unknown call,
+                // becomes a fresh symbol, so that every target stays reachable.
+                let value = self.operand(&mut state, discr);
+                let value = self.int(&mut state, &value, bits);
+                let conditions: Vec<Bool<'ctx>> = targets.iter()
+                    .map(|(value_of_target, _)| value._eq(&constant_bv(self.ctx(), *value_of_target, value.get_size())))
+                    .collect();
+                let negated: Vec<Bool<'ctx>> = conditions.iter().map(Bool::not).collect();
+                let none = Bool::and(self.ctx(), &negated.iter().collect::<Vec<_>>());
+                let branches = conditions.into_iter()
+                    .zip(targets.iter().map(|(_, target)| *target))
+                    .chain(std::iter::once((none, *otherwise)));
+                let mut next = Vec::new();
+                for (condition, target) in branches {
+                    if !self.feasible(&state.path, &condition) {
+                        continue;
+                    }
+                    let mut branch = state.clone();
+                    branch.path.push(condition);
+                    next.extend(self.goto(branch, target));
+                }
+                next
+            }
+            Terminator::Assert { cond, expected, target, .. } => {
+                // The other outcome panics, which is not an out-of-bounds access.
+                let value = self.operand(&mut state, cond);
+                let cond = self.boolean(&mut state, value);
+                let holds = if *expected { cond } else { cond.not() };
+                if !self.feasible(&state.path, &holds) {
+                    self.exploration.paths += 1;
+                    return Vec::new();
+                }
+                state.path.push(holds);
+                self.goto(state, *target)
+            }
+            Terminator::Call { destination, func, args, target, .. } => {
+                let site = Site { block: block.id, index: block.statements.len(), method: "", span: block.terminator_span.as_ref() };
+                for accessed in args.iter().filter_map(Operand::place).chain([destination]) {
+                    self.dereference(&mut state, accessed, site);
+                }
+                self.call(state, destination, func, args, *target, site)
+            }
+            Terminator::Return => {
+                let frame = state.frames.pop().expect("a path has a frame");
+                let Some((destination, target)) = frame.destination else {
+                    self.exploration.paths += 1;
+                    return Vec::new();
+                };
+                let value = frame.locals.get(&0).cloned().unwrap_or(Value::Unknown);
+                self.write(&mut state, &destination, value);
+                match target {
+                    Some(target) => self.goto(state, target),
+                    None => {
+                        self.exploration.paths += 1;
+                        Vec::new()
+                    }
+                }
+            }
+            Terminator::Resume | Terminator::Unreachable | Terminator::Other(_) => {
+                self.exploration.paths += 1;
+                Vec::new()
+            }
+        }
+    }
+
+    fn call(
+        &mut self,
+        mut state: State<'ctx>,
+        destination: &Place,
+        func: &str,
+        args: &[Operand],
+        target: Option<BlockId>,
+        site: Site<'e>,
+    ) -> Vec<State<'ctx>> {
+        let values: Vec<Value<'ctx>> = args.iter().map(|arg| self.operand(&mut state, arg)).collect();
+        if POINTER_ACCESSES.iter().any(|method| is_method(func, method)) {
+            for value in &values {
+                self.access(&mut state, value, site);
+            }
+        }
+
+        // Other bodies of the dump are executed in place, up to a call depth.
+        let callee = self.bodies.iter().position(|body| !body.blocks.is_empty() && callee_path(func) == body.name);
+        if let Some(callee) = callee
+            && state.frames.len() < self.executor.max_depth
+        {
+            let body = &self.bodies[callee];
+            let locals = body.args.iter().map(|(local, _)| *local).zip(values).collect();
+            state.frames.push(Frame {
+                body: callee,
+                block: 0,
+                locals,
+                destination: Some((destination.clone(), target)),
+                visits: HashMap::new(),
+            });
+            return vec![state];
+        }
+
+        let method = POINTER_METHODS.into_iter().find(|method| is_method(func, method));
+        let result = match method {
+            Some(method) if values.len() == 2 && matches!(values[0], Value::Pointer { .. }) => {
+                let body = &self.bodies[state.frames.last().expect("a path has a frame").body];
+                let signed = method.ends_with("offset") || operand_ty(body, &args[1]).is_some_and(is_signed);
+                let site = Site { method, ..site };
+                let (pointer, count) = (values[0].clone(), values[1].clone());
+                let backwards = method.ends_with("sub");
+                if method.contains("byte_") {
+                    // Byte offsets would need the element size.
+                    Value::Unknown
+                } else {
+                    self.offset(&mut state, pointer, count, signed, backwards, &site)
+                }
+            }
+            _ => self.summary(&mut state, destination, func, &values),
+        };
+        self.write(&mut state, destination, result);
+        match target {
+            Some(target) => self.goto(state, target),
+            None => {
+                self.exploration.paths += 1;
+                Vec::new()
+            }
+        }
+    }
+
+    /// Effect of a call to a function that is not executed: the summary of a known
+    /// standard library function, or an unknown result.
+    fn summary(&mut self, state: &mut State<'ctx>, destination: &Place, func: &str, args: &[Value<'ctx>]) -> Value<'ctx> {
+        let width = self.width();
+        let name = self.place_name(state, destination);
+        let buffer = |state: &State<'ctx>, index: usize| args.get(index).and_then(|arg| buffer_of(state, arg));
+        let is = |method: &str| is_method(func, method);
+
+        if is("from_elem") && args.len() == 2 {
+            let len = self.int(state, &args[1], width);
+            return self.allocate(state, &name, len.clone(), len);
+        }
+        // `vec![a, b, c]` on recent toolchains, with the length in the generic arguments.
+        if is("box_assume_init_into_vec_unsafe")
+            && let Some(len) = func.rsplit_once(", ").and_then(|(_, len)| len.strip_suffix('>')?.trim().parse::<u64>().ok())
+        {
+            let len = BV::from_u64(self.ctx(), len, width);
+            return self.allocate(state, &name, len.clone(), len);
+        }
+        if is("with_capacity") && args.len() == 1 {
+            let capacity = self.int(state, &args[0], width);
+            return self.allocate(state, &name, BV::from_u64(self.ctx(), 0, width), capacity);
+        }
+        if (func.starts_with("Vec::") || func.starts_with("String::")) && is("new") && args.is_empty() {
+            let zero = BV::from_u64(self.ctx(), 0, width);
+            return self.allocate(state, &name, zero.clone(), zero);
+        }
+        if func.contains("RangeInclusive") && is("new") && args.len() == 2 {
+            let start = self.int(state, &args[0], width);
+            let end = self.int(state, &args[1], start.get_size());
+            return Value::Range { start, end, exhausted: Some(Bool::from_bool(self.ctx(), false)) };
+        }
+        if is("index") || is("index_mut") || is("get") || is("get_mut") || is("clone") {
+            return Value::Unknown;
+        }
+        if (is("iter") || is("iter_mut") || is("into_iter")) && let Some(allocation) = buffer(state, 0) {
+            let index = BV::from_u64(self.ctx(), 0, width);
+            let end = state.allocations[allocation].len.clone();
+            return Value::Iter { allocation: Some(allocation), index, end, enumerate: false };
+        }
+        if is("enumerate") && let Some(Value::Iter { allocation, index, end, .. }) = args.first().cloned() {
+            return Value::Iter { allocation, index, end, enumerate: true };
+        }
+        if (is("chunks") || is("chunks_exact") || is("windows")) && args.len() == 2 && let Some(allocation) = buffer(state, 0) {
+            let len = state.allocations[allocation].len.clone();
+            let size = self.int(state, &args[1], width);
+            let one = BV::from_u64(self.ctx(), 1, width);
+            let count = if is("chunks") {
+                len.bvadd(&size).bvsub(&one).bvudiv(&size)
+            } else if is("chunks_exact") {
+                len.bvudiv(&size)
+            } else {
+                len.bvuge(&size).ite(&len.bvsub(&size).bvadd(&one), &BV::from_u64(self.ctx(), 0, width))
+            };
+            return Value::Iter { allocation: None, index: BV::from_u64(self.ctx(), 0, width), end: count.simplify(), enumerate: false };
+        }
+        if is("into_vec") || is("into_iter") || is("deref") || is("deref_mut") || is("as_slice") || is("as_mut_slice")
+            || is("into_boxed_slice") || is("as_bytes")
+        {
+            return args.first().cloned().unwrap_or(Value::Unknown);
+        }
+        if (is("as_ptr") || is("as_mut_ptr")) && let Some(allocation) = buffer(state, 0) {
+            let offset = BV::from_u64(self.ctx(), 0, width + POSITION_BITS);
+            return Value::Pointer { allocation, offset, name: Some(name), wrapped: None };
+        }
+        if is("len") && let Some(allocation) = buffer(state, 0) {
+            return Value::Int(state.allocations[allocation].len.clone());
+        }
+        if is("capacity") && let Some(allocation) = buffer(state, 0) {
+            return Value::Int(state.allocations[allocation].capacity.clone());
+        }
+        if is("is_empty") && let Some(allocation) = buffer(state, 0) {
+            let len = &state.allocations[allocation].len;
+            return Value::Bool(len._eq(&BV::from_u64(self.ctx(), 0, width)));
+        }
+        if (is("min") || is("max")) && args.len() == 2 {
+            let left = self.int(state, &args[0], width);
+            let right = self.int(state, &args[1], left.get_size());
+            let left_smaller = left.bvult(&right);
+            return Value::Int(if is("min") { left_smaller.ite(&left, &right) } else { left_smaller.ite(&right, &left) });
+        }
+        if is("next") && let Some(Value::Ref { frame, local, .. }) = args.first() {
+            let (frame, local) = (*frame, *local);
+            let iterator = state.frames[frame].locals.get(&local).cloned();
+            let (has_next, item, advanced) = match iterator {
+                Some(Value::Range { start, end, exhausted: None }) => {
+                    let has_next = start.bvult(&end);
+                    let next = has_next.ite(&start.bvadd(&BV::from_u64(self.ctx(), 1, start.get_size())), &start);
+                    (has_next, Value::Int(start), Value::Range { start: next.simplify(), end, exhausted: None })
+                }
+                // `start..=end` stops after yielding `end`, even at the maximum value.
+                Some(Value::Range { start, end, exhausted: Some(exhausted) }) => {
+                    let has_next = Bool::and(self.ctx(), &[&exhausted.not(), &start.bvule(&end)]);
+                    let more = start.bvult(&end);
+                    let step = Bool::and(self.ctx(), &[&has_next, &more]);
+                    let next = step.ite(&start.bvadd(&BV::from_u64(self.ctx(), 1, start.get_size())), &start);
+                    let exhausted = Bool::or(self.ctx(), &[&exhausted, &Bool::and(self.ctx(), &[&has_next, &more.not()])]);
+                    (has_next, Value::Int(start), Value::Range { start: next.simplify(), end, exhausted: Some(exhausted.simplify()) })
+                }
+                Some(Value::Iter { allocation, index, end, enumerate }) => {
+                    let has_next = index.bvult(&end);
+                    let element = allocation.map_or(Value::Unknown, |allocation| self.element(state, allocation, &index));
+                    let item = if enumerate { Value::Adt { variant: None, fields: vec![Value::Int(index.clone()), element] } } else { element };
+                    let next = has_next.ite(&index.bvadd(&BV::from_u64(self.ctx(), 1, width)), &index);
+                    (has_next, item, Value::Iter { allocation, index: next.simplify(), end, enumerate })
+                }
+                _ => return Value::Unknown,
+            };
+            state.frames[frame].locals.insert(local, advanced);
+            let variant = has_next.ite(&BV::from_u64(self.ctx(), 1, width), &BV::from_u64(self.ctx(), 0, width));
+            return Value::Adt { variant: Some(variant), fields: vec![item] };
+        }
+
+        // `Vec` methods changing the length.
+        if let Some(allocation) = buffer(state, 0) {
+            let zero = BV::from_u64(self.ctx(), 0, width);
+            let one = BV::from_u64(self.ctx(), 1, width);
+            let Allocation { len, capacity, .. } = state.allocations[allocation].clone();
+            let grown = |len: &BV<'ctx>| len.bvugt(&capacity).ite(len, &capacity);
+            let updated = if is("push") && args.len() == 2 {
+                let len = len.bvadd(&one);
+                Some((len.clone(), grown(&len)))
+            } else if is("pop") {
+                Some((len.bvugt(&zero).ite(&len.bvsub(&one), &zero), capacity.clone()))
+            } else if is("clear") {
+                Some((zero.clone(), capacity.clone()))
+            } else if (is("truncate") || is("set_len") || is("resize")) && args.len() >= 2 {
+                let n = self.int(state, &args[1], width);
+                if is("truncate") {
+                    Some((n.bvult(&len).ite(&n, &len), capacity.clone()))
+                } else if is("set_len") {
+                    Some((n, capacity.clone()))
+                } else {
+                    Some((n.clone(), grown(&n)))
+                }
+            } else if (is("reserve") || is("reserve_exact")) && args.len() == 2 {
+                let extra = self.int(state, &args[1], width);
+                Some((len.clone(), grown(&len.bvadd(&extra))))
+            } else if (is("extend_from_slice") || is("push_str")) && let Some(other) = buffer(state, 1) {
+                let len = len.bvadd(&state.allocations[other].len);
+                Some((len.clone(), grown(&len)))
+            } else {
+                None
+            };
+            if let Some((len, capacity)) = updated {
+                state.allocations[allocation].len = len;
+                state.allocations[allocation].capacity = capacity;
+                return if is("pop") { Value::Adt { variant: None, fields: vec![Value::Unknown] } } else { Value::Unknown };
+            }
+        }
+
+        // An unknown function may change the length of any buffer it borrows mutably.
+        for arg in args {
+            if let Value::Ref { mutable: true, .. } = arg
+                && let Some(allocation) = buffer_of(state, arg)
+            {
+                let name = format!("{}.len()", state.allocations[allocation].name);
+                let len = self.fresh_int(state, &name, width, false);
+                let capacity = state.allocations[allocation].capacity.clone();
+                state.allocations[allocation].len = len.clone();
+                state.allocations[allocation].capacity = len.bvugt(&capacity).ite(&len, &capacity);
+            }
+        }
+        Value::Unknown
+    }
+
+    /// Moves `pointer` by `count` elements, reporting the offset when it can leave the
+    /// allocation.
+    fn offset(
+        &mut self,
+        state: &mut State<'ctx>,
+        pointer: Value<'ctx>,
+        count: Value<'ctx>,
+        signed: bool,
+        backwards: bool,
+        site: &Site,
+    ) -> Value<'ctx> {
+        let Value::Pointer { allocation, offset, name, .. } = pointer else {
+            return Value::Unknown;
+        };
+        let width = self.width();
+        let count = match &count {
+            Value::Int(bv) => resize(bv, width, signed),
+            other => self.int(state, other, width),
+        };
+        let count = resize(&count, width + POSITION_BITS, signed);
+        let count = if backwards { count.bvneg() } else { count };
+        let moved = offset.bvadd(&count);
+        // `wrapping_*` may leave the allocation; only an access through the result has to
+        // stay within it.
+        if site.method.starts_with("wrapping_") {
+            return Value::Pointer { allocation, offset: moved, name, wrapped: Some(site.method) };
+        }
+        self.check_bounds(state, allocation, &moved, &name, site);
+        Value::Pointer { allocation, offset: moved, name, wrapped: None }
+    }
+
+    /// Checks a pointer moved by `site.method` to `moved` within its allocation, reporting
+    /// it once per site when it can be out of bounds.
+    fn check_bounds(&mut self, state: &mut State<'ctx>, allocation: usize, moved: &BV<'ctx>, name: &Option<String>, site: &Site) {
+        let width = self.width();
+        let capacity = state.allocations[allocation].capacity.zero_ext(POSITION_BITS);
+        let zero = BV::from_u64(self.ctx(), 0, width + POSITION_BITS);
+        let out_of_bounds = Bool::or(self.ctx(), &[&moved.bvslt(&zero), &moved.bvsge(&capacity)]);
+
+        let function = self.bodies[state.frames.last().expect("a path has a frame").body].name.clone();
+        let key = (function.clone(), site.block, site.index);
+        if !self.reported.contains(&key) && out_of_bounds.simplify().as_bool() != Some(false) {
+            self.solver.push();
+            for condition in &state.path {
+                self.solver.assert(condition);
+            }
+            self.solver.assert(&out_of_bounds);
+            // Small inputs make readable counterexamples; larger ones are used when needed.
+            let small: Vec<Bool<'ctx>> = state.inputs.iter()
+                .filter_map(|(_, value, signed)| match value {
+                    Value::Int(term) => Some(small(term, *signed)),
+                    _ => None,
+                })
+                .collect();
+            let result = match self.solver.check_assumptions(&small) {
+                SatResult::Sat => SatResult::Sat,
+                _ => self.solver.check(),
+            };
+            if result == SatResult::Sat
+                && let Some(model) = self.solver.get_model()
+            {
+                let mut inputs = BTreeMap::new();
+                for (input, value, signed) in &state.inputs {
+                    let value = match value {
+                        Value::Int(term) => model_value(&model, term, *signed),
+                        Value::Bool(term) => model.eval(term, true).and_then(|value| value.as_bool()).map(i128::from),
+                        _ => None,
+                    };
+                    if let Some(value) = value {
+                        inputs.entry(input.clone()).or_insert(value);
+                    }
+                }
+                let allocation = &state.allocations[allocation];
+                let finding = SymbolicFinding {
+                    function,
+                    block: site.block,
+                    method: site.method.to_string(),
+                    buffer_name: allocation.name.clone(),
+                    pointer_name: name.clone(),
+                    offset: model_value(&model, moved, true).unwrap_or_default(),
+                    buffer_len: model_value(&model, &allocation.capacity, false).and_then(|len| u64::try_from(len).ok()).unwrap_or_default(),
+                    inputs,
+                    span: site.span.cloned(),
+                };
+                self.exploration.findings.push(finding);
+                self.reported.insert(key);
+            }
+            self.solver.pop(1);
+        }
+
+        // Later accesses are checked on the paths that stay in bounds, or past this
+        // one when it is out of bounds on every path.
+        let in_bounds = out_of_bounds.not();
+        if self.feasible(&state.path, &in_bounds) {
+            state.path.push(in_bounds);
+        }
+    }
+
+    /// Checks a pointer moved by a `wrapping_*` method where `place` dereferences it.
+    fn dereference(&mut self, state: &mut State<'ctx>, place: &Place, site: Site) {
+        if place.projection.first() != Some(&PlaceElem::Deref) {
+            return;
+        }
+        let frame = state.frames.len() - 1;
+        if let Some(pointer) = state.frames[frame].locals.get(&place.local).cloned() {
+            self.access(state, &pointer, site);
+        }
+    }
+
+    /// Checks a pointer moved by a `wrapping_*` method where the pointee is accessed.
+    fn access(&mut self, state: &mut State<'ctx>, pointer: &Value<'ctx>, site: Site) {
+        if let Value::Pointer { allocation, offset, name, wrapped: Some(method) } = pointer {
+            self.check_bounds(state, *allocation, offset, name, &Site { method, ..site });
+        }
+    }
+
+    fn rvalue(&mut self, state: &mut State<'ctx>, rvalue: &Rvalue, target: &Place) -> Value<'ctx> {
+        let width = self.width();
+        let frame = state.frames.len() - 1;
+        let body = &self.bodies[state.frames[frame].body];
+        match rvalue {
+            Rvalue::Use(operand) => self.operand(state, operand),
+            Rvalue::Ref { mutable, place } | Rvalue::RawPtr { mutable, place } => {
+                let value = if let Some(local) = place.as_local() {
+                    Value::Ref { frame, local, mutable: *mutable }
+                } else if place.projection == [PlaceElem::Deref] {
+                    // A reborrow `&(*_2)` is `_2` itself.
+                    self.read(state, &Place { local: place.local, projection: Vec::new() })
+                } else {
+                    Value::Unknown
+                };
+                // Writes through the borrow are not followed.
+                if *mutable && let Some(allocation) = buffer_of(state, &value) {
+                    state.allocations[allocation].elements.clear();
+                }
+                value
+            }
+            Rvalue::Len(place) => match self.read(state, place) {
+                Value::Buffer(allocation) => Value::Int(state.allocations[allocation].len.clone()),
+                _ => Value::Unknown,
+            },
+            Rvalue::UnaryOp(UnOp::PtrMetadata, operand) => {
+                let value = self.operand(state, operand);
+                match buffer_of(state, &value) {
+                    Some(allocation) => Value::Int(state.allocations[allocation].len.clone()),
+                    None => Value::Unknown,
+                }
+            }
+            Rvalue::UnaryOp(op, operand) => match (op, self.operand(state, operand)) {
+                (UnOp::Not, Value::Bool(value)) => Value::Bool(value.not()),
+                (UnOp::Not, Value::Int(value)) => Value::Int(value.bvnot()),
+                (UnOp::Neg, Value::Int(value)) => Value::Int(value.bvneg()),
+                _ => Value::Unknown,
+            },
+            Rvalue::Discriminant(place) => match self.read(state, place) {
+                Value::Adt { variant: Some(variant), .. } => Value::Int(variant),
+                _ => Value::Unknown,
+            },
+            Rvalue::Cast { operand, ty, kind } => {
+                let value = self.operand(state, operand);
+                let source_ty = operand_ty(body, operand);
+                match value {
+                    Value::Int(value) if kind == "IntToInt" => match int_bits(ty, width) {
+                        Some(bits) => Value::Int(resize(&value, bits, source_ty.is_some_and(is_signed))),
+                        None => Value::Int(value),
+                    },
+                    // A pointer cast to another pointee counts other elements.
+                    Value::Pointer { .. } if kind == "PtrToPtr" && source_ty.and_then(raw_pointee) != raw_pointee(ty) => Value::Unknown,
+                    // `Box<[T; N]>` unsized to `Box<[T]>` by `vec![..]`.
+                    Value::Unknown => match source_ty.map(pointee).and_then(array_len) {
+                        Some(len) => {
+                            let name = self.place_name(state, target);
+                            let len = BV::from_u64(self.ctx(), len as u64, width);
+                            self.allocate(state, &name, len.clone(), len)
+                        }
+                        None => Value::Unknown,
+                    },
+                    value => value,
+                }
+            }
+            Rvalue::BinaryOp(op, left, right) => {
+                let signed = operand_ty(body, left).is_some_and(is_signed);
+                let left_bits = operand_ty(body, left).and_then(|ty| int_bits(ty, width)).unwrap_or(width);
+                let left = self.operand(state, left);
+                let right = self.operand(state, right);
+                self.binary(state, *op, left, right, signed, left_bits)
+            }
+            Rvalue::Repeat(_, count) => match count.trim().trim_end_matches("_usize").parse::<u64>() {
+                Ok(len) => {
+                    let name = self.place_name(state, target);
+                    let len = BV::from_u64(self.ctx(), len, width);
+                    self.allocate(state, &name, len.clone(), len)
+                }
+                Err(_) => Value::Unknown,
+            },
+            Rvalue::Aggregate(kind, operands) => {
+                let fields: Vec<Value<'ctx>> = operands.iter().map(|operand| self.operand(state, operand)).collect();
+                if kind == "array" {
+                    let name = self.place_name(state, target);
+                    let len = BV::from_u64(self.ctx(), fields.len() as u64, width);
+                    let array = self.allocate(state, &name, len.clone(), len);
+                    state.allocations.last_mut().expect("just allocated").elements = fields;
+                    return array;
+                }
+                if kind.contains("Range") && !kind.contains("Inclusive") && fields.len() == 2 {
+                    let start = self.int(state, &fields[0], width);
+                    let end = self.int(state, &fields[1], start.get_size());
+                    return Value::Range { start, end, exhausted: None };
+                }
+                let variant = variant_index(kind).map(|index| BV::from_u64(self.ctx(), index, width));
+                Value::Adt { variant, fields }
+            }
+            Rvalue::NullaryOp(..) | Rvalue::Other(_) => Value::Unknown,
+        }
+    }
+
+    fn binary(&mut self, state: &mut State<'ctx>, op: BinOp, left: Value<'ctx>, right: Value<'ctx>, signed: bool, bits: u32) -> Value<'ctx> {
+        if let (Value::Bool(left), Value::Bool(right)) = (&left, &right) {
+            return match op {
+                BinOp::BitAnd => Value::Bool(Bool::and(self.ctx(), &[left, right])),
+                BinOp::BitOr => Value::Bool(Bool::or(self.ctx(), &[left, right])),
+                BinOp::BitXor | BinOp::Ne => Value::Bool(left.xor(right)),
+                BinOp::Eq => Value::Bool(left._eq(right)),
+                _ => Value::Unknown,
+            };
+        }
+        if matches!(op, BinOp::Offset | BinOp::Cmp) {
+            return Value::Unknown;
+        }
+        let left = self.int(state, &left, bits);
+        let right = self.int(state, &right, left.get_size());
+        let right = resize(&right, left.get_size(), signed);
+        let with_overflow = |result: BV<'ctx>, no_overflow: Bool<'ctx>| Value::Adt {
+            variant: None,
+            fields: vec![Value::Int(result), Value::Bool(no_overflow.not())],
+        };
+        match op {
+            BinOp::Add | BinOp::AddUnchecked => Value::Int(left.bvadd(&right)),
+            BinOp::Sub | BinOp::SubUnchecked => Value::Int(left.bvsub(&right)),
+            BinOp::Mul | BinOp::MulUnchecked => Value::Int(left.bvmul(&right)),
+            BinOp::AddWithOverflow => {
+                let no_overflow = if signed {
+                    Bool::and(self.ctx(), &[&left.bvadd_no_overflow(&right, true), &left.bvadd_no_underflow(&right)])
+                } else {
+                    left.bvadd_no_overflow(&right, false)
+                };
+                with_overflow(left.bvadd(&right), no_overflow)
+            }
+            BinOp::SubWithOverflow => {
+                let no_overflow = if signed {
+                    Bool::and(self.ctx(), &[&left.bvsub_no_overflow(&right), &left.bvsub_no_underflow(&right, true)])
+                } else {
+                    left.bvsub_no_underflow(&right, false)
+                };
+                with_overflow(left.bvsub(&right), no_overflow)
+            }
+            BinOp::MulWithOverflow => {
+                let no_overflow = if signed {
+                    Bool::and(self.ctx(), &[&left.bvmul_no_overflow(&right, true), &left.bvmul_no_underflow(&right)])
+                } else {
+                    left.bvmul_no_overflow(&right, false)
+                };
+                with_overflow(left.bvmul(&right), no_overflow)
+            }
+            BinOp::Div => Value::Int(if signed { left.bvsdiv(&right) } else { left.bvudiv(&right) }),
+            BinOp::Rem => Value::Int(if signed { left.bvsrem(&right) } else { left.bvurem(&right) }),
+            BinOp::BitAnd => Value::Int(left.bvand(&right)),
+            BinOp::BitOr => Value::Int(left.bvor(&right)),
+            BinOp::BitXor => Value::Int(left.bvxor(&right)),
+            BinOp::Shl | BinOp::ShlUnchecked => Value::Int(left.bvshl(&right)),
+            BinOp::Shr | BinOp::ShrUnchecked => Value::Int(if signed { left.bvashr(&right) } else { left.bvlshr(&right) }),
+            BinOp::Eq => Value::Bool(left._eq(&right)),
+            BinOp::Ne => Value::Bool(left._eq(&right).not()),
+            BinOp::Lt => Value::Bool(if signed { left.bvslt(&right) } else { left.bvult(&right) }),
+            BinOp::Le => Value::Bool(if signed { left.bvsle(&right) } else { left.bvule(&right) }),
+            BinOp::Gt => Value::Bool(if signed { left.bvsgt(&right) } else { left.bvugt(&right) }),
+            BinOp::Ge => Value::Bool(if signed { left.bvsge(&right) } else { left.bvuge(&right) }),
+            BinOp::Offset | BinOp::Cmp => Value::Unknown,
+        }
+    }
+
+    fn operand(&mut self, state: &mut State<'ctx>, operand: &Operand) -> Value<'ctx> {
+        match operand {
+            Operand::Copy(place) | Operand::Move(place) => self.read(state, place),
+            Operand::Constant(constant) => match (&constant.ty, constant.value) {
+                (Some(ty), Some(value)) if ty == "bool" => Value::Bool(Bool::from_bool(self.ctx(), value != 0)),
+                (Some(ty), Some(value)) => Value::Int(constant_bv(self.ctx(), value, int_bits(ty, self.width()).unwrap_or(self.width()))),
+                _ => match variant_index(&constant.text) {
+                    Some(index) => Value::Adt { variant: Some(BV::from_u64(self.ctx(), index, self.width())), fields: Vec::new() },
+                    None => Value::Unknown,
+                },
+            },
+        }
+    }
+
+    /// The integer a value stands for, as a fresh symbol of `bits` bits when unknown.
+    fn int(&mut self, state: &mut State<'ctx>, value: &Value<'ctx>, bits: u32) -> BV<'ctx> {
+        match value {
+            Value::Int(value) => value.clone(),
+            Value::Bool(value) => value.ite(&BV::from_u64(self.ctx(), 1, bits), &BV::from_u64(self.ctx(), 0, bits)),
+            Value::Adt { variant: Some(variant), .. } => variant.clone(),
+            _ => self.fresh_int(state, UNKNOWN, bits, false),
+        }
+    }
+
+    fn boolean(&mut self, state: &mut State<'ctx>, value: Value<'ctx>) -> Bool<'ctx> {
+        match value {
+            Value::Bool(value) => value,
+            Value::Int(value) => value._eq(&BV::from_u64(self.ctx(), 0, value.get_size())).not(),
+            _ => self.fresh_bool(state, UNKNOWN),
+        }
+    }
+
+    /// Value of element `index` of an allocation, when the elements are known.
+    fn element(&self, state: &State<'ctx>, allocation: usize, index: &BV<'ctx>) -> Value<'ctx> {
+        let elements = &state.allocations[allocation].elements;
+        if let Some(index) = index.simplify().as_u64() {
+            return usize::try_from(index).ok().and_then(|index| elements.get(index)).cloned().unwrap_or(Value::Unknown);
+        }
+        // A symbolic index selects among integer elements.
+        let mut selected: Option<BV<'ctx>> = None;
+        for (position, element) in elements.iter().enumerate().rev() {
+            let Value::Int(element) = element else {
+                return Value::Unknown;
+            };
+            let at = index._eq(&BV::from_u64(self.ctx(), position as u64, index.get_size()));
+            selected = Some(match selected {
+                Some(rest) if rest.get_size() == element.get_size() => at.ite(element, &rest),
+                Some(_) => return Value::Unknown,
+                None => element.clone(),
+            });
+        }
+        selected.map_or(Value::Unknown, Value::Int)
+    }
+
+    /// Name of the user variable a place is bound to, e.g. `buffer` for `_1`.
+    fn place_name(&self, state: &State<'ctx>, place: &Place) -> String {
+        let body = &self.bodies[state.frames.last().expect("a path has a frame").body];
+        body.local_name(place.local).map(str::to_string).unwrap_or_else(|| format!("_{}", place.local))
+    }
+
+    fn read(&mut self, state: &mut State<'ctx>, place: &Place) -> Value<'ctx> {
+        let frame = state.frames.len() - 1;
+        let body = &self.bodies[state.frames[frame].body];
+        let mut value = match state.frames[frame].locals.get(&place.local) {
+            Some(value) => value.clone(),
+            None => {
+                // A local read before any assignment we understood holds an unknown value,
+                // kept so that every later read sees the same one.
+                let name = self.place_name(state, place);
+                let value = match body.local_ty(place.local) {
+                    Some(ty) => self.input(state, &name, ty),
+                    None => Value::Unknown,
+                };
+                state.frames[frame].locals.insert(place.local, value.clone());
+                value
+            }
+        };
+        for elem in &place.projection {
+            value = match (elem, value) {
+                (PlaceElem::Deref, Value::Ref { frame, local, .. }) => {
+                    state.frames[frame].locals.get(&local).cloned().unwrap_or(Value::Unknown)
+                }
+                (PlaceElem::Deref, Value::Buffer(allocation)) => Value::Buffer(allocation),
+                // A reference to an element is represented by the element's value.
+                (PlaceElem::Deref, value @ (Value::Int(_) | Value::Bool(_))) => value,
+                (PlaceElem::Index(index), Value::Buffer(allocation)) => {
+                    let index = self.read(state, &Place { local: *index, projection: Vec::new() });
+                    let index = self.int(state, &index, self.width());
+                    self.element(state, allocation, &index)
+                }
+                (PlaceElem::ConstantIndex { offset, from_end: false, .. }, Value::Buffer(allocation)) => {
+                    let index = BV::from_u64(self.ctx(), *offset, self.width());
+                    self.element(state, allocation, &index)
+                }
+                (PlaceElem::Downcast(_), value) => value,
+                (PlaceElem::Field(index, ty), Value::Adt { fields, .. }) => match fields.get(*index) {
+                    Some(Value::Unknown) | None => self.input(state, UNKNOWN, ty),
+                    Some(field) => field.clone(),
+                },
+                (PlaceElem::Field(0, _), Value::Range { start, .. }) => Value::Int(start),
+                (PlaceElem::Field(1, _), Value::Range { end, .. }) => Value::Int(end),
+                (PlaceElem::Field(_, ty), _) => self.input(state, UNKNOWN, ty),
+                _ => Value::Unknown,
+            };
+        }
+        value
+    }
+
+    fn write(&mut self, state: &mut State<'ctx>, place: &Place, value: Value<'ctx>) {
+        let frame = state.frames.len() - 1;
+        // A pointer stored in a named variable is reported under that name.
+        let value = match value {
+            Value::Pointer { allocation, offset, name, wrapped } if place.projection.is_empty() => {
+                let body = &self.bodies[state.frames[frame].body];
+                let name = body.local_name(place.local).map(str::to_string).or(name);
+                Value::Pointer { allocation, offset, name, wrapped }
+            }
+            value => value,
+        };
+        write_in(state, frame, place.local, &place.projection, value);
+    }
+}
+
+/// Stores `value` at `local` of `frame` with `projection` applied. Writes through raw
+/// pointers and into elements of buffers only forget the elements' values.
+fn write_in<'ctx>(state: &mut State<'ctx>, frame: usize, local: Local, projection: &[PlaceElem], value: Value<'ctx>) {
+    match projection.split_first() {
+        None => {
+            state.frames[frame].locals.insert(local, value);
+        }
+        Some((PlaceElem::Deref, rest)) => match state.frames[frame].locals.get(&local).cloned() {
+            Some(Value::Ref { frame, local, .. }) => write_in(state, frame, local, rest, value),
+            Some(Value::Pointer { allocation, .. } | Value::Buffer(allocation)) => state.allocations[allocation].elements.clear(),
+            _ => {}
+        },
+        Some(_) => {
+            let current = state.frames[frame].locals.remove(&local).unwrap_or(Value::Unknown);
+            if let Value::Buffer(allocation) = current {
+                state.allocations[allocation].elements.clear();
+            }
+            let updated = set_field(current, projection, value);
+            state.frames[frame].locals.insert(local, updated);
+        }
+    }
+}
+
+/// `container` with the field at `projection` replaced by `value`.
+fn set_field<'ctx>(container: Value<'ctx>, projection: &[PlaceElem], value: Value<'ctx>) -> Value<'ctx> {
+    match projection.split_first() {
+        None => value,
+        Some((PlaceElem::Downcast(_), rest)) => set_field(container, rest, value),
+        Some((PlaceElem::Field(index, _), rest)) => {
+            let (variant, mut fields) = match container {
+                Value::Adt { variant, fields } => (variant, fields),
+                _ => (None, Vec::new()),
+            };
+            if fields.len() <= *index {
+                fields.resize(*index + 1, Value::Unknown);
+            }
+            let field = std::mem::replace(&mut fields[*index], Value::Unknown);
+            fields[*index] = set_field(field, rest, value);
+            Value::Adt { variant, fields }
+        }
+        Some(_) => container,
+    }
+}
+
+/// Allocation a value refers to, through references.
+fn buffer_of(state: &State<'_>, value: &Value<'_>) -> Option<usize> {
+    match value {
+        Value::Buffer(allocation) => Some(*allocation),
+        Value::Ref { frame, local, .. } => buffer_of(state, state.frames.get(*frame)?.locals.get(local)?),
+        _ => None,
+    }
+}
+
+/// Discriminant of an `Option` or `Result` variant named by an aggregate or a constant,
+/// e.g. `1` for `Option::<usize>::Some`.
+fn variant_index(name: &str) -> Option<u64> {
+    match name.rsplit("::").next()? {
+        "None" | "Ok" => Some(0),
+        "Some" | "Err" => Some(1),
+        _ => None,
+    }
+}
+
+/// Width in bits of an integer type, `None` for other types.
+fn int_bits(ty: &str, width: u32) -> Option<u32> {
+    Some(match ty.trim() {
+        "u8" | "i8" => 8,
+        "u16" | "i16" => 16,
+        "u32" | "i32" | "char" => 32,
+        "u64" | "i64" => 64,
+        "u128" | "i128" => 128,
+        "usize" | "isize" => width,
+        _ => return None,
+    })
+}
+
+fn is_signed(ty: &str) -> bool {
+    matches!(ty.trim(), "i8" | "i16" | "i32" | "i64" | "i128" | "isize")
+}
+
+/// Type behind references and boxes, e.g. `[u8]` for `&mut [u8]` or `Box<[u8; 4]>`.
+fn pointee(ty: &str) -> &str {
+    let mut ty = ty.trim();
+    loop {
+        if let Some(rest) = ty.strip_prefix('&') {
+            ty = rest.trim_start();
+            if ty.starts_with('\'') {
+                ty = ty.split_once(' ').map_or(ty, |(_, rest)| rest);
+            }
+            ty = ty.strip_prefix("mut ").unwrap_or(ty).trim_start();
+        } else if let Some(inner) = ty.strip_prefix("std::boxed::Box<").or_else(|| ty.strip_prefix("Box<"))
+            .and_then(|inner| inner.strip_suffix('>'))
+        {
+            ty = inner.trim();
+        } else {
+            return ty;
+        }
+    }
+}
+
+/// `T` of `*mut T` or `*const T`.
+fn raw_pointee(ty: &str) -> Option<&str> {
+    let ty = ty.trim();
+    ty.strip_prefix("*mut ").or_else(|| ty.strip_prefix("*const ")).map(str::trim)
+}
+
+/// Type of an operand as printed in the dump, when it is known.
+fn operand_ty<'b>(body: &'b MirBody, operand: &'b Operand) -> Option<&'b str> {
+    match operand {
+        Operand::Constant(constant) => constant.ty.as_deref(),
+        Operand::Copy(place) | Operand::Move(place) => match place.projection.last() {
+            None => body.local_ty(place.local),
+            Some(PlaceElem::Field(_, ty)) => Some(ty),
+            Some(_) => None,
+        },
+    }
+}
+
+fn constant_bv(ctx: &Context, value: i128, bits: u32) -> BV<'_> {
+    match u64::try_from(value) {
+        Ok(value) => BV::from_u64(ctx, value, bits),
+        Err(_) => BV::from_i64(ctx, value as i64, bits),
+    }
+}
+
+/// `value` with `bits` bits, truncated or extended by sign or with zeros.
+fn resize<'ctx>(value: &BV<'ctx>, bits: u32, signed: bool) -> BV<'ctx> {
+    let size = value.get_size();
+    match size.cmp(&bits) {
+        std::cmp::Ordering::Equal => value.clone(),
+        std::cmp::Ordering::Greater => value.extract(bits - 1, 0),
+        std::cmp::Ordering::Less if signed => value.sign_ext(bits - size),
+        std::cmp::Ordering::Less => value.zero_ext(bits - size),
+    }
+}
+
+/// `term` lies within a thousand of zero.
+fn small<'ctx>(term: &BV<'ctx>, signed: bool) -> Bool<'ctx> {
+    let ctx = term.get_ctx();
+    let bits = term.get_size();
+    if bits <= 11 {
+        return Bool::from_bool(ctx, true);
+    }
+    let limit = BV::from_u64(ctx, 1000, bits);
+    if signed {
+        Bool::and(ctx, &[&term.bvsle(&limit), &term.bvsge(&limit.bvneg())])
+    } else {
+        term.bvule(&limit)
+    }
+}
+
+/// Value of `term` in `model`, read as a two's complement number when `signed` is set.
+fn model_value<'ctx>(model: &Model<'ctx>, term: &BV<'ctx>, signed: bool) -> Option<i128> {
+    let value = model.eval(term, true)?;
+    let top = value.get_size() - 1;
+    if signed && value.extract(top, top).simplify().as_u64() == Some(1) {
+        return value.bvneg().simplify().as_u64().map(|magnitude| -i128::from(magnitude));
+    }
+    value.as_u64().map(i128::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mir_parser::parse_mir;
+    use std::fs;
+
+    const GUARDED_READS: &str = r#"
+fn read_at(_1: &[u8], _2: usize) -> u8 {
+    debug buf => _1;
+    debug n => _2;
+    let mut _0: u8;
+    let mut _3: bool;
+    let mut _4: *const u8;
+    let mut _5: *const u8;
+
+    bb0: {
+        _3 = Lt(copy _2, const 16_usize);
+        switchInt(move _3) -> [0: bb3, otherwise: bb1];
+    }
+
+    bb1: {
+        _4 = core::slice::<impl [u8]>::as_ptr(copy _1) -> [return: bb2, unwind continue];
+    }
+
+    bb2: {
+        _5 = Offset(copy _4, copy _2);
+        _0 = copy (*_5);
+        return;
+    }
+
+    bb3: {
+        _0 = const 0_u8;
+        return;
+    }
+}
+
+fn checked_read(_1: &[u8], _2: usize) -> u8 {
+    debug buf => _1;
+    debug n => _2;
+    let mut _0: u8;
+    let mut _3: usize;
+    let mut _4: bool;
+
+    bb0: {
+        _3 = PtrMetadata(copy _1);
+        _4 = Lt(copy _2, copy _3);
+        switchInt(move _4) -> [0: bb2, otherwise: bb1];
+    }
+
+    bb1: {
+        _0 = read_at(copy _1, copy _2) -> [return: bb2, unwind continue];
+    }
+
+    bb2: {
+        return;
+    }
+}
+"#;
+
+    #[test]
+    fn test_counterexample_inputs() {
+        let bodies = parse_mir(GUARDED_READS).unwrap();
+        let ctx = Context::new(&z3::Config::new());
+        let exploration = SymbolicExecutor::new(&ctx).explore(&bodies);
+
+        // Only the unchecked entry reaches the offset out of bounds.
+        assert_eq!(exploration.findings.len(), 1);
+        let finding = &exploration.findings[0];
+        assert_eq!((finding.function.as_str(), finding.block, finding.method.as_str()), ("read_at", 2, "Offset"));
+        assert_eq!(finding.buffer_name, "buf");
+        let (n, len) = (finding.inputs["n"], finding.inputs["buf.len()"]);
+        assert!(n < 16 && n >= len, "{:?}", finding.inputs);
+        assert_eq!((finding.offset, finding.buffer_len), (n, len as u64));
+        assert_eq!(exploration.truncated, 0);
+    }
+
+    #[test]
+    fn test_unknown_discriminant() {
+        let bodies = parse_mir(r#"
+fn checked_by_call(_1: &[u8], _2: usize) -> u8 {
+    debug buf => _1;
+    debug n => _2;
+    let mut _0: u8;
+    let mut _3: bool;
+    let mut _4: *const u8;
+    let mut _5: *const u8;
+
+    bb0: {
+        _3 = check(copy _2) -> [return: bb1, unwind continue];
+    }
+
+    bb1: {
+        switchInt(move _3) -> [0: bb4, otherwise: bb2];
+    }
+
+    bb2: {
+        _4 = core::slice::<impl [u8]>::as_ptr(copy _1) -> [return: bb3, unwind continue];
+    }
+
+    bb3: {
+        _5 = Offset(copy _4, const 100_usize);
+        _0 = copy (*_5);
+        return;
+    }
+
+    bb4: {
+        _0 = const 0_u8;
+        return;
+    }
+}
+"#).unwrap();
+        let ctx = Context::new(&z3::Config::new());
+        let exploration = SymbolicExecutor::new(&ctx).explore(&bodies);
+
+        // `check` is not in the dump, so its result may be either.
+        assert_eq!(exploration.paths, 2);
+        assert_eq!(exploration.findings.len(), 1);
+        assert_eq!((exploration.findings[0].block, exploration.findings[0].offset), (3, 100));
+    }
+
+    #[test]
+    fn test_wrapping_offsets() {
+        let bodies = parse_mir(r#"
+fn wrap(_1: &[u8]) -> u8 {
+    debug buf => _1;
+    let mut _0: u8;
+    let mut _2: *const u8;
+    let mut _3: *const u8;
+    let mut _4: *const u8;
+
+    bb0: {
+        _2 = core::slice::<impl [u8]>::as_ptr(copy _1) -> [return: bb1, unwind continue];
+    }
+
+    bb1: {
+        _3 = std::ptr::const_ptr::<impl *const u8>::wrapping_add(copy _2, const 100_usize) -> [return: bb2, unwind continue];
+    }
+
+    bb2: {
+        _4 = std::ptr::const_ptr::<impl *const u8>::wrapping_sub(copy _3, const 100_usize) -> [return: bb3, unwind continue];
+    }
+
+    bb3: {
+        _0 = std::ptr::read::<u8>(copy _3) -> [return: bb4, unwind continue];
+    }
+
+    bb4: {
+        return;
+    }
+}
+"#).unwrap();
+        let ctx = Context::new(&z3::Config::new());
+        let findings = SymbolicExecutor::new(&ctx).explore(&bodies).findings;
+
+        // Only the read through `_3` has to stay in bounds; `_4` is never accessed.
+        let found: Vec<_> = findings.iter().map(|f| (f.block, f.method.as_str(), f.offset)).collect();
+        assert_eq!(found, vec![(3, "wrapping_add", 100)]);
+    }
+
+    #[test]
+    fn test_dumped_pointer_offsets() {
+        let paths = ["mir_output/buffer_overflow_test.mir", "mir_dump/buffer_overflow_test.test_buffer_overflow.-------.nll.0.mir"];
+        for path in paths {
+            let bodies = parse_mir(&fs::read_to_string(path).unwrap()).unwrap();
+            let ctx = Context::new(&z3::Config::new());
+            let found = SymbolicExecutor::new(&ctx).with_unroll(12).explore(&bodies).findings;
+
+            let ptr = found.iter().find(|f| f.pointer_name.as_deref() == Some("ptr")).unwrap();
+            assert_eq!((ptr.method.as_str(), ptr.buffer_name.as_str()), ("add", "buffer"));
+            assert_eq!((ptr.offset, ptr.buffer_len), (8, 5));
+
+            let another = found.iter().find(|f| f.pointer_name.as_deref() == Some("another_ptr")).unwrap();
+            assert_eq!((another.method.as_str(), another.buffer_name.as_str()), ("offset", "small_buffer"));
+            assert_eq!((another.offset, another.buffer_len), (5, 3));
+        }
+
+        let bodies = parse_mir(&fs::read_to_string("mir_output/safe_buffer.mir").unwrap()).unwrap();
+        let ctx = Context::new(&z3::Config::new());
+        assert!(SymbolicExecutor::new(&ctx).explore(&bodies).findings.is_empty());
+    }
+}
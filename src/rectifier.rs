@@ -7,7 +7,7 @@ use std::path::PathBuf;
 use anyhow::{Result, Error};
 use std::fs;
 use regex::Regex;
-use syn::{ExprBinary, BinOp, ExprCall};
+use syn::{ExprBinary, BinOp, ExprCall, spanned::Spanned, visit::Visit};
 use proc_macro2::TokenStream;
 
 #[derive(Debug)]
@@ -24,6 +24,9 @@ pub enum FixType {
     VecResize,
     SafeAccess,
     UnsafeToSafe,
+    /// Replaces an unchecked call in place with its checked counterpart, e.g.
+    /// `get_unchecked(i)` with `get(i).expect(..)`.
+    CheckedCall,
 }
 
 #[derive(Debug)]
@@ -70,7 +73,10 @@ impl Rectifier {
             candidate.location.clone()
         };
     
-        let original_code = if line.contains("*ptr.add") {
+        // Accesses found through a call are fixed at the call that passes the buffer.
+        let original_code = if let Some(snippet) = candidate.span.snippet(&content) {
+            snippet.to_string()
+        } else if line.contains("*ptr.add") {
             let re = Regex::new(r"(?s)unsafe\s*\{[^{}]*\*ptr\.add\(\d+\)[^{}]*\}").unwrap();
             let mut unsafe_block = String::new();
             let mut in_unsafe = false;
@@ -104,45 +110,59 @@ impl Rectifier {
         };
     
         let fix_type = self.determine_fix_type(candidate, constraint);
-        let fixed_code = self.generate_fixed_code(candidate, constraint, &fix_type);
+        let fixed_code = self.generate_fixed_code(candidate, constraint, &fix_type, &original_code);
     
         Ok(CodeFix {
             original_code,
             fixed_code,
-            location: format!("Line {}", line_num),
+            location: candidate_location(candidate),
             fix_type,
         })
     }
     
-    fn generate_fixed_code(&self, _candidate: &OverflowCandidate, constraint: &BufferConstraint, fix_type: &FixType) -> String {
+    fn generate_fixed_code(&self, candidate: &OverflowCandidate, constraint: &BufferConstraint, fix_type: &FixType, original_code: &str) -> String {
+        let buffer = &candidate.buffer_name;
+        let index = candidate.offset_expr.clone().unwrap_or_else(|| constraint.offset.to_string());
         match fix_type {
             FixType::BoundCheck | FixType::UnsafeToSafe => {
                 format!(
-                    "if {} < buffer.len() {{\n    buffer[{}] = 42;\n}} else {{\n    panic!(\"Buffer overflow prevented: index {}\");\n}}",
-                    constraint.offset,
-                    constraint.offset,
-                    constraint.offset
+                    "if {} < {}.len() {{\n    {}[{}] = 42;\n}} else {{\n    panic!(\"Buffer overflow prevented: index {{}}\", {});\n}}",
+                    index,
+                    buffer,
+                    buffer,
+                    index,
+                    index
                 )
             },
             FixType::SafeAccess => {
                 format!(
-                    "if let Some(value) = buffer.get_mut({}) {{\n    *value = 42;\n}} else {{\n    panic!(\"Buffer overflow prevented: index {}\");\n}}",
-                    constraint.offset,
-                    constraint.offset
+                    "if let Some(value) = {}.get_mut({}) {{\n    *value = 42;\n}} else {{\n    panic!(\"Buffer overflow prevented: index {{}}\", {});\n}}",
+                    buffer,
+                    index,
+                    index
                 )
             },
+            FixType::CheckedCall => checked_call(candidate, original_code),
             FixType::VecResize => {
                 format!(
-                    "buffer.resize({} + 1, 0);\n    buffer[{}] = 42;",
-                    constraint.offset,
-                    constraint.offset
+                    "{}.resize({} + 1, 0);\n    {}[{}] = 42;",
+                    buffer,
+                    index,
+                    buffer,
+                    index
                 )
             },
         }
     }
     
     fn determine_fix_type(&self, candidate: &OverflowCandidate, _constraint: &BufferConstraint) -> FixType {
-        if candidate.operation.contains("unsafe") || candidate.operation.contains("pointer_offset") {
+        if candidate.callee_span.is_some() || matches!(
+            candidate.operation.as_str(),
+            "unchecked_index" | "unchecked_range" | "from_raw_parts" | "copy_src" | "copy_dst" | "copy_overlap" | "write_bytes" | "set_len" | "use_after_free"
+                | "dealloc_layout" | "double_reclaim" | "reclaim_len" | "reclaim_cap"
+        ) {
+            FixType::CheckedCall
+        } else if candidate.operation.contains("unsafe") || candidate.operation.contains("pointer_offset") {
             FixType::UnsafeToSafe
         } else {
             FixType::SafeAccess
@@ -211,22 +231,78 @@ impl Rectifier {
     }
 
     pub fn generate_error_report(&self, candidate: &OverflowCandidate) -> ErrorReport {
-        ErrorReport {
+        let mut report = ErrorReport {
             issue_type: candidate.operation.clone(),
-            location: format!("Line {}", candidate.line),
+            location: candidate_location(candidate),
             risk_level: match candidate.operation.as_str() {
-                "pointer_offset" => "Critical",
+                "pointer_offset" | "unchecked_index" | "unchecked_range" | "from_raw_parts" => "Critical",
+                "copy_src" | "copy_dst" | "copy_overlap" | "write_bytes" => "Critical",
+                "set_len" | "uninitialized_read" | "use_after_free" | "dealloc_layout" => "Critical",
+                "double_reclaim" | "reclaim_len" | "reclaim_cap" => "Critical",
                 "allocation" => "Medium",
                 _ => "Unknown"
             }.to_string(),
-            description: format!("检测到未检查的指针偏移操作: {:?}", candidate.offset),
+            description: match candidate.operation.as_str() {
+                "unchecked_index" | "unchecked_range" => format!("检测到未检查的索引访问: {}", access_text(candidate)),
+                "from_raw_parts" => format!("从原始指针构造的切片长度可能超出分配: {}", candidate.count_expr.as_deref().unwrap_or("?")),
+                "copy_src" => format!("内存复制的源端 {} 可能越界: {} 个元素", candidate.buffer_name, candidate.count_expr.as_deref().unwrap_or("?")),
+                "copy_dst" => format!("内存复制的目标端 {} 可能越界: {} 个元素", candidate.buffer_name, candidate.count_expr.as_deref().unwrap_or("?")),
+                "copy_overlap" => format!("copy_nonoverlapping 的源和目标在 {} 中可能重叠", candidate.buffer_name),
+                "write_bytes" => format!("write_bytes 写入 {} 可能越界: {} 个元素", candidate.buffer_name, candidate.count_expr.as_deref().unwrap_or("?")),
+                "set_len" => format!("set_len 设置的长度 {} 可能超出 {} 的容量", candidate.count_expr.as_deref().unwrap_or("?"), candidate.buffer_name),
+                "use_after_free" => match &candidate.invalidation {
+                    Some(invalidation) => format!(
+                        "指针 {} 在第 {} 行 {} 之后被使用，{} 的内存可能已被释放或移动",
+                        candidate.pointer_name.as_deref().unwrap_or("?"), invalidation.line, invalidation.operation, candidate.buffer_name
+                    ),
+                    None => format!("指针 {} 可能是悬垂指针", candidate.pointer_name.as_deref().unwrap_or("?")),
+                },
+                "uninitialized_read" => format!("读取 {} 中长度之外的未初始化元素: {}", candidate.buffer_name, access_text(candidate)),
+                "dealloc_layout" => format!(
+                    "{} 释放 {} 时使用的 Layout 大小 {} 与分配时的大小不一致",
+                    candidate.pointer_method.as_deref().unwrap_or("dealloc"), candidate.buffer_name, candidate.count_expr.as_deref().unwrap_or("?")
+                ),
+                "double_reclaim" => match &candidate.invalidation {
+                    Some(invalidation) => format!(
+                        "{} 再次接管指针 {} 的内存，而它自第 {} 行 {} 起已归 {} 所有",
+                        candidate.pointer_method.as_deref().unwrap_or("from_raw"), candidate.pointer_name.as_deref().unwrap_or("?"),
+                        invalidation.line, invalidation.operation, candidate.origin_buffer.as_deref().unwrap_or("临时值")
+                    ),
+                    None => format!("指针 {} 的内存可能被重复接管", candidate.pointer_name.as_deref().unwrap_or("?")),
+                },
+                "reclaim_len" => format!(
+                    "{} 的长度 {} 可能超出 {} 原有的容量 {}",
+                    candidate.pointer_method.as_deref().unwrap_or("from_raw_parts"), candidate.count_expr.as_deref().unwrap_or("?"),
+                    candidate.buffer_name, allocation_text(candidate)
+                ),
+                "reclaim_cap" => format!(
+                    "{} 的容量 {} 可能与 {} 原有的容量 {} 不一致",
+                    candidate.pointer_method.as_deref().unwrap_or("from_raw_parts"), candidate.count_expr.as_deref().unwrap_or("?"),
+                    candidate.buffer_name, allocation_text(candidate)
+                ),
+                _ => format!("检测到未检查的指针偏移操作: {}", access_text(candidate)),
+            },
             impact: match candidate.operation.as_str() {
-                "pointer_offset" => "可能导致未定义行为和内存访问违规".to_string(),
+                "pointer_offset" | "unchecked_index" | "unchecked_range" | "from_raw_parts"
+                | "copy_src" | "copy_dst" | "copy_overlap" | "write_bytes" => "可能导致未定义行为和内存访问违规".to_string(),
+                "set_len" | "uninitialized_read" => "读取未初始化内存是未定义行为".to_string(),
+                "use_after_free" => "释放后使用会读写已被回收的内存，导致未定义行为".to_string(),
+                "dealloc_layout" => "以不同的 Layout 释放内存是未定义行为，可能破坏分配器的状态".to_string(),
+                "double_reclaim" => "同一块内存会被两个所有者各释放一次（double free），导致未定义行为".to_string(),
+                "reclaim_len" => "长度超出容量的 Vec 会读写分配之外的内存，导致未定义行为".to_string(),
+                "reclaim_cap" => "Vec 释放时会以错误的容量计算 Layout，导致未定义行为".to_string(),
                 "allocation" => "潜在的内存安全风险".to_string(),
                 _ => "未知影响".to_string()
             },
             recommendation: "建议在进行指针操作前添加显式的边界检查".to_string(),
+        };
+        if let (Some(callee_span), Some(callee)) = (&candidate.callee_span, &candidate.callee) {
+            report.description.push_str(&format!(
+                "（{} 由 {} 调用 {} 时传入，访问位于 {}）",
+                candidate.buffer_name, candidate.location, callee, callee_span.location()
+            ));
         }
+        report
     }
 
     fn find_line_with_context(&self, lines: &[&str], _target: &str) -> Result<(usize, String)> {
@@ -239,6 +315,217 @@ impl Rectifier {
     }
 }
 
+/// Offset of an access as written, with the accessed length for ranges.
+fn access_text(candidate: &OverflowCandidate) -> String {
+    let offset = candidate.offset_expr.clone()
+        .or_else(|| candidate.offset.map(|offset| offset.to_string()))
+        .unwrap_or_else(|| "?".to_string());
+    match &candidate.count_expr {
+        Some(count) => format!("{} (length {})", offset, count),
+        None => offset,
+    }
+}
+
+/// Checked replacement for an unchecked call: `get`/`get_mut` for `get_unchecked` and
+/// its `_mut` form, slicing the source buffer for `slice::from_raw_parts`, and
+/// `copy_from_slice`/`fill` on sliced buffers for `ptr::copy` and `ptr::write_bytes`, a
+/// capacity assertion before `set_len`, a size assertion before freeing with a `Layout`
+/// or rebuilding a `Vec` from raw parts, and a pointer taken again after reallocation.
+fn checked_call(candidate: &OverflowCandidate, original: &str) -> String {
+    if candidate.callee_span.is_some() {
+        return guarded_call(candidate, original);
+    }
+    let method = candidate.pointer_method.as_deref().unwrap_or_default();
+    let len = candidate.count_expr.clone().unwrap_or_default();
+    let buffer = &candidate.buffer_name;
+    let cast = candidate.element_type.is_some();
+    // Derived pointers start past the beginning of their buffer.
+    let start = offset_text(candidate);
+    let range_from = |start: &str| if start == "0" { format!("..{}", len) } else { format!("{}..{} + {}", start, start, len) };
+    let range = range_from(&start);
+    match (candidate.operation.as_str(), &candidate.origin_buffer) {
+        ("unchecked_index" | "unchecked_range", _) => {
+            let checked = if method == "get_unchecked" { ".get(" } else { ".get_mut(" };
+            let call = original.replacen(&format!(".{}(", method), checked, 1);
+            format!("{}.expect(\"index out of bounds\")", call)
+        }
+        ("from_raw_parts", _) if !cast => {
+            let reference = if method == "from_raw_parts" { "&" } else { "&mut " };
+            format!("{}{}[{}]", reference, buffer, range)
+        }
+        ("set_len", _) => format!(
+            "{{\n    assert!({} <= {}.capacity(), \"length exceeds capacity\");\n    {}\n}}",
+            len, buffer, original
+        ),
+        // A reallocated buffer is still alive, so the pointer can be taken again.
+        ("use_after_free", _) if let Some(invalidation) = &candidate.invalidation
+            && !matches!(invalidation.operation.as_str(), "drop" | "end of scope" | "dealloc" | "realloc") =>
+        {
+            let pointer = candidate.pointer_name.as_deref().unwrap_or_default();
+            let mut fresh = match &candidate.element_type {
+                Some(ty) => format!("{}.as_mut_ptr().cast::<{}>()", buffer, ty),
+                None => format!("{}.as_mut_ptr()", buffer),
+            };
+            if start != "0" {
+                fresh = format!("{}.add({})", fresh, start);
+            }
+            replace_variable(original, pointer, &fresh).unwrap_or_else(|| original.to_string())
+        }
+        ("use_after_free", _) => {
+            let line = candidate.invalidation.as_ref().map(|invalidation| invalidation.line).unwrap_or_default();
+            format!("unreachable!(\"{} is used after {} was freed at line {}\")", candidate.pointer_name.as_deref().unwrap_or_default(), buffer, line)
+        }
+        ("double_reclaim", _) => {
+            let line = candidate.invalidation.as_ref().map(|invalidation| invalidation.line).unwrap_or_default();
+            format!("unreachable!(\"{} was already reclaimed at line {}\")", candidate.pointer_name.as_deref().unwrap_or_default(), line)
+        }
+        ("reclaim_len", _) => format!(
+            "{{\n    assert!({} <= {}, \"length exceeds the original capacity\");\n    {}\n}}",
+            len, allocation_text(candidate), original
+        ),
+        ("reclaim_cap", _) => format!(
+            "{{\n    assert!({} == {}, \"capacity differs from the original capacity\");\n    {}\n}}",
+            len, allocation_text(candidate), original
+        ),
+        // The allocation's own size, in the layout's elements.
+        ("dealloc_layout", _) => {
+            let size = allocation_text(candidate);
+            let ty = candidate.element_type.clone().or(candidate.buffer_element_type.clone()).unwrap_or_else(|| "_".to_string());
+            let buffer_ty = candidate.buffer_element_type.clone().unwrap_or_else(|| "_".to_string());
+            let align = match &candidate.layout_align {
+                Some((layout, allocation)) => format!(" && {} == {}", layout, allocation),
+                None => String::new(),
+            };
+            format!(
+                "{{\n    assert!(({}) * std::mem::size_of::<{}>() == ({}) * std::mem::size_of::<{}>(){}, \"layout does not match the allocation\");\n    {}\n}}",
+                len, ty, size, buffer_ty, align, original
+            )
+        }
+        // Overlapping ranges are fine for `ptr::copy`.
+        ("copy_overlap", _) => original.replacen("_nonoverlapping", "", 1),
+        ("copy_src" | "copy_dst", Some(other)) if !cast && let Some(other_start) = &candidate.origin_offset => {
+            let ((src, src_start), (dst, dst_start)) = if candidate.operation == "copy_src" {
+                ((buffer, &start), (other, other_start))
+            } else {
+                ((other, other_start), (buffer, &start))
+            };
+            if src == dst {
+                format!("{}.copy_within({}, {})", dst, range_from(src_start), dst_start)
+            } else {
+                format!("{}[{}].copy_from_slice(&{}[{}])", dst, range_from(dst_start), src, range_from(src_start))
+            }
+        }
+        ("write_bytes", _)
+            if !cast
+                && let Some(value) = write_bytes_value(original)
+                && fills_like_bytes(candidate.buffer_element_type.as_deref(), &value) =>
+        {
+            format!("{}[{}].fill({})", buffer, range, value)
+        }
+        // The pointer was cast, so the elements cannot be taken from the buffer directly.
+        _ => {
+            let ty = candidate.element_type.clone().or(candidate.buffer_element_type.clone()).unwrap_or_else(|| "_".to_string());
+            let buffer_ty = candidate.buffer_element_type.clone().unwrap_or_else(|| "_".to_string());
+            let len = if start == "0" { len } else { format!("{} + {}", start, len) };
+            format!(
+                "{{\n    assert!(({}) * std::mem::size_of::<{}>() <= {}.len() * std::mem::size_of::<{}>(), \"access exceeds its buffer\");\n    {}\n}}",
+                len, ty, buffer, buffer_ty, original
+            )
+        }
+    }
+}
+
+/// A call that passes a buffer too small for the callee, preceded by an assertion that the
+/// callee's access stays within it. The assertion only uses the call's arguments, so an
+/// access depending on the callee's locals is left unchanged.
+fn guarded_call(candidate: &OverflowCandidate, original: &str) -> String {
+    if !candidate.callee_locals.is_empty() {
+        return original.to_string();
+    }
+    let buffer = &candidate.buffer_name;
+    let offset = offset_text(candidate);
+    let condition = match (&candidate.element_type, &candidate.buffer_element_type, &candidate.count_expr) {
+        (None, _, Some(count)) => format!("{} + {} <= {}.len()", offset, count, buffer),
+        (None, _, None) => format!("{} < {}.len()", offset, buffer),
+        // The callee counts its offset in elements of another type than the buffer's.
+        (Some(ty), Some(buffer_ty), count) => format!(
+            "({} + {}) * std::mem::size_of::<{}>() <= {}.len() * std::mem::size_of::<{}>()",
+            offset, count.as_deref().unwrap_or("1"), ty, buffer, buffer_ty
+        ),
+        (Some(_), None, _) => return original.to_string(),
+    };
+    format!(
+        "{{\n    assert!({}, \"{} is too small for {}\");\n    {}\n}}",
+        condition, buffer, candidate.callee.as_deref().unwrap_or("the callee"), original
+    )
+}
+
+/// Size of the allocation a candidate's access is checked against, as source text.
+fn allocation_text(candidate: &OverflowCandidate) -> String {
+    candidate.buffer_size.map(|size| size.to_string())
+        .or(candidate.buffer_size_expr.clone())
+        .unwrap_or_else(|| "?".to_string())
+}
+
+/// Offset of a candidate's access as source text.
+fn offset_text(candidate: &OverflowCandidate) -> String {
+    candidate.offset_expr.clone()
+        .or_else(|| candidate.offset.map(|offset| offset.to_string()))
+        .unwrap_or_else(|| "0".to_string())
+}
+
+/// Byte value of a `ptr::write_bytes(dst, value, n)` or `dst.write_bytes(value, n)` call.
+fn write_bytes_value(call: &str) -> Option<String> {
+    let value = match syn::parse_str::<Expr>(call).ok()? {
+        Expr::Call(call) => call.args.into_iter().nth(1)?,
+        Expr::MethodCall(call) => call.args.into_iter().next()?,
+        _ => return None,
+    };
+    Some(value.to_token_stream().to_string())
+}
+
+/// Whether `fill(value)` on elements of type `element` writes the same bytes as
+/// `write_bytes` with `value`: always for byte elements, and only for zero on wider
+/// integers, where `write_bytes` repeats the byte in every byte of the element.
+fn fills_like_bytes(element: Option<&str>, value: &str) -> bool {
+    const INTEGERS: [&str; 10] = ["u16", "u32", "u64", "u128", "usize", "i16", "i32", "i64", "i128", "isize"];
+    match element {
+        Some("u8" | "i8") => true,
+        Some(ty) if INTEGERS.contains(&ty) => syn::parse_str::<syn::LitInt>(value).is_ok_and(|lit| lit.base10_digits() == "0"),
+        _ => false,
+    }
+}
+
+/// `code` with its first use of the variable `name` replaced by `replacement`. Only paths
+/// naming the variable are replaced, not e.g. the `t` in `std::ptr`.
+fn replace_variable(code: &str, name: &str, replacement: &str) -> Option<String> {
+    struct FirstUse<'n> {
+        name: &'n str,
+        range: Option<std::ops::Range<usize>>,
+    }
+    impl<'ast> Visit<'ast> for FirstUse<'_> {
+        fn visit_expr_path(&mut self, path: &'ast syn::ExprPath) {
+            if self.range.is_none() && path.qself.is_none() && path.path.is_ident(self.name) {
+                self.range = Some(path.span().byte_range());
+            }
+        }
+    }
+    let mut first = FirstUse { name, range: None };
+    first.visit_expr(&syn::parse_str::<Expr>(code).ok()?);
+    let mut replaced = code.to_string();
+    replaced.replace_range(first.range?, replacement);
+    Some(replaced)
+}
+
+/// Location string shared by fixes and reports so the two can be matched up.
+fn candidate_location(candidate: &OverflowCandidate) -> String {
+    if candidate.span.start_line > 0 {
+        candidate.span.location()
+    } else {
+        format!("Line {}", candidate.line)
+    }
+}
+
 fn fix_overflow(ast: &mut File, _overflow: &OverflowCandidate) {
     for item in &mut ast.items {
         if let Item::Fn(func) = item {
@@ -380,4 +667,213 @@ pub trait SafeLib<T> {
     fn checked_add(&self, y: T) -> Option<T>;
     fn checked_sub(&self, y: T) -> Option<T>;
     fn on_flow(self, err: Error) -> Result<T, Error>;
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{Invalidation, SourceSpan};
+
+    #[test]
+    fn test_checked_call() {
+        let unchecked = OverflowCandidate {
+            buffer_name: "data".to_string(),
+            operation: "unchecked_range".to_string(),
+            pointer_method: Some("get_unchecked_mut".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(checked_call(&unchecked, "data.get_unchecked_mut(a..b)"), "data.get_mut(a..b).expect(\"index out of bounds\")");
+
+        let raw_parts = OverflowCandidate {
+            buffer_name: "buf".to_string(),
+            operation: "from_raw_parts".to_string(),
+            pointer_method: Some("from_raw_parts".to_string()),
+            count_expr: Some("n".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(checked_call(&raw_parts, "std::slice::from_raw_parts(buf.as_ptr(), n)"), "&buf[..n]");
+        // A pointer derived from the buffer's start slices from its offset.
+        let derived = OverflowCandidate { offset_expr: Some("i".to_string()), ..raw_parts };
+        assert_eq!(checked_call(&derived, "std::slice::from_raw_parts(q, n)"), "&buf[i..i + n]");
+
+        let set_len = OverflowCandidate {
+            buffer_name: "v".to_string(),
+            operation: "set_len".to_string(),
+            count_expr: Some("12".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            checked_call(&set_len, "v.set_len(12)"),
+            "{\n    assert!(12 <= v.capacity(), \"length exceeds capacity\");\n    v.set_len(12)\n}"
+        );
+
+        let mut dangling = OverflowCandidate {
+            buffer_name: "v".to_string(),
+            pointer_name: Some("p".to_string()),
+            operation: "use_after_free".to_string(),
+            invalidation: Some(Invalidation { operation: "push".to_string(), line: 3, certain: true, conditions: Vec::new() }),
+            ..Default::default()
+        };
+        assert_eq!(checked_call(&dangling, "p.add(1)"), "v.as_mut_ptr().add(1)");
+        // Only the pointer itself is replaced, not the same letter in a path.
+        let short = OverflowCandidate { pointer_name: Some("t".to_string()), ..dangling.clone() };
+        assert_eq!(
+            checked_call(&short, "std::ptr::write_bytes(t, 0, 2)"),
+            "std::ptr::write_bytes(v.as_mut_ptr(), 0, 2)"
+        );
+        dangling.invalidation = Some(Invalidation { operation: "drop".to_string(), line: 3, certain: true, conditions: Vec::new() });
+        assert_eq!(checked_call(&dangling, "p.add(1)"), "unreachable!(\"p is used after v was freed at line 3\")");
+
+        let layout = OverflowCandidate {
+            buffer_name: "p".to_string(),
+            operation: "dealloc_layout".to_string(),
+            buffer_size_expr: Some("n".to_string()),
+            buffer_element_type: Some("u32".to_string()),
+            count_expr: Some("n + 1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            checked_call(&layout, "dealloc(p as *mut u8, layout)"),
+            "{\n    assert!((n + 1) * std::mem::size_of::<u32>() == (n) * std::mem::size_of::<u32>(), \"layout does not match the allocation\");\n    dealloc(p as *mut u8, layout)\n}"
+        );
+        let aligned = OverflowCandidate { layout_align: Some(("8".to_string(), "1".to_string())), ..layout };
+        assert_eq!(
+            checked_call(&aligned, "dealloc(p as *mut u8, layout)"),
+            "{\n    assert!((n + 1) * std::mem::size_of::<u32>() == (n) * std::mem::size_of::<u32>() && 8 == 1, \"layout does not match the allocation\");\n    dealloc(p as *mut u8, layout)\n}"
+        );
+
+        let rebuilt = OverflowCandidate {
+            buffer_name: "v".to_string(),
+            pointer_name: Some("p".to_string()),
+            operation: "reclaim_len".to_string(),
+            buffer_size: Some(4),
+            count_expr: Some("len".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            checked_call(&rebuilt, "Vec::from_raw_parts(p, len, 4)"),
+            "{\n    assert!(len <= 4, \"length exceeds the original capacity\");\n    Vec::from_raw_parts(p, len, 4)\n}"
+        );
+        let capacity = OverflowCandidate { operation: "reclaim_cap".to_string(), count_expr: Some("5".to_string()), ..rebuilt.clone() };
+        assert_eq!(
+            checked_call(&capacity, "Vec::from_raw_parts(p, len, 5)"),
+            "{\n    assert!(5 == 4, \"capacity differs from the original capacity\");\n    Vec::from_raw_parts(p, len, 5)\n}"
+        );
+        let twice = OverflowCandidate {
+            operation: "double_reclaim".to_string(),
+            invalidation: Some(Invalidation { operation: "Box::from_raw".to_string(), line: 5, certain: true, conditions: Vec::new() }),
+            ..rebuilt
+        };
+        assert_eq!(checked_call(&twice, "Box::from_raw(p)"), "unreachable!(\"p was already reclaimed at line 5\")");
+    }
+
+    #[test]
+    fn test_bulk_copy_fixes() {
+        let copy = OverflowCandidate {
+            buffer_name: "dst".to_string(),
+            operation: "copy_dst".to_string(),
+            pointer_method: Some("copy_nonoverlapping".to_string()),
+            count_expr: Some("n".to_string()),
+            origin_buffer: Some("src".to_string()),
+            origin_offset: Some("0".to_string()),
+            ..Default::default()
+        };
+        let call = "std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), n)";
+        assert_eq!(checked_call(&copy, call), "dst[..n].copy_from_slice(&src[..n])");
+        // Both sides keep their offsets.
+        let offsets = OverflowCandidate { offset_expr: Some("j".to_string()), origin_offset: Some("i".to_string()), ..copy.clone() };
+        assert_eq!(
+            checked_call(&offsets, "std::ptr::copy_nonoverlapping(s, d, n)"),
+            "dst[j..j + n].copy_from_slice(&src[i..i + n])"
+        );
+
+        // `q` is `p.add(2)`, both into `data`.
+        let within = OverflowCandidate {
+            buffer_name: "data".to_string(),
+            operation: "copy_src".to_string(),
+            count_expr: Some("4".to_string()),
+            offset: Some(2),
+            origin_buffer: Some("data".to_string()),
+            origin_offset: Some("0".to_string()),
+            ..copy.clone()
+        };
+        assert_eq!(checked_call(&within, "std::ptr::copy_nonoverlapping(q, p, 4)"), "data.copy_within(2..2 + 4, 0)");
+        // Without the other side's offset the copy is only checked.
+        let unknown = OverflowCandidate { origin_offset: None, ..within };
+        assert!(checked_call(&unknown, "std::ptr::copy_nonoverlapping(q, p, 4)").starts_with("{\n    assert!("));
+
+        let overlap = OverflowCandidate { operation: "copy_overlap".to_string(), ..copy.clone() };
+        assert_eq!(checked_call(&overlap, call), "std::ptr::copy(src.as_ptr(), dst.as_mut_ptr(), n)");
+
+        let write = OverflowCandidate {
+            operation: "write_bytes".to_string(),
+            pointer_method: Some("write_bytes".to_string()),
+            origin_buffer: None,
+            buffer_element_type: Some("u8".to_string()),
+            ..copy
+        };
+        assert_eq!(checked_call(&write, "std::ptr::write_bytes(dst.as_mut_ptr(), 0xff, n)"), "dst[..n].fill(0xff)");
+        // Every byte of a wider element is set to the value, so only zero fills alike.
+        let wide = OverflowCandidate { buffer_element_type: Some("u32".to_string()), ..write.clone() };
+        assert_eq!(checked_call(&wide, "std::ptr::write_bytes(dst.as_mut_ptr(), 0, n)"), "dst[..n].fill(0)");
+        assert!(checked_call(&wide, "std::ptr::write_bytes(dst.as_mut_ptr(), 1, n)").starts_with("{\n    assert!("));
+        // Zero is no value of a float or of an unknown element type.
+        let float = OverflowCandidate { buffer_element_type: Some("f32".to_string()), ..write.clone() };
+        assert!(checked_call(&float, "std::ptr::write_bytes(dst.as_mut_ptr(), 0, n)").starts_with("{\n    assert!("));
+        let unknown = OverflowCandidate { buffer_element_type: None, ..write };
+        assert!(checked_call(&unknown, "dst.as_mut_ptr().write_bytes(0xff, n)").starts_with("{\n    assert!("));
+    }
+
+    #[test]
+    fn test_guarded_call() {
+        let access = OverflowCandidate {
+            location: "main".to_string(),
+            buffer_name: "small_buffer".to_string(),
+            operation: "pointer_offset".to_string(),
+            offset: Some(8),
+            callee_span: Some(SourceSpan::default()),
+            callee: Some("process_buffer".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            checked_call(&access, "process_buffer(&mut small_buffer)"),
+            "{\n    assert!(8 < small_buffer.len(), \"small_buffer is too small for process_buffer\");\n    process_buffer(&mut small_buffer)\n}"
+        );
+
+        // The callee reads `u32`s from a buffer of bytes.
+        let cast = OverflowCandidate {
+            offset: None,
+            offset_expr: Some("(n + 1)".to_string()),
+            count_expr: Some("2".to_string()),
+            element_type: Some("u32".to_string()),
+            buffer_element_type: Some("u8".to_string()),
+            ..access.clone()
+        };
+        assert_eq!(
+            checked_call(&cast, "process_buffer(&mut small_buffer, n + 1)"),
+            "{\n    assert!(((n + 1) + 2) * std::mem::size_of::<u32>() <= small_buffer.len() * std::mem::size_of::<u8>(), \"small_buffer is too small for process_buffer\");\n    process_buffer(&mut small_buffer, n + 1)\n}"
+        );
+
+        // The loop variable of the callee does not exist at the call.
+        let local = OverflowCandidate {
+            offset: None,
+            offset_expr: Some("process_buffer_j".to_string()),
+            callee_locals: vec!["process_buffer_j".to_string()],
+            ..access
+        };
+        assert_eq!(checked_call(&local, "process_buffer(&mut small_buffer)"), "process_buffer(&mut small_buffer)");
+    }
+
+    #[test]
+    fn test_error_report_description() {
+        let rectifier = Rectifier::new(PathBuf::from("test.rs"));
+        let access = OverflowCandidate {
+            buffer_name: "buf".to_string(),
+            operation: "unsafe_ptr_offset".to_string(),
+            offset_expr: Some("i + 1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(rectifier.generate_error_report(&access).description, "检测到未检查的指针偏移操作: i + 1");
+        let constant = OverflowCandidate { offset: Some(8), offset_expr: None, ..access };
+        assert_eq!(rectifier.generate_error_report(&constant).description, "检测到未检查的指针偏移操作: 8");
+    }
+}
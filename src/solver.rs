@@ -1,4 +1,8 @@
-use z3::{Context, Solver, ast::Int};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use quote::ToTokens;
+use syn::{BinOp, Expr, ExprLit, Lit, Type, UnOp};
+use z3::{Context, Solver, ast::{Ast, Bool, BV}};
 
 use crate::analyzer::OverflowCandidate;
 
@@ -7,56 +11,1023 @@ pub struct BufferConstraint {
     pub buffer_size: u64,
     pub offset: u64,
     pub is_overflow: bool,
+    pub verdict: Verdict,
+    /// Values of the offset, the buffer length and the variables they depend on for one
+    /// out-of-bounds execution, when the solver found one.
+    pub witness: BTreeMap<String, i128>,
+    /// Whether the offset arithmetic wraps around in the witness execution.
+    pub wraps: bool,
+}
+
+/// Outcome of checking one access.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// No execution satisfying the path conditions goes out of bounds.
+    ProvenSafe,
+    /// Every execution that reaches the access goes out of bounds.
+    DefiniteOverflow,
+    /// Some executions go out of bounds; the witness is one of them, with booleans as `1`
+    /// and `0`.
+    PossibleOverflow { witness: BTreeMap<String, i128> },
+    /// The access could not be decided, e.g. its offset or buffer size is not known.
+    Unknown { reason: String },
+}
+
+impl Verdict {
+    pub fn is_overflow(&self) -> bool {
+        matches!(self, Verdict::DefiniteOverflow | Verdict::PossibleOverflow { .. })
+    }
+
+    /// Triage order: definite bugs first, proven-safe accesses last.
+    pub fn priority(&self) -> u8 {
+        match self {
+            Verdict::DefiniteOverflow => 0,
+            Verdict::PossibleOverflow { .. } => 1,
+            Verdict::Unknown { .. } => 2,
+            Verdict::ProvenSafe => 3,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::ProvenSafe => write!(f, "Proven safe"),
+            Verdict::DefiniteOverflow => write!(f, "Definite overflow"),
+            Verdict::PossibleOverflow { witness } if witness.is_empty() => write!(f, "Possible overflow"),
+            Verdict::PossibleOverflow { witness } => {
+                let values: Vec<String> = witness.iter().map(|(name, value)| format!("{} = {}", name, value)).collect();
+                write!(f, "Possible overflow (witness: {})", values.join(", "))
+            }
+            Verdict::Unknown { reason } => write!(f, "Unknown ({})", reason),
+        }
+    }
+}
+
+/// Operations whose offset has an unsigned type, so it cannot be negative.
+const UNSIGNED_OFFSET_OPERATIONS: [&str; 14] = [
+    "pointer_offset", "index", "unchecked_index", "unchecked_range", "from_raw_parts",
+    "copy_src", "copy_dst", "copy_overlap", "write_bytes", "set_len", "uninitialized_read", "dealloc_layout",
+    "reclaim_len", "reclaim_cap",
+];
+
+/// Width of `usize`/`isize` on the analyzed target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PointerWidth {
+    Bits32,
+    #[default]
+    Bits64,
+}
+
+impl PointerWidth {
+    pub fn bits(self) -> u32 {
+        match self {
+            PointerWidth::Bits32 => 32,
+            PointerWidth::Bits64 => 64,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct BufferSolver<'a> {
     ctx: &'a Context,
     solver: Solver<'a>,
-    buffer_vars: std::collections::HashMap<String, Int<'a>>,
+    width: PointerWidth,
+    buffer_sizes: HashMap<String, u64>,
 }
 
 impl<'a> BufferSolver<'a> {
     pub fn new(ctx: &'a Context) -> Self {
+        Self::with_pointer_width(ctx, PointerWidth::default())
+    }
+
+    /// Creates a solver whose offsets and sizes are `usize` values of the given width.
+    pub fn with_pointer_width(ctx: &'a Context, width: PointerWidth) -> Self {
         let solver = Solver::new(ctx);
         BufferSolver {
             ctx,
             solver,
-            buffer_vars: std::collections::HashMap::new(),
+            width,
+            buffer_sizes: HashMap::new(),
         }
     }
 
+    pub fn pointer_width(&self) -> PointerWidth {
+        self.width
+    }
+
     pub fn add_buffer(&mut self, name: &str, size: u64) {
-        let size_ast = Int::from_i64(self.ctx, size as i64);
-        self.buffer_vars.insert(name.to_string(), size_ast);
-    }
-
-    pub fn check_overflow(&mut self, candidate: &OverflowCandidate) -> BufferConstraint {
-        let buffer_size = candidate.buffer_size.unwrap_or(0) as u64;
-        let offset = candidate.offset.unwrap_or(0) as u64;
-        
-        if buffer_size == 0 || offset == 0 {
-            return BufferConstraint {
-                buffer_size,
-                offset,
-                is_overflow: true,
+        self.buffer_sizes.insert(name.to_string(), size);
+    }
+
+    /// Size of a buffer registered with `add_buffer`.
+    pub fn buffer_size(&self, name: &str) -> Option<u64> {
+        self.buffer_sizes.get(name).copied()
+    }
+
+    /// Checks an access at `offset` into the registered buffer `name`.
+    ///
+    /// An unregistered buffer has an unknown size, so the verdict is `Unknown`.
+    pub fn check_overflow(&mut self, name: &str, offset: u64) -> BufferConstraint {
+        let candidate = OverflowCandidate {
+            buffer_name: name.to_string(),
+            operation: "pointer_offset".to_string(),
+            buffer_size: self.buffer_size(name).map(|size| size as usize),
+            offset: Some(offset as usize),
+            ..Default::default()
+        };
+        self.check_candidate(&candidate)
+    }
+
+    /// Emits a standalone program that performs the access `offset` into the registered
+    /// buffer `name`, e.g. to reproduce a reported overflow under Miri.
+    pub fn generate_test_case(&mut self, name: &str, offset: u64) -> String {
+        let size = self.buffer_size(name).unwrap_or(0);
+        let verdict = self.check_overflow(name, offset).verdict;
+        format!(
+            "// {}: {} (size {}) accessed at offset {}\n\
+             fn main() {{\n\
+             \x20   let mut {} = vec![0u8; {}];\n\
+             \x20   let ptr = {}.as_mut_ptr();\n\
+             \x20   unsafe {{\n\
+             \x20       *ptr.add({}) = 42;\n\
+             \x20   }}\n\
+             \x20   println!(\"{{:?}}\", {});\n\
+             }}\n",
+            verdict, name, size, offset, name, size, name, offset, name
+        )
+    }
+
+    /// Values of the variables of the path conditions on an execution that reaches the
+    /// candidate after its invalidation ran under `conditions`, e.g. `flag = 1` for a box
+    /// freed in `if flag`. Conditions contradicting the access are dropped, as they may be
+    /// about variables of a loop that has ended.
+    fn reaching(&mut self, candidate: &OverflowCandidate, conditions: &[String]) -> BTreeMap<String, i128> {
+        let mut witness = BTreeMap::new();
+        for conditions in [conditions, &[]] {
+            let mut terms = Terms::new(self.ctx, self.width.bits(), &self.buffer_sizes, candidate);
+            let parsed = |conditions: &[String]| -> Vec<Expr> {
+                conditions.iter().filter_map(|condition| syn::parse_str::<Expr>(condition).ok()).collect()
+            };
+            let mut facts: Vec<Bool<'a>> = parsed(&candidate.path_conditions).iter()
+                .chain(&parsed(conditions))
+                .filter_map(|condition| terms.bool(condition))
+                .collect();
+            let shown: HashSet<String> = terms.vars.keys().chain(terms.flags.keys()).cloned().collect();
+            facts.extend(parsed(&candidate.assumptions).iter().filter_map(|condition| terms.bool(condition)));
+            self.solver.push();
+            for fact in facts.iter().chain(&terms.assumptions) {
+                self.solver.assert(fact);
+            }
+            for wraps in &terms.wraps {
+                self.solver.assert(&wraps.not());
+            }
+            let found = self.solver.check() == z3::SatResult::Sat;
+            if found && let Some(model) = self.solver.get_model() {
+                for (name, var) in terms.vars.iter().filter(|(name, _)| shown.contains(*name)) {
+                    if let Some(value) = model.eval(var, true).and_then(|value| value.as_u64()) {
+                        witness.insert(name.clone(), value as i128);
+                    }
+                }
+                for (name, var) in terms.flags.iter().filter(|(name, _)| shown.contains(*name)) {
+                    if let Some(value) = model.eval(var, true).and_then(|value| value.as_bool()) {
+                        witness.insert(name.clone(), value as i128);
+                    }
+                }
+            }
+            self.solver.pop(1);
+            if found {
+                break;
+            }
+        }
+        witness
+    }
+
+    /// Checks whether the access can go out of bounds on some execution that satisfies the
+    /// candidate's path conditions and assumptions, i.e. whether `offset >= len` (or
+    /// `offset < 0` for signed offsets) is satisfiable, and whether it does so on all of them.
+    ///
+    /// Offsets, sizes and guards are translated from their source text into bit-vectors of
+    /// the pointer width with `usize` semantics; variables become unknown values and
+    /// `x.len()` becomes the length of `x`. Arithmetic in the offset that wraps around counts
+    /// as a violation, while arithmetic in guards is assumed not to, since it would have
+    /// panicked before reaching the access. A guard that cannot be translated is dropped,
+    /// which can only add executions, so a `ProvenSafe` verdict stays sound. Accesses whose
+    /// offset cannot be translated, or whose buffer length is unknown and unconstrained, are
+    /// `Unknown`.
+    pub fn check_candidate(&mut self, candidate: &OverflowCandidate) -> BufferConstraint {
+        let mut constraint = BufferConstraint {
+            buffer_size: candidate.buffer_size.unwrap_or(0) as u64,
+            offset: candidate.offset.unwrap_or(0) as u64,
+            is_overflow: false,
+            verdict: Verdict::Unknown { reason: "offset is unknown".to_string() },
+            witness: BTreeMap::new(),
+            wraps: false,
+        };
+
+        if candidate.operation == "offset_from"
+            && let Some(origin) = &candidate.origin_buffer
+        {
+            // `offset_from` between two allocations is undefined whatever the offsets are.
+            constraint.verdict = if *origin == candidate.buffer_name { Verdict::ProvenSafe } else { Verdict::DefiniteOverflow };
+            constraint.is_overflow = constraint.verdict.is_overflow();
+            return constraint;
+        }
+
+        if (candidate.operation == "use_after_free" || candidate.operation == "double_reclaim")
+            && let Some(invalidation) = &candidate.invalidation
+        {
+            // Any access through a dangling pointer is undefined, whatever its offset, and
+            // so is freeing memory that already has an owner.
+            constraint.verdict = if invalidation.certain {
+                Verdict::DefiniteOverflow
+            } else {
+                Verdict::PossibleOverflow { witness: self.reaching(candidate, &invalidation.conditions) }
             };
+            constraint.is_overflow = true;
+            return constraint;
+        }
+
+        let width = self.width.bits();
+        let method = candidate.pointer_method.as_deref().unwrap_or("add");
+        let bytes = method.contains("byte_");
+        let backwards = method.ends_with("sub");
+        let signed = backwards
+            || method.ends_with("offset")
+            || !UNSIGNED_OFFSET_OPERATIONS.contains(&candidate.operation.as_str());
+        let mut terms = Terms::new(self.ctx, width, &self.buffer_sizes, candidate);
+        let offset_ast = match (candidate.offset, &candidate.offset_expr) {
+            (Some(offset), _) => Some(BV::from_u64(self.ctx, offset as u64, width)),
+            (None, Some(expr)) => syn::parse_str::<Expr>(expr).ok().and_then(|expr| terms.int(&expr)),
+            (None, None) => None,
+        };
+        let Some(offset_ast) = offset_ast else {
+            if let Some(expr) = &candidate.offset_expr {
+                constraint.verdict = Verdict::Unknown { reason: format!("offset `{}` is not supported", expr) };
+            }
+            return constraint;
+        };
+        // The interval analysis bounds the offset where the access executes, e.g. to the
+        // range a loop variable goes through.
+        let mut offset_bounds: Vec<Bool<'a>> = Vec::new();
+        if let Some(range) = &candidate.offset_range
+            && let Some(lo) = range.lo.and_then(|lo| u64::try_from(lo).ok())
+        {
+            offset_bounds.push(offset_ast.bvuge(&BV::from_u64(self.ctx, lo, width)));
+            if let Some(hi) = range.hi.and_then(|hi| u64::try_from(hi).ok()) {
+                offset_bounds.push(offset_ast.bvule(&BV::from_u64(self.ctx, hi, width)));
+            }
         }
-        
-        let buffer_size_ast = Int::from_i64(self.ctx, buffer_size as i64);
-        let offset_ast = Int::from_i64(self.ctx, offset as i64);
-        
-        let overflow_condition = offset_ast.ge(&buffer_size_ast);
-        
+        // `sub(n)` moves the pointer back by `n`.
+        let offset_ast = if backwards { offset_ast.bvneg() } else { offset_ast };
+        let count_ast = match &candidate.count_expr {
+            Some(expr) => match syn::parse_str::<Expr>(expr).ok().and_then(|expr| terms.int(&expr)) {
+                Some(count) => Some(count),
+                None => {
+                    constraint.verdict = Verdict::Unknown { reason: format!("length `{}` is not supported", expr) };
+                    return constraint;
+                }
+            },
+            None => None,
+        };
+        let mut offset_wraps: Vec<Bool<'a>> = std::mem::take(&mut terms.wraps);
+        let element_sizes = match element_sizes(candidate, self.width, bytes) {
+            Ok(sizes) => sizes,
+            Err(reason) => {
+                constraint.verdict = Verdict::Unknown { reason };
+                return constraint;
+            }
+        };
+        let buffer_size_ast = terms.len(&candidate.buffer_name);
+        let size_known = terms.len_is_known();
+
+        let mut facts: Vec<Bool<'a>> = candidate.path_conditions.iter()
+            .chain(&candidate.assumptions)
+            .filter_map(|condition| syn::parse_str::<Expr>(condition).ok())
+            .filter_map(|condition| terms.bool(&condition))
+            .collect();
+        facts.extend(terms.wraps.iter().map(|wraps| wraps.not()));
+        facts.extend(offset_bounds);
+        // A length only mentioned by the access itself says nothing about the buffer.
+        let overlap = candidate.operation == "copy_overlap";
+        // Memory must be freed with the layout it was allocated with, so the layout's size
+        // has to match the allocation exactly, as does the capacity given to `from_raw_parts`.
+        let exact = candidate.operation == "dealloc_layout" || candidate.operation == "reclaim_cap";
+        let size_constrained = size_known || terms.len_uses > 1 || overlap;
+        let mut violations = match element_sizes {
+            // The two ranges of a nonoverlapping copy overlap when the distance between
+            // their starts, given as the offset, is less than the number of elements.
+            _ if overlap => {
+                let count = count_ast.clone().unwrap_or_else(|| BV::from_u64(self.ctx, 1, width));
+                vec![offset_ast.bvult(&count)]
+            }
+            // The accessed bytes `[start, start + count * size)` must lie within the
+            // `len * buffer_size` bytes of the allocation, where `start` is the offset
+            // itself for the `byte_` methods and `offset * size` otherwise.
+            Some((size, buffer_size)) => {
+                let size = BV::from_u64(self.ctx, size, width);
+                let start = if bytes {
+                    offset_ast.clone()
+                } else {
+                    offset_wraps.push(offset_ast.bvmul_no_overflow(&size, signed).not());
+                    if signed {
+                        offset_wraps.push(offset_ast.bvmul_no_underflow(&size).not());
+                    }
+                    offset_ast.bvmul(&size)
+                };
+                let extent = match &count_ast {
+                    Some(count) => {
+                        offset_wraps.push(count.bvmul_no_overflow(&size, false).not());
+                        count.bvmul(&size)
+                    }
+                    None => size,
+                };
+                let end = start.bvadd(&extent);
+                let allocation = buffer_size_ast.bvmul(&BV::from_u64(self.ctx, buffer_size, width));
+                offset_wraps.push(start.bvadd_no_overflow(&extent, signed).not());
+                facts.push(buffer_size_ast.bvmul_no_overflow(&BV::from_u64(self.ctx, buffer_size, width), false));
+                if exact { vec![end._eq(&allocation).not()] } else { vec![end.bvugt(&allocation)] }
+            }
+            // A range of `count` elements may end exactly at the end of the buffer.
+            None => match &count_ast {
+                Some(count) => {
+                    offset_wraps.push(offset_ast.bvadd_no_overflow(count, false).not());
+                    let end = offset_ast.bvadd(count);
+                    if exact { vec![end._eq(&buffer_size_ast).not()] } else { vec![end.bvugt(&buffer_size_ast)] }
+                }
+                None => vec![offset_ast.bvuge(&buffer_size_ast)],
+            },
+        };
+        // The layout must also have the alignment the memory was allocated with.
+        if exact
+            && let Some((layout, allocation)) = &candidate.layout_align
+            && let (Some(layout), Some(allocation)) = (
+                syn::parse_str::<Expr>(layout).ok().and_then(|align| terms.int(&align)),
+                syn::parse_str::<Expr>(allocation).ok().and_then(|align| terms.int(&align)),
+            )
+        {
+            violations.push(layout._eq(&allocation).not());
+        }
+        if signed {
+            // A negative offset points below the start of the allocation.
+            violations.push(offset_ast.bvslt(&BV::from_u64(self.ctx, 0, width)));
+        }
+        let wraps = Bool::or(self.ctx, &offset_wraps.iter().collect::<Vec<_>>());
+        violations.push(wraps.clone());
+        let out_of_bounds = Bool::or(self.ctx, &violations.iter().collect::<Vec<_>>());
+
         self.solver.push();
-        self.solver.assert(&overflow_condition);
-        let is_overflow = self.solver.check() == z3::SatResult::Sat;
+        for fact in facts.iter().chain(&terms.assumptions) {
+            self.solver.assert(fact);
+        }
+
+        self.solver.push();
+        self.solver.assert(&out_of_bounds);
+        let result = self.solver.check();
+        if result == z3::SatResult::Sat
+            && let Some(model) = self.solver.get_model()
+        {
+            let value = |term: &BV<'a>| model.eval(term, true).and_then(|value| value.as_u64());
+            for (name, var) in &terms.vars {
+                if let Some(value) = value(var) {
+                    constraint.witness.insert(name.clone(), value as i128);
+                }
+            }
+            if let Some(offset) = value(&offset_ast) {
+                let shown = if signed { as_signed(offset, width) } else { offset as i128 };
+                constraint.witness.insert("offset".to_string(), shown);
+                if shown >= 0 {
+                    constraint.offset = offset;
+                }
+            }
+            if let Some(count) = count_ast.as_ref().and_then(&value) {
+                constraint.witness.insert("count".to_string(), count as i128);
+            }
+            if let Some(size) = value(&buffer_size_ast) {
+                constraint.witness.insert(format!("{}.len()", candidate.buffer_name), size as i128);
+                constraint.buffer_size = size;
+            }
+            constraint.wraps = model.eval(&wraps, true).and_then(|wraps| wraps.as_bool()).unwrap_or(false);
+        }
+        let reason_unknown = self.solver.get_reason_unknown();
         self.solver.pop(1);
 
-        BufferConstraint {
-            buffer_size,
-            offset,
-            is_overflow,
+        constraint.verdict = match result {
+            z3::SatResult::Unsat => Verdict::ProvenSafe,
+            z3::SatResult::Unknown => Verdict::Unknown {
+                reason: format!("solver gave up: {}", reason_unknown.unwrap_or_default()),
+            },
+            z3::SatResult::Sat if !size_constrained => Verdict::Unknown {
+                reason: format!("size of `{}` is unknown", candidate.buffer_name),
+            },
+            z3::SatResult::Sat => {
+                // Overflow is definite when no execution reaching the access stays in bounds.
+                self.solver.push();
+                self.solver.assert(&out_of_bounds.not());
+                let in_bounds = self.solver.check();
+                self.solver.pop(1);
+                if in_bounds == z3::SatResult::Unsat {
+                    Verdict::DefiniteOverflow
+                } else {
+                    Verdict::PossibleOverflow { witness: constraint.witness.clone() }
+                }
+            }
+        };
+        self.solver.pop(1);
+
+        constraint.is_overflow = constraint.verdict.is_overflow();
+        constraint
+    }
+}
+
+/// Byte sizes of the accessed element and of the buffer's elements, when the pointer was
+/// cast to a different element type or the offset counts bytes.
+fn element_sizes(candidate: &OverflowCandidate, width: PointerWidth, bytes: bool) -> Result<Option<(u64, u64)>, String> {
+    let cast = candidate.element_type.is_some() && candidate.element_type != candidate.buffer_element_type;
+    if !cast && !bytes {
+        return Ok(None);
+    }
+    let Some(element_type) = candidate.element_type.as_ref().or(candidate.buffer_element_type.as_ref()) else {
+        return Err(format!("element type of `{}` is unknown", candidate.buffer_name));
+    };
+    let size = type_size(element_type, width).ok_or_else(|| format!("size of `{}` is unknown", element_type))?;
+    let buffer_size = candidate.buffer_element_type.as_deref()
+        .and_then(|ty| type_size(ty, width))
+        .ok_or_else(|| format!("element type of `{}` is unknown", candidate.buffer_name))?;
+    Ok(Some((size, buffer_size)))
+}
+
+/// Size in bytes of a primitive, array, tuple or thin pointer type.
+fn type_size(ty: &str, width: PointerWidth) -> Option<u64> {
+    fn size(ty: &Type, width: PointerWidth) -> Option<u64> {
+        match ty {
+            Type::Path(path) => match path.path.get_ident()?.to_string().as_str() {
+                "u8" | "i8" | "bool" => Some(1),
+                "u16" | "i16" => Some(2),
+                "u32" | "i32" | "f32" | "char" => Some(4),
+                "u64" | "i64" | "f64" => Some(8),
+                "u128" | "i128" => Some(16),
+                "usize" | "isize" => Some(width.bits() as u64 / 8),
+                _ => None,
+            },
+            Type::Array(array) => match &array.len {
+                Expr::Lit(ExprLit { lit: Lit::Int(len), .. }) => size(&array.elem, width)?.checked_mul(len.base10_parse().ok()?),
+                _ => None,
+            },
+            // Only tuples whose fields need no padding, e.g. `(u8, u8)`.
+            Type::Tuple(tuple) => {
+                let sizes = tuple.elems.iter().map(|elem| size(elem, width)).collect::<Option<Vec<_>>>()?;
+                sizes.windows(2).all(|pair| pair[0] == pair[1]).then(|| sizes.iter().sum())
+            }
+            Type::Paren(paren) => size(&paren.elem, width),
+            // Pointers to sized types are thin.
+            Type::Ptr(ptr) if size(&ptr.elem, width).is_some() => Some(width.bits() as u64 / 8),
+            Type::Reference(reference) if size(&reference.elem, width).is_some() => Some(width.bits() as u64 / 8),
+            _ => None,
+        }
+    }
+    size(&syn::parse_str::<Type>(ty).ok()?, width)
+}
+
+/// Alignment in bytes of a primitive, array, tuple or thin pointer type.
+fn type_align(ty: &Type, width: PointerWidth) -> Option<u64> {
+    match ty {
+        Type::Array(array) => type_align(&array.elem, width),
+        Type::Paren(paren) => type_align(&paren.elem, width),
+        Type::Tuple(tuple) => tuple.elems.iter().try_fold(1, |align, elem| Some(align.max(type_align(elem, width)?))),
+        // Primitives and thin pointers are aligned to their size.
+        ty => type_size(&ty.to_token_stream().to_string(), width),
+    }
+}
+
+/// Reads a `width`-bit value as two's complement.
+fn as_signed(value: u64, width: u32) -> i128 {
+    let value = value as i128;
+    if value >> (width - 1) & 1 == 1 { value - (1i128 << width) } else { value }
+}
+
+/// Translates source expressions over one candidate into Z3 bit-vectors.
+struct Terms<'a, 'b> {
+    ctx: &'a Context,
+    width: u32,
+    buffers: &'b HashMap<String, u64>,
+    candidate: &'b OverflowCandidate,
+    vars: HashMap<String, BV<'a>>,
+    /// Boolean variables used as conditions, e.g. `flag` in `if flag`.
+    flags: HashMap<String, Bool<'a>>,
+    /// Facts about the terms introduced so far, e.g. that lengths fit in `isize`.
+    assumptions: Vec<Bool<'a>>,
+    /// Conditions under which the arithmetic translated so far wraps around.
+    wraps: Vec<Bool<'a>>,
+    /// Length of the candidate's buffer once it has been built.
+    buffer_len: Option<BV<'a>>,
+    /// How many times the candidate buffer's length was referenced.
+    len_uses: usize,
+}
+
+impl<'a, 'b> Terms<'a, 'b> {
+    fn new(ctx: &'a Context, width: u32, buffers: &'b HashMap<String, u64>, candidate: &'b OverflowCandidate) -> Self {
+        Terms {
+            ctx,
+            width,
+            buffers,
+            candidate,
+            vars: HashMap::new(),
+            flags: HashMap::new(),
+            assumptions: Vec::new(),
+            wraps: Vec::new(),
+            buffer_len: None,
+            len_uses: 0,
+        }
+    }
+
+    fn constant(&self, value: u64) -> BV<'a> {
+        BV::from_u64(self.ctx, value, self.width)
+    }
+
+    fn var(&mut self, name: &str) -> BV<'a> {
+        if let Some(var) = self.vars.get(name) {
+            return var.clone();
+        }
+        let var = BV::new_const(self.ctx, name, self.width);
+        self.vars.insert(name.to_string(), var.clone());
+        var
+    }
+
+    /// Length of a buffer: its known or symbolic size, a registered buffer, or an unknown
+    /// length.
+    fn len(&mut self, buffer: &str) -> BV<'a> {
+        if buffer == self.candidate.buffer_name {
+            self.len_uses += 1;
+            if let Some(len) = &self.buffer_len {
+                return len.clone();
+            }
+            let len = match (self.candidate.buffer_size, &self.candidate.buffer_size_expr) {
+                (Some(size), _) => Some(self.constant(size as u64)),
+                (None, Some(expr)) => {
+                    // Guards against a size expression that mentions the buffer itself.
+                    self.buffer_len = Some(self.unknown_len(buffer));
+                    let start = self.wraps.len();
+                    let len = syn::parse_str::<Expr>(expr).ok().and_then(|expr| self.int(&expr));
+                    // An allocation whose size wraps around panics before the access.
+                    let wraps: Vec<Bool<'a>> = self.wraps.drain(start..).collect();
+                    self.assumptions.extend(wraps.iter().map(|wraps| wraps.not()));
+                    len
+                }
+                (None, None) => None,
+            };
+            let len = len
+                .or_else(|| self.buffers.get(buffer).map(|size| self.constant(*size)))
+                .unwrap_or_else(|| self.unknown_len(buffer));
+            self.buffer_len = Some(len.clone());
+            return len;
+        }
+        if let Some(size) = self.buffers.get(buffer) {
+            return self.constant(*size);
+        }
+        self.unknown_len(buffer)
+    }
+
+    /// Whether the candidate buffer has a known, symbolic or registered size.
+    fn len_is_known(&self) -> bool {
+        self.candidate.buffer_size.is_some()
+            || self.candidate.buffer_size_expr.is_some()
+            || self.buffers.contains_key(&self.candidate.buffer_name)
+    }
+
+    fn unknown_len(&mut self, buffer: &str) -> BV<'a> {
+        self.unknown_size(format!("{}.len()", buffer))
+    }
+
+    /// A length or capacity that is only known to fit in an allocation.
+    fn unknown_size(&mut self, name: String) -> BV<'a> {
+        let known = self.vars.contains_key(&name);
+        let len = self.var(&name);
+        if !known {
+            // No allocation is larger than `isize::MAX` bytes.
+            let max = self.constant(u64::MAX >> (65 - self.width));
+            self.assumptions.push(len.bvule(&max));
+        }
+        len
+    }
+
+    fn int(&mut self, expr: &Expr) -> Option<BV<'a>> {
+        match expr {
+            Expr::Lit(lit) => match &lit.lit {
+                Lit::Int(int) => Some(self.constant(int.base10_parse().ok()?)),
+                _ => None,
+            },
+            Expr::Path(path) => Some(self.var(&path.path.get_ident()?.to_string())),
+            Expr::Paren(paren) => self.int(&paren.expr),
+            Expr::Group(group) => self.int(&group.expr),
+            Expr::Cast(cast) => self.int(&cast.expr),
+            Expr::Unary(unary) => match unary.op {
+                UnOp::Deref(_) => self.int(&unary.expr),
+                UnOp::Neg(_) => Some(self.int(&unary.expr)?.bvneg()),
+                _ => None,
+            },
+            Expr::MethodCall(call) if call.method == "len" && call.args.is_empty() => {
+                Some(self.len(&place_name(&call.receiver)?))
+            }
+            // `std::mem::align_of::<T>()`
+            Expr::Call(call) if call.args.is_empty()
+                && let Expr::Path(func) = &*call.func
+                && let Some(segment) = func.path.segments.last()
+                && segment.ident == "align_of"
+                && let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+                && let Some(syn::GenericArgument::Type(ty)) = args.args.first() =>
+            {
+                let width = if self.width == 32 { PointerWidth::Bits32 } else { PointerWidth::Bits64 };
+                Some(self.constant(type_align(ty, width)?))
+            }
+            Expr::MethodCall(call) if call.method == "capacity" && call.args.is_empty() => {
+                Some(self.unknown_size(format!("{}.capacity()", place_name(&call.receiver)?)))
+            }
+            Expr::Binary(binary) => {
+                let left = self.int(&binary.left)?;
+                let right = self.int(&binary.right)?;
+                let (value, in_range) = match binary.op {
+                    BinOp::Add(_) => (left.bvadd(&right), left.bvadd_no_overflow(&right, false)),
+                    BinOp::Sub(_) => (left.bvsub(&right), left.bvsub_no_underflow(&right, false)),
+                    BinOp::Mul(_) => (left.bvmul(&right), left.bvmul_no_overflow(&right, false)),
+                    BinOp::Div(_) => return Some(left.bvudiv(&right)),
+                    BinOp::Rem(_) => return Some(left.bvurem(&right)),
+                    _ => return None,
+                };
+                self.wraps.push(in_range.not());
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    fn bool(&mut self, expr: &Expr) -> Option<Bool<'a>> {
+        match expr {
+            Expr::Lit(lit) => match &lit.lit {
+                Lit::Bool(value) => Some(Bool::from_bool(self.ctx, value.value)),
+                _ => None,
+            },
+            Expr::Paren(paren) => self.bool(&paren.expr),
+            Expr::Group(group) => self.bool(&group.expr),
+            Expr::Path(path) => {
+                let name = path.path.get_ident()?.to_string();
+                let ctx = self.ctx;
+                Some(self.flags.entry(name.clone()).or_insert_with(|| Bool::new_const(ctx, name)).clone())
+            }
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Not(_)) => Some(self.bool(&unary.expr)?.not()),
+            Expr::Binary(binary) => match binary.op {
+                BinOp::And(_) => Some(Bool::and(self.ctx, &[&self.bool(&binary.left)?, &self.bool(&binary.right)?])),
+                BinOp::Or(_) => Some(Bool::or(self.ctx, &[&self.bool(&binary.left)?, &self.bool(&binary.right)?])),
+                _ => {
+                    let left = self.int(&binary.left)?;
+                    let right = self.int(&binary.right)?;
+                    match binary.op {
+                        BinOp::Lt(_) => Some(left.bvult(&right)),
+                        BinOp::Le(_) => Some(left.bvule(&right)),
+                        BinOp::Gt(_) => Some(left.bvugt(&right)),
+                        BinOp::Ge(_) => Some(left.bvuge(&right)),
+                        BinOp::Eq(_) => Some(left._eq(&right)),
+                        BinOp::Ne(_) => Some(left._eq(&right).not()),
+                        _ => None,
+                    }
+                }
+            },
+            _ => None,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Name of a buffer variable or field, e.g. `buf` or `self.buf`.
+fn place_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) => Some(path.path.get_ident()?.to_string()),
+        Expr::Field(field) => match &field.member {
+            syn::Member::Named(member) => Some(format!("{}.{}", place_name(&field.base)?, member)),
+            syn::Member::Unnamed(index) => Some(format!("{}.{}", place_name(&field.base)?, index.index)),
+        },
+        Expr::Paren(paren) => place_name(&paren.expr),
+        _ => None,
+    }
+}
+
+/// Candidates the analyzer finds in `code`, each with the constraint the solver gives it
+/// at `width`.
+#[cfg(test)]
+pub(crate) fn check_source(code: &str, width: PointerWidth) -> Vec<(OverflowCandidate, BufferConstraint)> {
+    let ast = syn::parse_file(code).unwrap();
+    let ctx = Context::new(&z3::Config::new());
+    let mut solver = BufferSolver::with_pointer_width(&ctx, width);
+    crate::analyzer::find_buffer_overflows(&ast, Vec::new())
+        .into_iter()
+        .map(|candidate| {
+            let constraint = solver.check_candidate(&candidate);
+            (candidate, constraint)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guarded_access_is_safe() {
+        let found = check_source(r#"
+            fn write(offset: usize) {
+                let mut buffer = vec![0u8; 5];
+                let ptr = buffer.as_mut_ptr();
+                unsafe {
+                    if offset < buffer.len() {
+                        *ptr.add(offset) = 1;
+                    } else {
+                        *ptr.add(offset) = 2;
+                    }
+                }
+            }
+        "#, PointerWidth::default());
+        assert_eq!(found.len(), 2);
+        assert!(!found[0].1.is_overflow);
+        assert!(found[1].1.is_overflow);
+    }
+
+    #[test]
+    fn test_guard_of_reassigned_offset() {
+        let found = check_source(r#"
+            fn write(mut i: usize, j: usize, k: usize) {
+                let mut buffer = vec![0u8; 16];
+                let p = buffer.as_mut_ptr();
+                if i < buffer.len() {
+                    i += 10;
+                    unsafe { *p.add(i) }
+                }
+                let mut j = j;
+                if j < 16 {
+                    bump(&mut j);
+                    unsafe { *p.add(j) = 1; }
+                }
+                if k < 16 {
+                    let k = k.wrapping_add(10);
+                    unsafe { *p.add(k) = 1; }
+                }
+            }
+        "#, PointerWidth::default());
+        // None of the guards holds any more at the access.
+        assert_eq!(found.iter().map(|f| f.1.is_overflow).collect::<Vec<_>>(), vec![true, true, true]);
+    }
+
+    #[test]
+    fn test_early_return_and_assert_guard() {
+        let found = check_source(r#"
+            fn write(i: usize, j: usize) {
+                let mut buffer = vec![0u8; 8];
+                let ptr = buffer.as_mut_ptr();
+                if i >= buffer.len() {
+                    return;
+                }
+                assert!(j + 1 < 8);
+                unsafe {
+                    *ptr.add(i) = 1;
+                    *ptr.add(j + 1) = 2;
+                    *ptr.add(j + 2) = 3;
+                }
+            }
+        "#, PointerWidth::default());
+        assert_eq!(found.iter().map(|f| f.1.is_overflow).collect::<Vec<_>>(), vec![false, false, true]);
+    }
+
+    #[test]
+    fn test_match_guard() {
+        let found = check_source(r#"
+            fn write(i: usize) {
+                let mut buffer = vec![0u8; 4];
+                let ptr = buffer.as_mut_ptr();
+                unsafe {
+                    match i {
+                        0..=3 => *ptr.add(i) = 1,
+                        n if n < 10 => *ptr.add(i) = 2,
+                        _ => {}
+                    }
+                }
+            }
+        "#, PointerWidth::default());
+        assert_eq!(found.iter().map(|f| f.1.is_overflow).collect::<Vec<_>>(), vec![false, true]);
+    }
+
+    #[test]
+    fn test_symbolic_size_and_loop_bounds() {
+        let code = r#"
+            fn fill(n: usize) {
+                let mut buffer = vec![0u8; n];
+                let ptr = buffer.as_mut_ptr();
+                unsafe {
+                    for i in 0..n {
+                        *ptr.add(i) = 1;
+                    }
+                    for j in 0..=n {
+                        *ptr.add(j) = 2;
+                    }
+                }
+            }
+        "#;
+        let found = check_source(code, PointerWidth::default());
+
+        assert!(!found[0].1.is_overflow);
+        let constraint = &found[1].1;
+        assert!(constraint.is_overflow);
+        assert_eq!(constraint.witness.get("j"), constraint.witness.get("n"));
+        assert_eq!(constraint.offset, constraint.buffer_size);
+    }
+
+    #[test]
+    fn test_let_bound_offset_witness() {
+        let code = r#"
+            fn write(i: usize) {
+                let mut buffer = vec![0u8; 4];
+                let ptr = buffer.as_mut_ptr();
+                let idx = i + 1;
+                if i < 4 {
+                    unsafe { *ptr.add(idx) = 1; }
+                }
+            }
+        "#;
+        let found = check_source(code, PointerWidth::default());
+
+        let constraint = &found[0].1;
+        assert!(constraint.is_overflow);
+        assert_eq!(constraint.witness.get("i"), Some(&3));
+        assert_eq!(constraint.witness.get("offset"), Some(&4));
+    }
+
+    #[test]
+    fn test_shadowing_let_bindings() {
+        let found = check_source(r#"
+            fn write(len: usize, i: usize) {
+                let mut buffer = vec![0u8; 4];
+                let ptr = buffer.as_mut_ptr();
+                let len = len + 1;
+                let idx = 2;
+                unsafe {
+                    *ptr.add(len) = 1;
+                    *ptr.add(idx) = 1;
+                }
+                let idx = i + 1;
+                unsafe { *ptr.add(idx) = 1; }
+            }
+        "#, PointerWidth::default());
+        // `len == len + 1` would hold for no value, and `idx == 2` is about the first `idx`.
+        assert_eq!(found.iter().map(|f| f.1.is_overflow).collect::<Vec<_>>(), vec![true, false, true]);
+    }
+
+    #[test]
+    fn test_complex_overflow_example() {
+        let code = std::fs::read_to_string("examples/complex_overflow.rs").unwrap();
+        let found = check_source(&code, PointerWidth::default());
+        // The first `small_ptr` access is dominated by `offset < small_buffer.len()`.
+        assert!(!found[0].1.is_overflow);
+        assert!(found[1].1.is_overflow);
+        // `main` passes the 5-element `small_buffer` to `process_buffer`, which writes at 8.
+        assert!(found[3].1.is_overflow);
+        // On its own, `process_buffer` writes at 8 into a `&mut Vec<u8>` that may be shorter.
+        assert!(found[4].1.is_overflow);
+        // `offsets` holds no value above 12, within the 20 elements of `large_buffer`.
+        assert!(!found[2].1.is_overflow);
+    }
+
+    #[test]
+    fn test_verdicts() {
+        let found: Vec<Verdict> = check_source(r#"
+            fn write(i: usize, buf: &mut Vec<u8>, table: &[usize]) {
+                let mut buffer = vec![0u8; 5];
+                let ptr = buffer.as_mut_ptr();
+                let other = buf.as_mut_ptr();
+                unsafe {
+                    *ptr.add(0) = 1;
+                    *ptr.add(8) = 1;
+                    if i <= buffer.len() {
+                        *ptr.add(i) = 1;
+                    }
+                    *other.add(3) = 1;
+                    *ptr.add(table[0]) = 1;
+                }
+            }
+        "#, PointerWidth::default())
+            .into_iter()
+            .map(|(_, constraint)| constraint.verdict)
+            .collect();
+        assert_eq!(found[0], Verdict::ProvenSafe);
+        assert_eq!(found[1], Verdict::DefiniteOverflow);
+        assert!(matches!(&found[2], Verdict::PossibleOverflow { witness } if witness.get("i") == Some(&5)));
+        // `buf` has the symbolic length `buf.len()`, which may be too short.
+        assert!(matches!(&found[3], Verdict::PossibleOverflow { witness } if witness.get("buf.len()").is_some_and(|len| *len <= 3)));
+        assert!(matches!(&found[4], Verdict::Unknown { reason } if reason.contains("table[0]")));
+    }
+
+    #[test]
+    fn test_index_arithmetic_wraps() {
+        let found = check_source(r#"
+            fn last(n: usize, i: usize, stride: usize) {
+                let mut buffer = vec![0u8; n];
+                let ptr = buffer.as_mut_ptr();
+                unsafe {
+                    *ptr.add(buffer.len() - 1) = 1;
+                    if n > 0 {
+                        *ptr.add(n - 1) = 2;
+                    }
+                    if n == 64 {
+                        *ptr.add(i * stride % 64) = 3;
+                    }
+                }
+            }
+        "#, PointerWidth::Bits64);
+        assert!(found[0].1.is_overflow && found[0].1.wraps);
+        assert_eq!(found[0].1.witness.get("n"), Some(&0));
+        assert_eq!(found[1].1.verdict, Verdict::ProvenSafe);
+        assert!(found[2].1.is_overflow && found[2].1.wraps);
+    }
+
+    #[test]
+    fn test_pointer_width() {
+        let code = r#"
+            fn write(i: usize) {
+                let mut buffer = vec![0u8; 64];
+                let ptr = buffer.as_mut_ptr();
+                if i < 8 {
+                    unsafe { *ptr.add(i * 0x4000_0000 % 64) = 1; }
+                }
+            }
+        "#;
+        assert_eq!(check_source(code, PointerWidth::Bits64)[0].1.verdict, Verdict::ProvenSafe);
+        let found = check_source(code, PointerWidth::Bits32);
+        assert!(found[0].1.is_overflow && found[0].1.wraps);
+        assert!(found[0].1.witness["i"] >= 4);
+    }
+
+    #[test]
+    fn test_byte_range_after_pointer_cast() {
+        let found: Vec<Verdict> = check_source(r#"
+            fn write() {
+                let mut buf = vec![0u8; 10];
+                let words: Vec<u32> = vec![0; 4];
+                let mut unknown = vec![0; 4];
+                unsafe {
+                    *(buf.as_mut_ptr() as *mut u32).add(3) = 1;
+                    *(buf.as_mut_ptr() as *mut u32).add(1) = 1;
+                    *buf.as_mut_ptr().cast::<u16>().add(4) = 1;
+                    let _ = *(words.as_ptr() as *const u8).add(15);
+                    let _ = *(words.as_ptr() as *const [u8; 8]).add(2);
+                    *(unknown.as_mut_ptr() as *mut u8).add(1) = 1;
+                }
+            }
+        "#, PointerWidth::default())
+            .into_iter()
+            .map(|(_, constraint)| constraint.verdict)
+            .collect();
+        assert_eq!(found[0], Verdict::DefiniteOverflow);
+        assert_eq!(found[1], Verdict::ProvenSafe);
+        assert_eq!(found[2], Verdict::ProvenSafe);
+        assert_eq!(found[3], Verdict::ProvenSafe);
+        assert_eq!(found[4], Verdict::DefiniteOverflow);
+        assert_eq!(found[5], Verdict::Unknown { reason: "element type of `unknown` is unknown".to_string() });
+        assert_eq!(type_size("[u32; 4]", PointerWidth::Bits32), Some(16));
+        assert_eq!(type_size("*const usize", PointerWidth::Bits32), Some(4));
+    }
+
+    #[test]
+    fn test_pointer_methods() {
+        let found: Vec<Verdict> = check_source(r#"
+            fn shift(i: usize) {
+                let mut buf = vec![0u32; 4];
+                let mut other = vec![0u32; 4];
+                let p = buf.as_mut_ptr();
+                let q = other.as_mut_ptr();
+                unsafe {
+                    *p.offset(-1) = 1;
+                    *p.offset(3) = 1;
+                    *p.sub(1) = 1;
+                    *p.wrapping_add(4) = 1;
+                    *p.byte_add(12) = 1;
+                    *p.byte_add(13) = 1;
+                    *<*mut u32>::add(p, 5) = 1;
+                    if i < 4 {
+                        *p.sub(i) = 1;
+                    }
+                    let _ = p.offset_from(q);
+                    let _ = p.offset_from(p);
+                }
+            }
+        "#, PointerWidth::default())
+            .into_iter()
+            .map(|(_, constraint)| constraint.verdict)
+            .collect();
+        assert_eq!(found.len(), 9);
+        assert_eq!(found[0], Verdict::DefiniteOverflow);
+        assert_eq!(found[1], Verdict::ProvenSafe);
+        assert_eq!(found[2], Verdict::DefiniteOverflow);
+        assert_eq!(found[3], Verdict::DefiniteOverflow);
+        assert_eq!(found[4], Verdict::ProvenSafe);
+        assert_eq!(found[5], Verdict::DefiniteOverflow);
+        assert_eq!(found[6], Verdict::DefiniteOverflow);
+        assert!(matches!(&found[7], Verdict::PossibleOverflow { witness } if witness["offset"] < 0 && witness["offset"] == -witness["i"]));
+        assert_eq!(found[8], Verdict::DefiniteOverflow);
+    }
+}